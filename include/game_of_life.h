@@ -0,0 +1,185 @@
+/* C interface of homomorphic_game_of_life_2, see src/capi.rs. */
+
+#ifndef GAME_OF_LIFE_H
+#define GAME_OF_LIFE_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum GolStatus {
+  GolStatus_Ok = 0,
+  /**
+   * A null pointer, a path that is not UTF-8, or cells that do not make a
+   * board.
+   */
+  GolStatus_InvalidArgument = 1,
+  /**
+   * A file could not be read or written, or does not hold what was
+   * expected.
+   */
+  GolStatus_File = 2,
+  /**
+   * The bitmask is too small for the board.
+   */
+  GolStatus_BufferTooSmall = 3,
+  GolStatus_Panic = 4,
+} GolStatus;
+
+/**
+ * An encrypted board.
+ */
+typedef struct GolBoard GolBoard;
+
+/**
+ * The client and server keys.
+ */
+typedef struct GolKeys GolKeys;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The message of the last error of the calling thread, or null if no call
+ * failed yet. The string is owned by the library and valid until the next
+ * failing call on the thread.
+ */
+const char *gol_last_error(void);
+
+/**
+ * Generates new keys.
+ *
+ * # Safety
+ *
+ * `out` must be valid for writes.
+ */
+enum GolStatus gol_keys_generate(struct GolKeys **out);
+
+/**
+ * Reads keys written by `gol_keys_save`, or by the `--keys` option.
+ *
+ * # Safety
+ *
+ * `path` must be a C string and `out` valid for writes.
+ */
+enum GolStatus gol_keys_load(const char *path, struct GolKeys **out);
+
+/**
+ * Writes the keys to a file only readable by its owner.
+ *
+ * # Safety
+ *
+ * `keys` must come from `gol_keys_generate` or `gol_keys_load` and `path`
+ * must be a C string.
+ */
+enum GolStatus gol_keys_save(const struct GolKeys *keys, const char *path);
+
+/**
+ * Releases keys. Null is ignored.
+ *
+ * # Safety
+ *
+ * `keys` must be null or come from `gol_keys_generate` or `gol_keys_load`,
+ * and not be used afterwards.
+ */
+void gol_keys_free(struct GolKeys *keys);
+
+/**
+ * Encrypts a board of `len` cells in rows of `n_cols`, in row-major order,
+ * a cell being alive when its byte is not 0.
+ *
+ * # Safety
+ *
+ * `keys` must be a key handle, `cells` valid for `len` bytes and `out`
+ * valid for writes.
+ */
+enum GolStatus gol_board_new(const struct GolKeys *keys,
+                             const uint8_t *cells,
+                             uintptr_t len,
+                             uintptr_t n_cols,
+                             struct GolBoard **out);
+
+/**
+ * Writes the number of rows and columns of the board.
+ *
+ * # Safety
+ *
+ * `board` must be a board handle, `n_rows` and `n_cols` valid for writes.
+ */
+enum GolStatus gol_board_dimensions(const struct GolBoard *board,
+                                    uintptr_t *n_rows,
+                                    uintptr_t *n_cols);
+
+/**
+ * Plays `n` generations of the board with the server key.
+ *
+ * # Safety
+ *
+ * `keys` must be a key handle and `board` a board handle encrypted with
+ * them.
+ */
+enum GolStatus gol_board_step(const struct GolKeys *keys, struct GolBoard *board, uintptr_t n);
+
+/**
+ * Writes the bits of the decrypted cells to `bitmask`, in row-major order,
+ * the least significant bit of every byte first. `len` must be at least
+ * the number of cells divided by 8, rounded up.
+ *
+ * # Safety
+ *
+ * `keys` and `board` must be handles, and `bitmask` valid for `len` bytes.
+ */
+enum GolStatus gol_board_decrypt(const struct GolKeys *keys,
+                                 const struct GolBoard *board,
+                                 uint8_t *bitmask,
+                                 uintptr_t len);
+
+/**
+ * Serializes the encrypted board, as `Board::save` writes it, to a buffer
+ * allocated by the library and released by `gol_buffer_free`.
+ *
+ * # Safety
+ *
+ * `board` must be a board handle, `buffer` and `len` valid for writes.
+ */
+enum GolStatus gol_board_serialize(const struct GolBoard *board, uint8_t **buffer, uintptr_t *len);
+
+/**
+ * Reads a board from the bytes of `gol_board_serialize`, which stay owned
+ * by the caller.
+ *
+ * # Safety
+ *
+ * `buffer` must be valid for `len` bytes and `out` valid for writes.
+ */
+enum GolStatus gol_board_deserialize(const uint8_t *buffer, uintptr_t len, struct GolBoard **out);
+
+/**
+ * Releases a buffer of `gol_board_serialize`. Null is ignored.
+ *
+ * # Safety
+ *
+ * `buffer` and `len` must be null or as returned by `gol_board_serialize`,
+ * and the buffer not be used afterwards.
+ */
+void gol_buffer_free(uint8_t *buffer, uintptr_t len);
+
+/**
+ * Releases a board. Null is ignored.
+ *
+ * # Safety
+ *
+ * `board` must be null or come from `gol_board_new` or
+ * `gol_board_deserialize`, and not be used afterwards.
+ */
+void gol_board_free(struct GolBoard *board);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GAME_OF_LIFE_H */
@@ -0,0 +1,535 @@
+//! Client-side analysis of decrypted boards.
+
+use crate::pattern::CATALOG;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A board that repeats itself, possibly translated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Periodicity {
+    /// Smallest number of generations after which the board repeats.
+    pub period: usize,
+    /// `(rows, cols)` travelled in one period, as signed offsets on the torus.
+    pub displacement: (isize, isize),
+    /// Generation, within the last period of the history, of the phase whose
+    /// translation-normalized frame is the lexicographically smallest.
+    pub canonical_phase: usize,
+}
+
+impl Periodicity {
+    pub fn is_spaceship(&self) -> bool {
+        self.displacement != (0, 0)
+    }
+}
+
+impl fmt::Display for Periodicity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_spaceship() {
+            write!(
+                f,
+                "period {} spaceship moving by {:?}",
+                self.period, self.displacement
+            )?;
+        } else if self.period == 1 {
+            write!(f, "still life")?;
+        } else {
+            write!(f, "period {} oscillator", self.period)?;
+        }
+        write!(
+            f,
+            ", canonical phase at generation {}",
+            self.canonical_phase
+        )
+    }
+}
+
+/// Returns `frame` shifted by `(di, dj)` on the torus.
+fn translate(frame: &[bool], dimensions: (usize, usize), (di, dj): (usize, usize)) -> Vec<bool> {
+    let (n_rows, n_cols) = dimensions;
+    let mut shifted = vec![false; frame.len()];
+    for (k, alive) in frame.iter().enumerate() {
+        shifted[(k / n_cols + di) % n_rows * n_cols + (k % n_cols + dj) % n_cols] = *alive;
+    }
+    shifted
+}
+
+/// Finds a translation taking `from` to `to`, preferring no translation.
+fn find_translation(
+    from: &[bool],
+    to: &[bool],
+    dimensions: (usize, usize),
+) -> Option<(usize, usize)> {
+    if from == to {
+        return Some((0, 0));
+    }
+    // translations keep the population, which rules most pairs out quickly
+    let population = |frame: &[bool]| frame.iter().filter(|alive| **alive).count();
+    if population(from) != population(to) {
+        return None;
+    }
+    let (n_rows, n_cols) = dimensions;
+    (0..n_rows)
+        .flat_map(|di| (0..n_cols).map(move |dj| (di, dj)))
+        .find(|&shift| translate(from, dimensions, shift) == to)
+}
+
+/// The lexicographically smallest translation of `frame`.
+fn normalize(frame: &[bool], dimensions: (usize, usize)) -> Vec<bool> {
+    let (n_rows, n_cols) = dimensions;
+    (0..n_rows)
+        .flat_map(|di| (0..n_cols).map(move |dj| (di, dj)))
+        .map(|shift| translate(frame, dimensions, shift))
+        .min()
+        .unwrap_or_default()
+}
+
+fn signed(offset: usize, size: usize) -> isize {
+    if offset > size / 2 {
+        offset as isize - size as isize
+    } else {
+        offset as isize
+    }
+}
+
+/// Looks for the smallest period of the end of `history`, a list of
+/// consecutive decrypted frames.
+///
+/// Returns `None` if the last frame does not appear earlier in the history,
+/// even translated.
+pub fn find_periodicity(history: &[Vec<bool>], dimensions: (usize, usize)) -> Option<Periodicity> {
+    let last = history.len().checked_sub(1)?;
+
+    (1..=last).find_map(|period| {
+        let (di, dj) = find_translation(&history[last - period], &history[last], dimensions)?;
+
+        let canonical_phase = (last + 1 - period..=last)
+            .map(|generation| (normalize(&history[generation], dimensions), generation))
+            .min()?
+            .1;
+
+        Some(Periodicity {
+            period,
+            displacement: (signed(di, dimensions.0), signed(dj, dimensions.1)),
+            canonical_phase,
+        })
+    })
+}
+
+/// Estimates the topological entropy of the dynamics from consecutive
+/// frames: `log2` of the number of distinct cell trajectories, divided by
+/// their length.
+///
+/// The estimate lies between 0 and 1. Periodic boards have a bounded number
+/// of trajectories, so their estimate vanishes as the window grows, while
+/// chaotic ones keep close to 1 as long as the board has enough cells to
+/// exhibit every trajectory.
+pub fn topological_entropy(frames: &[Vec<bool>]) -> f64 {
+    let Some(first) = frames.first() else {
+        return 0.0;
+    };
+    let trajectories: HashSet<Vec<bool>> = (0..first.len())
+        .map(|k| frames.iter().map(|frame| frame[k]).collect())
+        .collect();
+
+    (trajectories.len() as f64).log2() / frames.len() as f64
+}
+
+/// Shannon entropy, in bits, of the distribution of live and dead cells.
+pub fn shannon_entropy(cells: &[bool]) -> f64 {
+    let alive = cells.iter().filter(|alive| **alive).count() as f64 / cells.len() as f64;
+    [alive, 1.0 - alive]
+        .iter()
+        .filter(|p| **p > 0.0)
+        .map(|p| -p * p.log2())
+        .sum()
+}
+
+/// Which cells touch each other when grouping live cells into components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Cells sharing a side.
+    Four,
+    /// Cells sharing a side or a corner.
+    Eight,
+}
+
+/// Disjoint sets of cell indices, with path halving and union by size.
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut k: usize) -> usize {
+        while self.parent[k] != k {
+            self.parent[k] = self.parent[self.parent[k]];
+            k = self.parent[k];
+        }
+        k
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Sizes of the connected components of live cells, largest first. Cells
+/// on opposite edges touch, as on the board.
+pub fn component_sizes(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    connectivity: Connectivity,
+) -> Vec<usize> {
+    let (n_rows, n_cols) = dimensions;
+    // looking forward is enough, the backward links are the same pairs
+    let offsets: &[(usize, usize)] = match connectivity {
+        Connectivity::Four => &[(0, 1), (1, 0)],
+        Connectivity::Eight => &[(0, 1), (1, n_cols - 1), (1, 0), (1, 1)],
+    };
+
+    let mut sets = UnionFind::new(cells.len());
+    for (k, _) in cells.iter().enumerate().filter(|(_, alive)| **alive) {
+        let (i, j) = (k / n_cols, k % n_cols);
+        for (di, dj) in offsets {
+            let other = (i + di) % n_rows * n_cols + (j + dj) % n_cols;
+            if cells[other] {
+                sets.union(k, other);
+            }
+        }
+    }
+
+    let mut sizes: Vec<usize> = (0..cells.len())
+        .filter(|&k| cells[k] && sets.find(k) == k)
+        .collect();
+    for root in sizes.iter_mut() {
+        *root = sets.size[*root];
+    }
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes
+}
+
+/// The distinct rotations and reflections of a pattern, with their numbers
+/// of columns.
+pub fn dihedral_variants(pattern: &[bool], pattern_cols: usize) -> Vec<(Vec<bool>, usize)> {
+    let pattern_rows = pattern.len() / pattern_cols;
+    let mut variants: Vec<(Vec<bool>, usize)> = Vec::with_capacity(8);
+
+    for orientation in 0..8 {
+        let transposed = orientation & 4 != 0;
+        let (rows, cols) = if transposed {
+            (pattern_cols, pattern_rows)
+        } else {
+            (pattern_rows, pattern_cols)
+        };
+        let mut variant = vec![false; pattern.len()];
+        for (k, alive) in pattern.iter().enumerate() {
+            let (mut i, mut j) = (k / pattern_cols, k % pattern_cols);
+            if orientation & 1 != 0 {
+                i = pattern_rows - 1 - i;
+            }
+            if orientation & 2 != 0 {
+                j = pattern_cols - 1 - j;
+            }
+            if transposed {
+                std::mem::swap(&mut i, &mut j);
+            }
+            variant[i * cols + j] = *alive;
+        }
+        debug_assert_eq!(rows * cols, pattern.len());
+        if !variants.contains(&(variant.clone(), cols)) {
+            variants.push((variant, cols));
+        }
+    }
+
+    variants
+}
+
+/// Counts the copies of a pattern, in any orientation, surrounded by a ring
+/// of dead cells so that a match is not part of a larger object.
+pub fn count_pattern(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    pattern: &[bool],
+    pattern_cols: usize,
+) -> usize {
+    let (n_rows, n_cols) = dimensions;
+    let variants = dihedral_variants(pattern, pattern_cols);
+
+    let matches_at = |row: usize, col: usize, &(ref variant, cols): &(Vec<bool>, usize)| {
+        let rows = variant.len() / cols;
+        if rows + 2 > n_rows || cols + 2 > n_cols {
+            return false;
+        }
+        // (row, col) is the top left corner of the dead ring
+        (0..rows + 2).all(|i| {
+            (0..cols + 2).all(|j| {
+                let inside = (1..=rows).contains(&i) && (1..=cols).contains(&j);
+                let expected = inside && variant[(i - 1) * cols + j - 1];
+                cells[(row + i) % n_rows * n_cols + (col + j) % n_cols] == expected
+            })
+        })
+    };
+
+    (0..n_rows * n_cols)
+        .filter(|k| {
+            variants
+                .iter()
+                .any(|variant| matches_at(k / n_cols, k % n_cols, variant))
+        })
+        .count()
+}
+
+/// Plaintext statistics of a decrypted frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameAnalysis {
+    pub entropy: f64,
+    pub components_4: usize,
+    pub components_8: usize,
+    /// Size of the largest 8-connected component.
+    pub largest_component: usize,
+    /// Number of copies of each pattern of the catalog, in the phase it is
+    /// drawn in.
+    pub known_patterns: Vec<(&'static str, usize)>,
+}
+
+impl FrameAnalysis {
+    pub fn new(cells: &[bool], dimensions: (usize, usize)) -> Self {
+        let components_8 = component_sizes(cells, dimensions, Connectivity::Eight);
+        Self {
+            entropy: shannon_entropy(cells),
+            components_4: component_sizes(cells, dimensions, Connectivity::Four).len(),
+            components_8: components_8.len(),
+            largest_component: components_8.first().copied().unwrap_or(0),
+            known_patterns: CATALOG
+                .iter()
+                .map(|pattern| {
+                    let (pattern_cells, pattern_cols) = pattern.cells();
+                    (
+                        pattern.name,
+                        count_pattern(cells, dimensions, &pattern_cells, pattern_cols),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for FrameAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "entropy {:.3} bits, {} components (4-connected), {} (8-connected), largest {}",
+            self.entropy, self.components_4, self.components_8, self.largest_component
+        )?;
+        for (name, count) in self.known_patterns.iter().filter(|(_, count)| *count > 0) {
+            write!(f, ", {} {}", count, name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::pattern::{tile, GLIDER};
+    use crate::reference::next_generation;
+
+    fn evolve(cells: Vec<bool>, dimensions: (usize, usize), generations: usize) -> Vec<Vec<bool>> {
+        let mut history = vec![cells];
+        for _ in 0..generations {
+            let next = next_generation(history.last().unwrap(), dimensions);
+            history.push(next);
+        }
+        history
+    }
+
+    #[test]
+    fn test_still_life() {
+        let mut block = vec![false; 16];
+        for k in [5, 6, 9, 10] {
+            block[k] = true;
+        }
+
+        let periodicity = find_periodicity(&evolve(block, (4, 4), 2), (4, 4)).unwrap();
+
+        assert_eq!(periodicity.period, 1);
+        assert!(!periodicity.is_spaceship());
+        assert_eq!(
+            periodicity.to_string(),
+            "still life, canonical phase at generation 2"
+        );
+    }
+
+    #[test]
+    fn test_oscillator() {
+        let mut blinker = vec![false; 25];
+        for k in [7, 12, 17] {
+            blinker[k] = true;
+        }
+
+        let history = evolve(blinker, (5, 5), 3);
+        let periodicity = find_periodicity(&history, (5, 5)).unwrap();
+
+        assert_eq!(periodicity.period, 2);
+        assert_eq!(periodicity.displacement, (0, 0));
+        // the horizontal phase comes first once normalized
+        assert_eq!(periodicity.canonical_phase, 3);
+        assert_eq!(
+            periodicity.to_string(),
+            "period 2 oscillator, canonical phase at generation 3"
+        );
+    }
+
+    #[test]
+    fn test_spaceship() {
+        let glider = tile(&GLIDER, 3, (8, 8), (8, 8)).unwrap();
+
+        let periodicity = find_periodicity(&evolve(glider, (8, 8), 6), (8, 8)).unwrap();
+
+        assert_eq!(periodicity.period, 4);
+        assert_eq!(periodicity.displacement, (1, 1));
+        assert!(periodicity.is_spaceship());
+    }
+
+    #[test]
+    fn test_no_periodicity() {
+        // the R-pentomino takes over a thousand generations to settle
+        let mut r_pentomino = vec![false; 144];
+        for k in [5 * 12 + 6, 5 * 12 + 7, 6 * 12 + 5, 6 * 12 + 6, 7 * 12 + 6] {
+            r_pentomino[k] = true;
+        }
+
+        assert_eq!(
+            find_periodicity(&evolve(r_pentomino, (12, 12), 5), (12, 12)),
+            None
+        );
+        assert_eq!(find_periodicity(&[], (12, 12)), None);
+    }
+
+    #[test]
+    fn test_entropy_of_still_life_vanishes() {
+        let mut block = vec![false; 16];
+        for k in [5, 6, 9, 10] {
+            block[k] = true;
+        }
+
+        // the only trajectories are "always dead" and "always alive"
+        for window in [1, 4, 16] {
+            let frames = &evolve(block.clone(), (4, 4), window)[1..];
+            assert_eq!(topological_entropy(frames), 1.0 / window as f64);
+        }
+        assert_eq!(
+            topological_entropy(&evolve(vec![false; 16], (4, 4), 3)),
+            0.0
+        );
+        assert_eq!(topological_entropy(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_entropy_of_oscillator() {
+        let mut blinker = vec![false; 25];
+        for k in [7, 12, 17] {
+            blinker[k] = true;
+        }
+
+        // dead, center, vertical arms and horizontal arms
+        for window in [2, 5, 10] {
+            let frames = &evolve(blinker.clone(), (5, 5), window)[1..];
+            assert_eq!(topological_entropy(frames), 2.0 / window as f64);
+        }
+    }
+
+    #[test]
+    fn test_entropy_of_soup() {
+        let soup = generate(&BoardProfile::Soup { density: 0.5 }, (64, 64), 1);
+
+        let frames = &evolve(soup, (64, 64), 6)[1..];
+        let entropy = topological_entropy(frames);
+        assert!(entropy > 0.9 && entropy <= 1.0);
+    }
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy(&[false; 8]), 0.0);
+        assert_eq!(shannon_entropy(&[true; 8]), 0.0);
+        assert_eq!(shannon_entropy(&[true, false, true, false]), 1.0);
+        assert!((shannon_entropy(&[true, false, false, false]) - 0.811).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_component_sizes() {
+        #[rustfmt::skip]
+        let cells = [
+            true, true, false, false, false, true,
+            false, false, true, false, false, false,
+            false, false, false, false, true, false,
+            false, false, false, false, true, false,
+            true, false, false, false, false, false,
+        ];
+        let dimensions = (5, 6);
+
+        // the corners touch across the edges
+        assert_eq!(
+            component_sizes(&cells, dimensions, Connectivity::Four),
+            vec![4, 2, 1]
+        );
+        assert_eq!(
+            component_sizes(&cells, dimensions, Connectivity::Eight),
+            vec![5, 2]
+        );
+        assert!(component_sizes(&[false; 30], dimensions, Connectivity::Eight).is_empty());
+    }
+
+    #[test]
+    fn test_glider_found_in_all_orientations() {
+        let variants = dihedral_variants(&GLIDER, 3);
+        assert_eq!(variants.len(), 8);
+
+        for (variant, cols) in &variants {
+            let cells = tile(variant, *cols, (9, 9), (9, 9)).unwrap();
+            assert_eq!(count_pattern(&cells, (9, 9), &GLIDER, 3), 1);
+        }
+
+        // blocks only have one orientation
+        assert_eq!(dihedral_variants(&[true; 4], 2).len(), 1);
+    }
+
+    #[test]
+    fn test_count_pattern_needs_dead_ring() {
+        let blinker = [true, true, true];
+        let mut cells = tile(&blinker, 3, (8, 8), (4, 4)).unwrap();
+        assert_eq!(count_pattern(&cells, (8, 8), &blinker, 3), 4);
+
+        // touching another cell, it is no longer an isolated blinker
+        cells[8] = true;
+        assert_eq!(count_pattern(&cells, (8, 8), &blinker, 3), 3);
+    }
+
+    #[test]
+    fn test_frame_analysis() {
+        let cells = tile(&GLIDER, 3, (8, 8), (8, 8)).unwrap();
+
+        let analysis = FrameAnalysis::new(&cells, (8, 8));
+
+        assert_eq!(analysis.components_4, 2);
+        assert_eq!(analysis.components_8, 1);
+        assert_eq!(analysis.largest_component, 5);
+        assert!(analysis.to_string().ends_with(", 1 glider"));
+    }
+}
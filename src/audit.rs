@@ -0,0 +1,267 @@
+//! Accounting of what the client decrypts during a run, so that reviewers
+//! can see exactly how much of the encrypted board is revealed.
+//!
+//! Every option that decrypts declares its leakage before the run, which
+//! lets a budget be enforced before any homomorphic work starts, and the
+//! decryptions actually made are recorded as the run goes.
+
+use std::error::Error;
+use std::fmt;
+
+/// What a decryption reveals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Leak {
+    /// Cells of the board.
+    Frame,
+    /// Number of live cells.
+    Population,
+    /// Aggregates with noise added before decryption, see `noise`.
+    NoisyAggregate,
+    /// Whether the board changed.
+    StillFlag,
+    /// Which cells changed, see `checkpoint`.
+    Changes,
+    /// Cells sampled for the population estimate, see `estimate`.
+    SampledCells,
+    /// Intermediate values of the cell updates.
+    Trace,
+    /// Distances to the nearest live cell.
+    Distances,
+    /// Generations of the first deaths of the cells.
+    Lifespans,
+    /// Transition counts of `Board::rule_evidence`.
+    RuleEvidence,
+}
+
+impl Leak {
+    pub const ALL: [Leak; 10] = [
+        Leak::Frame,
+        Leak::Population,
+        Leak::NoisyAggregate,
+        Leak::StillFlag,
+        Leak::Changes,
+        Leak::SampledCells,
+        Leak::Trace,
+        Leak::Distances,
+        Leak::Lifespans,
+        Leak::RuleEvidence,
+    ];
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Frame => "frames",
+            Self::Population => "population counts",
+            Self::NoisyAggregate => "noisy aggregates",
+            Self::StillFlag => "still flags",
+            Self::Changes => "changed cells",
+            Self::SampledCells => "sampled cells",
+            Self::Trace => "update traces",
+            Self::Distances => "distances",
+            Self::Lifespans => "lifespans",
+            Self::RuleEvidence => "transition counts",
+        })
+    }
+}
+
+/// Bits decrypted by the options of a run, declared before it starts.
+#[derive(Debug, Default)]
+pub struct Declaration {
+    per_frame: Vec<(Leak, usize)>,
+    once: Vec<(Leak, usize)>,
+}
+
+impl Declaration {
+    /// Declares `bits` decrypted for every frame of the run, at most.
+    pub fn every_frame(&mut self, leak: Leak, bits: usize) {
+        self.per_frame.push((leak, bits));
+    }
+
+    /// Declares `bits` decrypted once in the run, at most.
+    pub fn once(&mut self, leak: Leak, bits: usize) {
+        self.once.push((leak, bits));
+    }
+
+    /// Most bits decrypted over a run of `n_frames` frames.
+    pub fn total(&self, n_frames: usize) -> usize {
+        let per_frame: usize = self.per_frame.iter().map(|(_, bits)| bits).sum();
+        let once: usize = self.once.iter().map(|(_, bits)| bits).sum();
+        per_frame * n_frames + once
+    }
+}
+
+/// The declared leakage of a run is over the `--max-leak-bits` budget.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub declared: usize,
+    pub budget: usize,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the run would decrypt up to {} bits, over the budget of {} bits",
+            self.declared, self.budget
+        )
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+/// One decryption made by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub leak: Leak,
+    pub bits: usize,
+    pub generation: usize,
+}
+
+/// Log of the decryptions of a run.
+#[derive(Debug)]
+pub struct DecryptionAuditor {
+    records: Vec<Record>,
+    /// Bits the run declared it would decrypt at most.
+    declared: usize,
+    generation: usize,
+}
+
+impl DecryptionAuditor {
+    /// Checks the declared leakage of a run of `n_frames` frames against the
+    /// budget, if any.
+    pub fn new(
+        declaration: &Declaration,
+        n_frames: usize,
+        budget: Option<usize>,
+    ) -> Result<Self, BudgetExceeded> {
+        let declared = declaration.total(n_frames);
+        if let Some(budget) = budget.filter(|budget| declared > *budget) {
+            return Err(BudgetExceeded { declared, budget });
+        }
+        Ok(Self {
+            records: Vec::new(),
+            declared,
+            generation: 0,
+        })
+    }
+
+    /// Attributes the next decryptions to `generation`.
+    pub fn set_generation(&mut self, generation: usize) {
+        self.generation = generation;
+    }
+
+    /// Records a decryption of `bits` bits.
+    ///
+    /// Panics if the run decrypts more than it declared, which would make
+    /// the budget check meaningless.
+    pub fn record(&mut self, leak: Leak, bits: usize) {
+        self.records.push(Record {
+            leak,
+            bits,
+            generation: self.generation,
+        });
+        assert!(
+            self.total_bits() <= self.declared,
+            "decrypted more than the {} bits declared",
+            self.declared
+        );
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    pub fn total_bits(&self) -> usize {
+        self.records.iter().map(|record| record.bits).sum()
+    }
+}
+
+impl fmt::Display for DecryptionAuditor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "decrypted {} bits in {} decryptions, {} declared",
+            self.total_bits(),
+            self.records.len(),
+            self.declared
+        )?;
+        let mut leaks: Vec<Leak> = self.records.iter().map(|record| record.leak).collect();
+        leaks.sort();
+        leaks.dedup();
+        for leak in leaks {
+            let (count, bits) = self
+                .records
+                .iter()
+                .filter(|record| record.leak == leak)
+                .fold((0, 0), |(count, bits), record| {
+                    (count + 1, bits + record.bits)
+                });
+            write!(f, "\n  {}: {} bits in {} decryptions", leak, bits, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declaration() -> Declaration {
+        let mut declaration = Declaration::default();
+        declaration.every_frame(Leak::Frame, 64);
+        declaration.every_frame(Leak::Population, 7);
+        declaration.once(Leak::Distances, 128);
+        declaration
+    }
+
+    #[test]
+    fn test_budget_is_checked_before_the_run() {
+        assert_eq!(declaration().total(10), 10 * 71 + 128);
+        assert_eq!(
+            DecryptionAuditor::new(&declaration(), 10, Some(800)).unwrap_err(),
+            BudgetExceeded {
+                declared: 838,
+                budget: 800
+            }
+        );
+        assert!(DecryptionAuditor::new(&declaration(), 10, Some(838)).is_ok());
+        assert!(DecryptionAuditor::new(&declaration(), 10, None).is_ok());
+    }
+
+    #[test]
+    fn test_records() {
+        let mut auditor = DecryptionAuditor::new(&declaration(), 3, Some(1000)).unwrap();
+        for generation in 0..3 {
+            auditor.set_generation(generation);
+            auditor.record(Leak::Frame, 64);
+            auditor.record(Leak::Population, 7);
+        }
+        auditor.record(Leak::Distances, 128);
+
+        assert_eq!(auditor.total_bits(), declaration().total(3));
+        assert_eq!(
+            auditor.records()[2],
+            Record {
+                leak: Leak::Frame,
+                bits: 64,
+                generation: 1
+            }
+        );
+        assert_eq!(
+            auditor.to_string(),
+            "decrypted 341 bits in 7 decryptions, 341 declared\n\
+             \x20 frames: 192 bits in 3 decryptions\n\
+             \x20 population counts: 21 bits in 3 decryptions\n\
+             \x20 distances: 128 bits in 1 decryptions"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "decrypted more than the 128 bits declared")]
+    fn test_undeclared_decryption() {
+        let mut auditor = DecryptionAuditor::new(&declaration(), 0, None).unwrap();
+        auditor.record(Leak::Distances, 128);
+        auditor.record(Leak::Frame, 64);
+    }
+}
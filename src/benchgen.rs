@@ -0,0 +1,242 @@
+//! Plaintext benchmark boards with a controllable structure.
+//!
+//! Random soups are not representative of every workload, so boards can also
+//! be made of scattered clusters, of gliders, or of the "ash" left after a
+//! soup has evolved for a while. Every board is a deterministic function of
+//! the profile, the dimensions and the seed.
+
+use crate::pattern::GLIDER;
+use crate::reference::next_generation;
+use std::str::FromStr;
+
+/// SplitMix64, enough to scatter cells reproducibly.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// `true` with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64) < p * (1u64 << 53) as f64
+    }
+}
+
+/// How the live cells of a benchmark board are laid out.
+///
+/// Parsed from one of `BoardProfile::SYNTAX`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardProfile {
+    /// Every cell is alive with the given probability.
+    Soup { density: f64 },
+    /// Square blobs, half filled, with sides uniform in `1..=max_size`.
+    Clusters { count: usize, max_size: usize },
+    /// Gliders at random positions, in any of their 8 orientations.
+    Gliders { count: usize },
+    /// A 50% soup evolved for some generations with the plaintext game.
+    Ash { generations: usize },
+}
+
+impl BoardProfile {
+    /// The forms of the profiles, by kind.
+    pub const SYNTAX: [&'static str; 4] = [
+        "soup:<density>",
+        "clusters:<count>:<max size>",
+        "gliders:<count>",
+        "ash:<generations>",
+    ];
+}
+
+impl FromStr for BoardProfile {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        fn number<T: FromStr>(spec: &str, value: Option<&str>) -> Result<T, String> {
+            value
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("invalid board profile '{}'", spec))
+        }
+
+        let mut parts = spec.split(':');
+        let profile = match parts.next() {
+            Some("soup") => {
+                let density = number(spec, parts.next())?;
+                if !(0.0..=1.0).contains(&density) {
+                    return Err(format!("density must be between 0 and 1 in '{}'", spec));
+                }
+                Self::Soup { density }
+            }
+            Some("clusters") => {
+                let count = number(spec, parts.next())?;
+                let max_size = number(spec, parts.next())?;
+                if max_size == 0 {
+                    return Err(format!("cluster size must be at least 1 in '{}'", spec));
+                }
+                Self::Clusters { count, max_size }
+            }
+            Some("gliders") => Self::Gliders {
+                count: number(spec, parts.next())?,
+            },
+            Some("ash") => Self::Ash {
+                generations: number(spec, parts.next())?,
+            },
+            _ => return Err(format!("unknown board profile '{}'", spec)),
+        };
+        if parts.next().is_some() {
+            return Err(format!("invalid board profile '{}'", spec));
+        }
+
+        Ok(profile)
+    }
+}
+
+/// Generates a board following `profile`.
+pub fn generate(profile: &BoardProfile, dimensions: (usize, usize), seed: u64) -> Vec<bool> {
+    let (n_rows, n_cols) = dimensions;
+    let mut rng = Rng::new(seed);
+    let mut cells = vec![false; n_rows * n_cols];
+
+    match *profile {
+        BoardProfile::Soup { density } => {
+            for cell in cells.iter_mut() {
+                *cell = rng.chance(density);
+            }
+        }
+        BoardProfile::Clusters { count, max_size } => {
+            for _ in 0..count {
+                let size = 1 + rng.below(max_size);
+                let (row, col) = (rng.below(n_rows), rng.below(n_cols));
+                for i in 0..size {
+                    for j in 0..size {
+                        if rng.chance(0.5) {
+                            cells[(row + i) % n_rows * n_cols + (col + j) % n_cols] = true;
+                        }
+                    }
+                }
+            }
+        }
+        BoardProfile::Gliders { count } => {
+            for _ in 0..count {
+                let orientation = rng.below(8);
+                let (row, col) = (rng.below(n_rows), rng.below(n_cols));
+                for (k, alive) in GLIDER.iter().enumerate() {
+                    let (mut i, mut j) = (k / 3, k % 3);
+                    if orientation & 1 != 0 {
+                        i = 2 - i;
+                    }
+                    if orientation & 2 != 0 {
+                        j = 2 - j;
+                    }
+                    if orientation & 4 != 0 {
+                        std::mem::swap(&mut i, &mut j);
+                    }
+                    if *alive {
+                        cells[(row + i) % n_rows * n_cols + (col + j) % n_cols] = true;
+                    }
+                }
+            }
+        }
+        BoardProfile::Ash { generations } => {
+            for cell in cells.iter_mut() {
+                *cell = rng.chance(0.5);
+            }
+            for _ in 0..generations {
+                cells = next_generation(&cells, dimensions);
+            }
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn density(cells: &[bool]) -> f64 {
+        cells.iter().filter(|alive| **alive).count() as f64 / cells.len() as f64
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        assert_eq!(
+            "soup:0.25".parse(),
+            Ok(BoardProfile::Soup { density: 0.25 })
+        );
+        assert_eq!(
+            "clusters:4:3".parse(),
+            Ok(BoardProfile::Clusters {
+                count: 4,
+                max_size: 3
+            })
+        );
+        assert_eq!("gliders:8".parse(), Ok(BoardProfile::Gliders { count: 8 }));
+        assert_eq!("ash:20".parse(), Ok(BoardProfile::Ash { generations: 20 }));
+
+        assert!("soup:1.5".parse::<BoardProfile>().is_err());
+        assert!("clusters:4:0".parse::<BoardProfile>().is_err());
+        assert!("gliders".parse::<BoardProfile>().is_err());
+        assert!("gliders:8:1".parse::<BoardProfile>().is_err());
+        assert!("random:0.5".parse::<BoardProfile>().is_err());
+
+        for syntax in BoardProfile::SYNTAX {
+            let (kind, values) = syntax.split_once(':').unwrap();
+            let spec = format!("{}{}", kind, ":1".repeat(values.split(':').count()));
+            assert!(spec.parse::<BoardProfile>().is_ok(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let profile = BoardProfile::Clusters {
+            count: 5,
+            max_size: 4,
+        };
+
+        let board = generate(&profile, (16, 16), 7);
+
+        assert_eq!(board, generate(&profile, (16, 16), 7));
+        assert_ne!(board, generate(&profile, (16, 16), 8));
+    }
+
+    #[test]
+    fn test_soup_density() {
+        for target in [0.0, 0.1, 0.5, 1.0] {
+            let board = generate(&BoardProfile::Soup { density: target }, (64, 64), 1);
+            assert!((density(&board) - target).abs() < 0.03);
+        }
+    }
+
+    #[test]
+    fn test_gliders_stay_in_bounds() {
+        for seed in 0..32 {
+            // gliders placed near the edges wrap around
+            let board = generate(&BoardProfile::Gliders { count: 1 }, (5, 7), seed);
+            assert_eq!(board.len(), 35);
+            assert_eq!(board.iter().filter(|alive| **alive).count(), 5);
+        }
+    }
+
+    #[test]
+    fn test_ash_is_evolved_soup() {
+        let soup = generate(&BoardProfile::Ash { generations: 0 }, (16, 16), 3);
+        let ash = generate(&BoardProfile::Ash { generations: 2 }, (16, 16), 3);
+
+        let expected = next_generation(&next_generation(&soup, (16, 16)), (16, 16));
+        assert_eq!(ash, expected);
+    }
+}
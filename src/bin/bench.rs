@@ -0,0 +1,194 @@
+//! Compares the parameter sets of the boolean keys, see `keys::parameters`:
+//! for each of them, plays the same soup for a few generations, checks the
+//! decrypted board against the plaintext game, and prints the key generation
+//! and update times as tab-separated values.
+//!
+//! `--rows <n>` and `--cols <n>` set the dimensions of the board, 8x8 by
+//! default, and `--steps <n>` the number of timed generations, 3 by default.
+//! The exit status is 1 if any parameter set gives a wrong board.
+//!
+//! With the `phase-timer` feature, the table also has the mean time per cell
+//! of each phase of the update, see `phase_timer`.
+
+use concrete::prelude::*;
+use concrete::{generate_keys, set_server_key, ConfigBuilder, FheBool, FheBoolParameters};
+use homomorphic_game_of_life_2::benchgen::{generate, BoardProfile};
+use homomorphic_game_of_life_2::keys::{parameters, PARAMETER_NAMES};
+#[cfg(feature = "phase-timer")]
+use homomorphic_game_of_life_2::phase_timer::{self, Phase, PhaseProfile};
+use homomorphic_game_of_life_2::reference::next_generation;
+use homomorphic_game_of_life_2::Board;
+use std::time::{Duration, Instant};
+
+/// Cells between two cells whose phases are timed.
+#[cfg(feature = "phase-timer")]
+const PHASE_SAMPLING: usize = 4;
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    dimensions: (usize, usize),
+    steps: usize,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        dimensions: (8, 8),
+        steps: 3,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = match args.next().map(|value| value.parse::<usize>()) {
+            Some(Ok(value)) if value > 0 => value,
+            Some(_) => return Err(format!("invalid value for {}", arg)),
+            None => return Err(format!("missing value for {}", arg)),
+        };
+        match arg.as_str() {
+            "--rows" => options.dimensions.0 = value,
+            "--cols" => options.dimensions.1 = value,
+            "--steps" => options.steps = value,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+/// What a parameter set did.
+struct Measure {
+    keygen: Duration,
+    /// Mean update time of the generations.
+    generation: Duration,
+    /// Whether the final board is the expected one.
+    correct: bool,
+    #[cfg(feature = "phase-timer")]
+    phases: PhaseProfile,
+}
+
+fn run(
+    parameters: FheBoolParameters,
+    cells: &[bool],
+    expected: &[bool],
+    options: &Options,
+) -> Measure {
+    let config = ConfigBuilder::all_disabled()
+        .enable_custom_bool(parameters)
+        .build();
+    let keygen_start = Instant::now();
+    let (client_key, server_key) = generate_keys(config);
+    let keygen = keygen_start.elapsed();
+    let encrypt = |alive| FheBool::encrypt(alive, &client_key);
+    let states = cells.iter().map(|&alive| encrypt(alive)).collect();
+    let zeros = (encrypt(false), encrypt(false), encrypt(false));
+    set_server_key(server_key);
+    let mut board = Board::new(options.dimensions.1, states, zeros);
+
+    #[cfg(feature = "phase-timer")]
+    phase_timer::set_sampling(Some(PHASE_SAMPLING));
+    let start = Instant::now();
+    for _ in 0..options.steps {
+        board.update();
+    }
+    let generation = start.elapsed() / options.steps as u32;
+    Measure {
+        keygen,
+        generation,
+        correct: board.decrypt(&client_key) == expected,
+        #[cfg(feature = "phase-timer")]
+        phases: phase_timer::take_profile(),
+    }
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        eprintln!("usage: bench [--rows <n>] [--cols <n>] [--steps <n>]");
+        std::process::exit(1);
+    });
+    let (n_rows, n_cols) = options.dimensions;
+    let cells = generate(&BoardProfile::Soup { density: 0.4 }, options.dimensions, 0);
+    let mut expected = cells.clone();
+    for _ in 0..options.steps {
+        expected = next_generation(&expected, options.dimensions);
+    }
+
+    print!("parameters\trows\tcols\tsteps\tkeygen_ms\tgeneration_ms\tcell_us\tcorrect");
+    #[cfg(feature = "phase-timer")]
+    print!("\tgather_us\tcount_us\trule_us");
+    println!();
+    let mut all_correct = true;
+    for name in PARAMETER_NAMES {
+        let measure = run(parameters(name).unwrap(), &cells, &expected, &options);
+        all_correct &= measure.correct;
+        print!(
+            "{}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{:.3}\t{}",
+            name,
+            n_rows,
+            n_cols,
+            options.steps,
+            measure.keygen.as_secs_f64() * 1e3,
+            measure.generation.as_secs_f64() * 1e3,
+            measure.generation.as_secs_f64() * 1e6 / (n_rows * n_cols) as f64,
+            measure.correct
+        );
+        #[cfg(feature = "phase-timer")]
+        for phase in Phase::ALL {
+            print!("\t{:.3}", measure.phases.mean(phase).as_secs_f64() * 1e6);
+        }
+        println!();
+    }
+    if !all_correct {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(args("")),
+            Ok(Options {
+                dimensions: (8, 8),
+                steps: 3
+            })
+        );
+        assert_eq!(
+            parse_args(args("--cols 12 --steps 1 --rows 4")),
+            Ok(Options {
+                dimensions: (4, 12),
+                steps: 1
+            })
+        );
+        assert_eq!(
+            parse_args(args("--rows 0")),
+            Err("invalid value for --rows".to_string())
+        );
+        assert_eq!(
+            parse_args(args("--steps")),
+            Err("missing value for --steps".to_string())
+        );
+        assert_eq!(
+            parse_args(args("--size 4")),
+            Err("unknown option --size".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parameter_sets_are_correct() {
+        let options = Options {
+            dimensions: (5, 6),
+            steps: 2,
+        };
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, options.dimensions, 3);
+        let expected = next_generation(&next_generation(&cells, (5, 6)), (5, 6));
+        for name in PARAMETER_NAMES {
+            let measure = run(parameters(name).unwrap(), &cells, &expected, &options);
+            assert!(measure.correct, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Writes the fixtures of the current version of the file format to
+//! `tests/fixtures` (see `fixtures`), with new keys, then checks every
+//! fixture there. Run it once after changing `keys::FORMAT_VERSION`, with
+//! `cargo run --release --features fixtures --bin fixtures`, and commit the
+//! new files along with those of the previous versions.
+//!
+//! `--check` only checks the fixtures, as the tests do. The exit status is
+//! 1 if a fixture fails.
+
+use concrete::{generate_keys, set_server_key, ConfigBuilder};
+use homomorphic_game_of_life_2::fixtures::{check_all, generate, DIRECTORY};
+use std::path::Path;
+
+fn main() {
+    let mut check_only = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check_only = true,
+            other => {
+                eprintln!("unknown argument: {}", other);
+                eprintln!("usage: fixtures [--check]");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join(DIRECTORY);
+    if !check_only {
+        let config = ConfigBuilder::all_disabled().enable_default_bool().build();
+        let (client_key, server_key) = generate_keys(config);
+        set_server_key(server_key);
+        let paths = generate(&directory, &client_key).unwrap_or_else(|err| {
+            eprintln!("{}: {}", directory.display(), err);
+            std::process::exit(1);
+        });
+        for path in paths {
+            println!("wrote {}", path.display());
+        }
+    }
+    match check_all(&directory) {
+        Ok(checked) => println!("{} fixtures checked", checked),
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{}", error);
+            }
+            std::process::exit(1);
+        }
+    }
+}
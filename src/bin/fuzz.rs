@@ -0,0 +1,432 @@
+//! Plays random combinations of what `Board` supports: a rule, a parameter
+//! set of the keys, boundary conditions and a subset of the checks of
+//! `Check`, for 3 generations of a small random board, comparing every
+//! generation with the plaintext game of `reference::next_generation_with`.
+//!
+//! A failing case is shrunk to the simplest case that still fails, which is
+//! printed as an RLE pattern file whose comments give the command line to
+//! replay it with and the checks that failed. Any failure is a bug in the
+//! encrypted game or in one of the checks.
+//!
+//! `--cases <n>` sets the number of cases, 20 by default, and `--seed <n>`
+//! the seed they are drawn from, 0 by default, so that a nightly run can
+//! seed it with the date. The exit status is 1 if a case fails.
+
+use concrete::prelude::*;
+use concrete::{generate_keys, set_server_key, ClientKey, ConfigBuilder, FheBool, ServerKey};
+use homomorphic_game_of_life_2::benchgen::{generate, BoardProfile, Rng};
+use homomorphic_game_of_life_2::keys::{parameters, PARAMETER_NAMES};
+use homomorphic_game_of_life_2::patternfile::to_rle;
+use homomorphic_game_of_life_2::reference::next_generation_with;
+use homomorphic_game_of_life_2::rule::Rule;
+use homomorphic_game_of_life_2::{decrypt_cells, decrypt_number, Board, BoundaryCondition};
+use std::collections::HashMap;
+use std::fmt;
+
+const GENERATIONS: usize = 3;
+
+/// Fewest rows and columns of the boards, so that no cell is its own
+/// neighbour.
+const MIN_SIZE: usize = 3;
+
+/// Most rows and columns of the boards.
+const MAX_SIZE: usize = 5;
+
+/// The rules the cases are drawn from.
+const RULES: [&str; 5] = ["B3/S23", "B36/S23", "B2/S", "B3678/S34678", "B3/S012345678"];
+
+const BOUNDARIES: [BoundaryCondition; 3] = [
+    BoundaryCondition::Toroidal,
+    BoundaryCondition::Fixed,
+    BoundaryCondition::Mirror,
+];
+
+/// What is checked at every generation, besides the cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    /// `Board::changes` against the previous generation.
+    Changes,
+    /// `Board::encrypted_population_count`.
+    Population,
+    /// The flag of `Board::update_with_change_flag`.
+    ChangeFlag,
+    /// The board goes through `Board::to_bytes` and `Board::from_bytes`.
+    Serialization,
+}
+
+const CHECKS: [Check; 4] = [
+    Check::Changes,
+    Check::Population,
+    Check::ChangeFlag,
+    Check::Serialization,
+];
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Changes => "changes",
+            Self::Population => "population",
+            Self::ChangeFlag => "change flag",
+            Self::Serialization => "serialization",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Case {
+    rule: Rule,
+    parameters: &'static str,
+    boundary: BoundaryCondition,
+    checks: Vec<Check>,
+    dimensions: (usize, usize),
+    cells: Vec<bool>,
+}
+
+impl Case {
+    fn sample(rng: &mut Rng) -> Self {
+        let mut size = || MIN_SIZE + rng.below(MAX_SIZE - MIN_SIZE + 1);
+        let dimensions = (size(), size());
+        Self {
+            rule: RULES[rng.below(RULES.len())].parse().unwrap(),
+            parameters: PARAMETER_NAMES[rng.below(PARAMETER_NAMES.len())],
+            boundary: BOUNDARIES[rng.below(BOUNDARIES.len())],
+            checks: CHECKS.into_iter().filter(|_| rng.chance(0.5)).collect(),
+            dimensions,
+            cells: generate(
+                &BoardProfile::Soup { density: 0.4 },
+                dimensions,
+                rng.next_u64(),
+            ),
+        }
+    }
+
+    /// The case with the cells of rows `rows` and columns `cols` only.
+    fn crop(&self, rows: std::ops::Range<usize>, cols: std::ops::Range<usize>) -> Self {
+        let n_cols = self.dimensions.1;
+        Self {
+            dimensions: (rows.len(), cols.len()),
+            cells: rows
+                .flat_map(|i| cols.clone().map(move |j| i * n_cols + j))
+                .map(|k| self.cells[k])
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Plays `case` with the keys of its parameters, returning the first
+/// mismatch with the plaintext game.
+fn run(case: &Case, client_key: &ClientKey, server_key: &ServerKey) -> Result<(), String> {
+    set_server_key(server_key.clone());
+    let encrypt = |alive| FheBool::encrypt(alive, client_key);
+    let zeros = (encrypt(false), encrypt(false), encrypt(false));
+    let states = case.cells.iter().map(|&alive| encrypt(alive)).collect();
+    let mut board = Board::new(case.dimensions.1, states, zeros)
+        .with_rule(case.rule)
+        .with_boundary(case.boundary);
+
+    let mut expected = case.cells.clone();
+    for generation in 1..=GENERATIONS {
+        let next = next_generation_with(&expected, case.dimensions, &case.rule, case.boundary);
+        let previous = board.snapshot();
+        if case.checks.contains(&Check::ChangeFlag) {
+            let changed = board.update_with_change_flag().decrypt(client_key);
+            if changed != (next != expected) {
+                return Err(format!(
+                    "change flag {} at generation {}",
+                    changed, generation
+                ));
+            }
+        } else {
+            board.update();
+        }
+        if case.checks.contains(&Check::Serialization) {
+            let bytes = board.to_bytes(case.parameters);
+            board = Board::from_bytes(&bytes, case.parameters).map_err(|err| err.to_string())?;
+        }
+        if board.decrypt(client_key) != next {
+            return Err(format!("wrong cells at generation {}", generation));
+        }
+        if case.checks.contains(&Check::Changes) {
+            let changes = decrypt_cells(&board.changes(&previous), client_key);
+            let expected_changes: Vec<bool> =
+                next.iter().zip(&expected).map(|(a, b)| a != b).collect();
+            if changes != expected_changes {
+                return Err(format!("wrong changes at generation {}", generation));
+            }
+        }
+        if case.checks.contains(&Check::Population) {
+            let population = decrypt_number(&board.encrypted_population_count(), client_key);
+            let expected_population = next.iter().filter(|&&alive| alive).count();
+            if population != expected_population {
+                return Err(format!(
+                    "population {} instead of {} at generation {}",
+                    population, expected_population, generation
+                ));
+            }
+        }
+        expected = next;
+    }
+    Ok(())
+}
+
+/// Simpler variants of `case`, each one step away from it: one check less,
+/// the default rule, parameters or boundary conditions, one row or column
+/// less, or one live cell less.
+fn simplifications(case: &Case) -> Vec<Case> {
+    let mut candidates = Vec::new();
+    for k in 0..case.checks.len() {
+        let mut candidate = case.clone();
+        candidate.checks.remove(k);
+        candidates.push(candidate);
+    }
+    if case.rule != Rule::conway() {
+        candidates.push(Case {
+            rule: Rule::conway(),
+            ..case.clone()
+        });
+    }
+    if case.parameters != PARAMETER_NAMES[0] {
+        candidates.push(Case {
+            parameters: PARAMETER_NAMES[0],
+            ..case.clone()
+        });
+    }
+    if case.boundary != BoundaryCondition::Toroidal {
+        candidates.push(Case {
+            boundary: BoundaryCondition::Toroidal,
+            ..case.clone()
+        });
+    }
+    let (n_rows, n_cols) = case.dimensions;
+    if n_rows > MIN_SIZE {
+        candidates.push(case.crop(0..n_rows - 1, 0..n_cols));
+        candidates.push(case.crop(1..n_rows, 0..n_cols));
+    }
+    if n_cols > MIN_SIZE {
+        candidates.push(case.crop(0..n_rows, 0..n_cols - 1));
+        candidates.push(case.crop(0..n_rows, 1..n_cols));
+    }
+    for (k, _) in case.cells.iter().enumerate().filter(|(_, &alive)| alive) {
+        let mut candidate = case.clone();
+        candidate.cells[k] = false;
+        candidates.push(candidate);
+    }
+    candidates
+}
+
+/// Simplifies `case` for as long as the simpler case still `fails`.
+fn shrink(mut case: Case, mut fails: impl FnMut(&Case) -> bool) -> Case {
+    'simplify: loop {
+        for candidate in simplifications(&case) {
+            if fails(&candidate) {
+                case = candidate;
+                continue 'simplify;
+            }
+        }
+        return case;
+    }
+}
+
+/// `case` as an RLE pattern file, with the command line replaying it and
+/// the `failure` in its comments.
+fn reproducer(case: &Case, failure: &str) -> String {
+    let (n_rows, n_cols) = case.dimensions;
+    let mut options = format!(
+        "--pattern-file repro.rle --size {}x{} --offset 0,0 --rule {} --boundary {} --params {} \
+         --generations {}",
+        n_rows, n_cols, case.rule, case.boundary, case.parameters, GENERATIONS
+    );
+    if case.checks.contains(&Check::Population) {
+        options += " --population";
+    }
+    if case.checks.contains(&Check::ChangeFlag) {
+        options += " --detect-still";
+    }
+    if case.checks.contains(&Check::Changes) || case.checks.contains(&Check::Serialization) {
+        options += " --keys repro-keys.bin --checkpoint repro-checkpoints";
+    }
+    if case.checks.contains(&Check::Changes) {
+        options += " --full-checkpoint-every 2";
+    }
+    format!(
+        "#C {}\n#C checks: {}\n#C replay: homomorphic_game_of_life_2 {}\n{}",
+        failure,
+        check_names(&case.checks),
+        options,
+        to_rle(&case.cells, n_cols, &case.rule)
+    )
+}
+
+fn check_names(checks: &[Check]) -> String {
+    if checks.is_empty() {
+        return "none".to_string();
+    }
+    let names: Vec<String> = checks.iter().map(Check::to_string).collect();
+    names.join(", ")
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    cases: usize,
+    seed: u64,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options { cases: 20, seed: 0 };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = match args.next().map(|value| value.parse::<u64>()) {
+            Some(Ok(value)) => value,
+            Some(Err(_)) => return Err(format!("invalid value for {}", arg)),
+            None => return Err(format!("missing value for {}", arg)),
+        };
+        match arg.as_str() {
+            "--cases" => options.cases = value as usize,
+            "--seed" => options.seed = value,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        eprintln!("usage: fuzz [--cases <n>] [--seed <n>]");
+        std::process::exit(1);
+    });
+
+    let mut keys = HashMap::new();
+    let mut keys_for = |name: &'static str| -> (ClientKey, ServerKey) {
+        keys.entry(name)
+            .or_insert_with(|| {
+                let config = ConfigBuilder::all_disabled()
+                    .enable_custom_bool(parameters(name).unwrap())
+                    .build();
+                generate_keys(config)
+            })
+            .clone()
+    };
+    let mut run_case = |case: &Case| {
+        let (client_key, server_key) = keys_for(case.parameters);
+        run(case, &client_key, &server_key)
+    };
+
+    let mut rng = Rng::new(options.seed);
+    for k in 0..options.cases {
+        let case = Case::sample(&mut rng);
+        if let Err(failure) = run_case(&case) {
+            eprintln!(
+                "case {} of seed {} failed: {}, shrinking",
+                k, options.seed, failure
+            );
+            let case = shrink(case, |candidate| run_case(candidate).is_err());
+            let failure = run_case(&case).unwrap_err();
+            print!("{}", reproducer(&case, &failure));
+            std::process::exit(1);
+        }
+        println!(
+            "case {}: {} {} {}x{}, checks: {}: ok",
+            k,
+            case.rule,
+            case.boundary,
+            case.dimensions.0,
+            case.dimensions.1,
+            check_names(&case.checks)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use homomorphic_game_of_life_2::patternfile::PatternFile;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(args("")), Ok(Options { cases: 20, seed: 0 }));
+        assert_eq!(
+            parse_args(args("--seed 20261016 --cases 3")),
+            Ok(Options {
+                cases: 3,
+                seed: 20261016
+            })
+        );
+        assert_eq!(
+            parse_args(args("--cases many")),
+            Err("invalid value for --cases".to_string())
+        );
+    }
+
+    #[test]
+    fn test_shrink() {
+        let case = Case {
+            rule: "B36/S23".parse().unwrap(),
+            parameters: PARAMETER_NAMES[1],
+            boundary: BoundaryCondition::Mirror,
+            checks: CHECKS.to_vec(),
+            dimensions: (5, 4),
+            cells: generate(&BoardProfile::Soup { density: 0.5 }, (5, 4), 1),
+        };
+        // a bug of the population count of boards with 2 live cells or more
+        let fails = |case: &Case| {
+            case.checks.contains(&Check::Population)
+                && case.cells.iter().filter(|&&alive| alive).count() >= 2
+        };
+        assert!(fails(&case));
+
+        let shrunk = shrink(case, fails);
+        assert_eq!(shrunk.checks, [Check::Population]);
+        assert_eq!(shrunk.rule, Rule::conway());
+        assert_eq!(shrunk.parameters, PARAMETER_NAMES[0]);
+        assert_eq!(shrunk.boundary, BoundaryCondition::Toroidal);
+        assert_eq!(shrunk.dimensions, (MIN_SIZE, MIN_SIZE));
+        assert_eq!(shrunk.cells.iter().filter(|&&alive| alive).count(), 2);
+    }
+
+    #[test]
+    fn test_reproducer() {
+        let case = Case {
+            rule: "B2/S".parse().unwrap(),
+            parameters: "tfhe-lib",
+            boundary: BoundaryCondition::Mirror,
+            checks: vec![Check::Population, Check::ChangeFlag],
+            dimensions: (3, 4),
+            cells: generate(&BoardProfile::Soup { density: 0.5 }, (3, 4), 2),
+        };
+        let text = reproducer(&case, "wrong cells at generation 2");
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "#C wrong cells at generation 2");
+        assert_eq!(lines[1], "#C checks: population, change flag");
+        assert_eq!(
+            lines[2],
+            "#C replay: homomorphic_game_of_life_2 --pattern-file repro.rle --size 3x4 \
+             --offset 0,0 --rule B2/S --boundary mirror --params tfhe-lib --generations 3 \
+             --population --detect-still"
+        );
+        let file = PatternFile::from_rle(&text).unwrap();
+        assert_eq!(file.dimensions, case.dimensions);
+        assert_eq!(file.cells, case.cells);
+        assert_eq!(file.rule, Some(case.rule));
+    }
+
+    #[test]
+    fn test_sampled_cases_pass() {
+        let mut rng = Rng::new(5);
+        let config = ConfigBuilder::all_disabled().enable_default_bool().build();
+        let (client_key, server_key) = generate_keys(config);
+        for _ in 0..2 {
+            let case = Case {
+                parameters: PARAMETER_NAMES[0],
+                checks: CHECKS.to_vec(),
+                ..Case::sample(&mut rng)
+            };
+            assert_eq!(run(&case, &client_key, &server_key), Ok(()), "{:?}", case);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Canonical packing of plaintext frames into bytes, shared by everything
+//! that stores, hashes or compares frames, so that they agree on bit order.
+//!
+//! Cells are laid out in row-major order, each taking `bits_per_cell` bits,
+//! least significant first. The resulting stream of bits fills the bytes
+//! least significant bit first, and the last byte is padded with zeros.
+
+/// Number of bytes of a packed frame.
+pub fn packed_len(dimensions: (usize, usize), bits_per_cell: usize) -> usize {
+    (dimensions.0 * dimensions.1 * bits_per_cell).div_ceil(8)
+}
+
+/// Packs the states of the cells, which must fit in `bits_per_cell` bits.
+pub fn pack_frame(cells: &[u8], dimensions: (usize, usize), bits_per_cell: usize) -> Vec<u8> {
+    assert_eq!(
+        cells.len(),
+        dimensions.0 * dimensions.1,
+        "wrong number of cells"
+    );
+    assert!((1..=8).contains(&bits_per_cell), "cells have 1 to 8 bits");
+
+    let mut bytes = vec![0; packed_len(dimensions, bits_per_cell)];
+    for (k, &state) in cells.iter().enumerate() {
+        debug_assert!(u32::from(state) < 1 << bits_per_cell, "state out of range");
+        for bit in 0..bits_per_cell {
+            let position = k * bits_per_cell + bit;
+            bytes[position / 8] |= (state >> bit & 1) << (position % 8);
+        }
+    }
+    bytes
+}
+
+/// Inverse of `pack_frame`. Missing bytes read as zeros and extra ones are
+/// ignored.
+pub fn unpack_frame(bytes: &[u8], dimensions: (usize, usize), bits_per_cell: usize) -> Vec<u8> {
+    assert!((1..=8).contains(&bits_per_cell), "cells have 1 to 8 bits");
+
+    (0..dimensions.0 * dimensions.1)
+        .map(|k| {
+            (0..bits_per_cell).fold(0, |state, bit| {
+                let position = k * bits_per_cell + bit;
+                let set = bytes
+                    .get(position / 8)
+                    .is_some_and(|byte| byte >> (position % 8) & 1 == 1);
+                state | u8::from(set) << bit
+            })
+        })
+        .collect()
+}
+
+/// Packs a two-state frame, one bit per cell.
+pub fn pack_cells(cells: &[bool]) -> Vec<u8> {
+    let states: Vec<u8> = cells.iter().map(|&alive| u8::from(alive)).collect();
+    pack_frame(&states, (1, cells.len()), 1)
+}
+
+/// Inverse of `pack_cells`, for `n_cells` cells.
+pub fn unpack_cells(bytes: &[u8], n_cells: usize) -> Vec<bool> {
+    unpack_frame(bytes, (1, n_cells), 1)
+        .into_iter()
+        .map(|state| state == 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_order() {
+        assert_eq!(pack_cells(&[true, false, false, true]), vec![0b1001]);
+        assert_eq!(
+            pack_cells(&[false, false, false, false, false, false, false, false, true]),
+            vec![0, 1]
+        );
+        // the second cell starts at bit 3
+        assert_eq!(pack_frame(&[0b101, 0b011], (1, 2), 3), vec![0b011_101]);
+        assert_eq!(
+            pack_frame(&[0b110, 0b111, 0b001], (3, 1), 3),
+            vec![0b0111_1110, 0]
+        );
+        assert_eq!(unpack_cells(&[0b10], 3), vec![false, true, false]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for bits_per_cell in 1..=8 {
+            for (n_rows, n_cols) in [(1, 1), (1, 7), (3, 3), (2, 8), (5, 13)] {
+                let n_cells = n_rows * n_cols;
+                // every state in every position, starting at different offsets
+                for offset in 0..1 << bits_per_cell.min(4) {
+                    let cells: Vec<u8> = (0..n_cells)
+                        .map(|k| ((k + offset) % (1 << bits_per_cell)) as u8)
+                        .collect();
+                    let bytes = pack_frame(&cells, (n_rows, n_cols), bits_per_cell);
+                    assert_eq!(bytes.len(), packed_len((n_rows, n_cols), bits_per_cell));
+                    assert_eq!(unpack_frame(&bytes, (n_rows, n_cols), bits_per_cell), cells);
+                }
+            }
+        }
+
+        // every two-state frame of up to 10 cells
+        for n_cells in 0..=10usize {
+            for frame in 0..1u32 << n_cells {
+                let cells: Vec<bool> = (0..n_cells).map(|k| frame >> k & 1 == 1).collect();
+                let bytes = pack_cells(&cells);
+                assert_eq!(bytes.len(), n_cells.div_ceil(8));
+                assert_eq!(unpack_cells(&bytes, n_cells), cells);
+            }
+        }
+    }
+
+    #[test]
+    fn test_padding() {
+        // the padding bits of the last byte stay clear
+        assert_eq!(pack_frame(&[3; 3], (1, 3), 2), vec![0b0011_1111]);
+        assert_eq!(unpack_frame(&[0xff], (1, 5), 2), vec![3, 3, 3, 3, 0]);
+    }
+}
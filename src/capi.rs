@@ -0,0 +1,510 @@
+//! C interface to the engine, with the `capi` feature, for hosts written in
+//! other languages. `include/game_of_life.h` declares these functions, and
+//! is regenerated with `cbindgen --config cbindgen.toml --output
+//! include/game_of_life.h`. Build a library to link against with
+//! `cargo rustc --release --lib --features capi --crate-type cdylib`.
+//!
+//! Keys and boards are opaque handles, created by the functions returning
+//! them through an out pointer and released by their `_free` function. The
+//! buffers of `gol_board_serialize` are released by `gol_buffer_free`; every
+//! other buffer belongs to the caller.
+//!
+//! Every function but the `_free` ones returns a `GolStatus`. On failure, the
+//! out pointers are left untouched and `gol_last_error` describes the error.
+//! Panics are caught and reported as `GolStatus::Panic` instead of unwinding
+//! into the caller.
+//!
+//! The server key is set for the calling thread before every update, so that
+//! boards can be stepped from any thread.
+
+use crate::{decrypt_cells, keys, Board};
+use concrete::prelude::*;
+use concrete::{generate_keys, set_server_key, ClientKey, ConfigBuilder, FheBool, ServerKey};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+/// Name of the parameters of the keys, see `keys`.
+const PARAMETERS: &str = "default";
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GolStatus {
+    Ok = 0,
+    /// A null pointer, a path that is not UTF-8, or cells that do not make a
+    /// board.
+    InvalidArgument = 1,
+    /// A file could not be read or written, or does not hold what was
+    /// expected.
+    File = 2,
+    /// The bitmask is too small for the board.
+    BufferTooSmall = 3,
+    Panic = 4,
+}
+
+/// The client and server keys.
+pub struct GolKeys {
+    client_key: ClientKey,
+    server_key: ServerKey,
+}
+
+/// An encrypted board.
+pub struct GolBoard(Board);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, recording its error or panic for `gol_last_error`.
+fn ffi_call(f: impl FnOnce() -> Result<(), (GolStatus, String)>) -> GolStatus {
+    let (status, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return GolStatus::Ok,
+        Ok(Err(error)) => error,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            (GolStatus::Panic, message)
+        }
+    };
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    status
+}
+
+fn invalid(message: &str) -> (GolStatus, String) {
+    (GolStatus::InvalidArgument, message.to_string())
+}
+
+fn file_error(path: &str, err: keys::FileError) -> (GolStatus, String) {
+    (GolStatus::File, format!("{}: {}", path, err))
+}
+
+/// The string behind `path`, which must be null or a valid C string.
+unsafe fn path_arg<'a>(path: *const c_char) -> Result<&'a str, (GolStatus, String)> {
+    if path.is_null() {
+        return Err(invalid("null path"));
+    }
+    CStr::from_ptr(path)
+        .to_str()
+        .map_err(|_| invalid("the path is not UTF-8"))
+}
+
+/// Stores `value` in a new handle behind `out`.
+unsafe fn give<T>(out: *mut *mut T, value: T) -> Result<(), (GolStatus, String)> {
+    if out.is_null() {
+        return Err(invalid("null out pointer"));
+    }
+    *out = Box::into_raw(Box::new(value));
+    Ok(())
+}
+
+/// The message of the last error of the calling thread, or null if no call
+/// failed yet. The string is owned by the library and valid until the next
+/// failing call on the thread.
+#[no_mangle]
+pub extern "C" fn gol_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Generates new keys.
+///
+/// # Safety
+///
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_keys_generate(out: *mut *mut GolKeys) -> GolStatus {
+    ffi_call(|| {
+        let config = ConfigBuilder::all_disabled().enable_default_bool().build();
+        let (client_key, server_key) = generate_keys(config);
+        give(
+            out,
+            GolKeys {
+                client_key,
+                server_key,
+            },
+        )
+    })
+}
+
+/// Reads keys written by `gol_keys_save`, or by the `--keys` option.
+///
+/// # Safety
+///
+/// `path` must be a C string and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_keys_load(path: *const c_char, out: *mut *mut GolKeys) -> GolStatus {
+    ffi_call(|| {
+        let path = path_arg(path)?;
+        let (client_key, server_key) =
+            keys::load(Path::new(path), PARAMETERS).map_err(|err| file_error(path, err))?;
+        give(
+            out,
+            GolKeys {
+                client_key,
+                server_key,
+            },
+        )
+    })
+}
+
+/// Writes the keys to a file only readable by its owner.
+///
+/// # Safety
+///
+/// `keys` must come from `gol_keys_generate` or `gol_keys_load` and `path`
+/// must be a C string.
+#[no_mangle]
+pub unsafe extern "C" fn gol_keys_save(keys: *const GolKeys, path: *const c_char) -> GolStatus {
+    ffi_call(|| {
+        let keys = keys.as_ref().ok_or_else(|| invalid("null keys"))?;
+        let path = path_arg(path)?;
+        keys::save(
+            Path::new(path),
+            PARAMETERS,
+            &keys.client_key,
+            &keys.server_key,
+        )
+        .map_err(|err| file_error(path, err))
+    })
+}
+
+/// Releases keys. Null is ignored.
+///
+/// # Safety
+///
+/// `keys` must be null or come from `gol_keys_generate` or `gol_keys_load`,
+/// and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gol_keys_free(keys: *mut GolKeys) {
+    if !keys.is_null() {
+        drop(Box::from_raw(keys));
+    }
+}
+
+/// Encrypts a board of `len` cells in rows of `n_cols`, in row-major order,
+/// a cell being alive when its byte is not 0.
+///
+/// # Safety
+///
+/// `keys` must be a key handle, `cells` valid for `len` bytes and `out`
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_new(
+    keys: *const GolKeys,
+    cells: *const u8,
+    len: usize,
+    n_cols: usize,
+    out: *mut *mut GolBoard,
+) -> GolStatus {
+    ffi_call(|| {
+        let keys = keys.as_ref().ok_or_else(|| invalid("null keys"))?;
+        if cells.is_null() {
+            return Err(invalid("null cells"));
+        }
+        if n_cols == 0 || len == 0 || !len.is_multiple_of(n_cols) {
+            return Err(invalid(&format!(
+                "{} cells cannot be split in rows of {}",
+                len, n_cols
+            )));
+        }
+        let encrypt = |alive| FheBool::encrypt(alive, &keys.client_key);
+        let states = std::slice::from_raw_parts(cells, len)
+            .iter()
+            .map(|&cell| encrypt(cell != 0))
+            .collect();
+        let zeros = (encrypt(false), encrypt(false), encrypt(false));
+        give(out, GolBoard(Board::new(n_cols, states, zeros)))
+    })
+}
+
+/// Writes the number of rows and columns of the board.
+///
+/// # Safety
+///
+/// `board` must be a board handle, `n_rows` and `n_cols` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_dimensions(
+    board: *const GolBoard,
+    n_rows: *mut usize,
+    n_cols: *mut usize,
+) -> GolStatus {
+    ffi_call(|| {
+        let board = board.as_ref().ok_or_else(|| invalid("null board"))?;
+        if n_rows.is_null() || n_cols.is_null() {
+            return Err(invalid("null out pointer"));
+        }
+        (*n_rows, *n_cols) = board.0.dimensions();
+        Ok(())
+    })
+}
+
+/// Plays `n` generations of the board with the server key.
+///
+/// # Safety
+///
+/// `keys` must be a key handle and `board` a board handle encrypted with
+/// them.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_step(
+    keys: *const GolKeys,
+    board: *mut GolBoard,
+    n: usize,
+) -> GolStatus {
+    ffi_call(|| {
+        let keys = keys.as_ref().ok_or_else(|| invalid("null keys"))?;
+        let board = board.as_mut().ok_or_else(|| invalid("null board"))?;
+        set_server_key(keys.server_key.clone());
+        for _ in 0..n {
+            board.0.update();
+        }
+        Ok(())
+    })
+}
+
+/// Writes the bits of the decrypted cells to `bitmask`, in row-major order,
+/// the least significant bit of every byte first. `len` must be at least
+/// the number of cells divided by 8, rounded up.
+///
+/// # Safety
+///
+/// `keys` and `board` must be handles, and `bitmask` valid for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_decrypt(
+    keys: *const GolKeys,
+    board: *const GolBoard,
+    bitmask: *mut u8,
+    len: usize,
+) -> GolStatus {
+    ffi_call(|| {
+        let keys = keys.as_ref().ok_or_else(|| invalid("null keys"))?;
+        let board = board.as_ref().ok_or_else(|| invalid("null board"))?;
+        if bitmask.is_null() {
+            return Err(invalid("null bitmask"));
+        }
+        let cells = decrypt_cells(board.0.states(), &keys.client_key);
+        let needed = cells.len().div_ceil(8);
+        if len < needed {
+            return Err((
+                GolStatus::BufferTooSmall,
+                format!("{} bytes are needed for {} cells", needed, cells.len()),
+            ));
+        }
+        let bitmask = std::slice::from_raw_parts_mut(bitmask, needed);
+        bitmask.fill(0);
+        for (k, _) in cells.iter().enumerate().filter(|(_, alive)| **alive) {
+            bitmask[k / 8] |= 1 << (k % 8);
+        }
+        Ok(())
+    })
+}
+
+/// Serializes the encrypted board, as `Board::save` writes it, to a buffer
+/// allocated by the library and released by `gol_buffer_free`.
+///
+/// # Safety
+///
+/// `board` must be a board handle, `buffer` and `len` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_serialize(
+    board: *const GolBoard,
+    buffer: *mut *mut u8,
+    len: *mut usize,
+) -> GolStatus {
+    ffi_call(|| {
+        let board = board.as_ref().ok_or_else(|| invalid("null board"))?;
+        if buffer.is_null() || len.is_null() {
+            return Err(invalid("null out pointer"));
+        }
+        let bytes = board.0.to_bytes(PARAMETERS).into_boxed_slice();
+        *len = bytes.len();
+        *buffer = Box::into_raw(bytes).cast();
+        Ok(())
+    })
+}
+
+/// Reads a board from the bytes of `gol_board_serialize`, which stay owned
+/// by the caller.
+///
+/// # Safety
+///
+/// `buffer` must be valid for `len` bytes and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_deserialize(
+    buffer: *const u8,
+    len: usize,
+    out: *mut *mut GolBoard,
+) -> GolStatus {
+    ffi_call(|| {
+        if buffer.is_null() {
+            return Err(invalid("null buffer"));
+        }
+        let bytes = std::slice::from_raw_parts(buffer, len);
+        let board = Board::from_bytes(bytes, PARAMETERS)
+            .map_err(|err| (GolStatus::File, format!("invalid board: {}", err)))?;
+        give(out, GolBoard(board))
+    })
+}
+
+/// Releases a buffer of `gol_board_serialize`. Null is ignored.
+///
+/// # Safety
+///
+/// `buffer` and `len` must be null or as returned by `gol_board_serialize`,
+/// and the buffer not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gol_buffer_free(buffer: *mut u8, len: usize) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)));
+    }
+}
+
+/// Releases a board. Null is ignored.
+///
+/// # Safety
+///
+/// `board` must be null or come from `gol_board_new` or
+/// `gol_board_deserialize`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gol_board_free(board: *mut GolBoard) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blinker_round_trip() {
+        let mut blinker = [0u8; 25];
+        for k in [7, 12, 17] {
+            blinker[k] = 1;
+        }
+        let expected_bits = |cells: &[u8]| {
+            let mut bits = [0u8; 4];
+            for (k, _) in cells.iter().enumerate().filter(|(_, cell)| **cell != 0) {
+                bits[k / 8] |= 1 << (k % 8);
+            }
+            bits
+        };
+
+        unsafe {
+            let mut keys = ptr::null_mut();
+            assert_eq!(gol_keys_generate(&mut keys), GolStatus::Ok);
+            let mut board = ptr::null_mut();
+            assert_eq!(
+                gol_board_new(keys, blinker.as_ptr(), blinker.len(), 5, &mut board),
+                GolStatus::Ok
+            );
+            let (mut n_rows, mut n_cols) = (0, 0);
+            assert_eq!(
+                gol_board_dimensions(board, &mut n_rows, &mut n_cols),
+                GolStatus::Ok
+            );
+            assert_eq!((n_rows, n_cols), (5, 5));
+            assert_eq!(gol_board_step(keys, board, 1), GolStatus::Ok);
+
+            let (mut buffer, mut len) = (ptr::null_mut(), 0);
+            assert_eq!(
+                gol_board_serialize(board, &mut buffer, &mut len),
+                GolStatus::Ok
+            );
+            gol_board_free(board);
+            let mut board = ptr::null_mut();
+            assert_eq!(
+                gol_board_deserialize(buffer, len, &mut board),
+                GolStatus::Ok
+            );
+            gol_buffer_free(buffer, len);
+
+            let mut bits = [0xffu8; 4];
+            assert_eq!(
+                gol_board_decrypt(keys, board, bits.as_mut_ptr(), bits.len()),
+                GolStatus::Ok
+            );
+            let mut horizontal = [0u8; 25];
+            horizontal[11..14].fill(1);
+            assert_eq!(bits, expected_bits(&horizontal));
+
+            assert_eq!(gol_board_step(keys, board, 1), GolStatus::Ok);
+            assert_eq!(
+                gol_board_decrypt(keys, board, bits.as_mut_ptr(), bits.len()),
+                GolStatus::Ok
+            );
+            assert_eq!(bits, expected_bits(&blinker));
+
+            gol_board_free(board);
+            gol_keys_free(keys);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let message = || {
+            unsafe { CStr::from_ptr(gol_last_error()) }
+                .to_str()
+                .unwrap()
+        };
+        unsafe {
+            let mut keys = ptr::null_mut();
+            assert_eq!(gol_keys_generate(&mut keys), GolStatus::Ok);
+
+            let mut board = ptr::null_mut();
+            let cells = [1u8; 6];
+            assert_eq!(
+                gol_board_new(keys, cells.as_ptr(), cells.len(), 4, &mut board),
+                GolStatus::InvalidArgument
+            );
+            assert!(board.is_null());
+            assert_eq!(message(), "6 cells cannot be split in rows of 4");
+
+            assert_eq!(
+                gol_board_new(keys, cells.as_ptr(), cells.len(), 3, &mut board),
+                GolStatus::Ok
+            );
+            let mut bits = [0u8; 0];
+            assert_eq!(
+                gol_board_decrypt(keys, board, bits.as_mut_ptr(), bits.len()),
+                GolStatus::BufferTooSmall
+            );
+            assert_eq!(message(), "1 bytes are needed for 6 cells");
+            assert_eq!(
+                gol_board_step(ptr::null(), board, 1),
+                GolStatus::InvalidArgument
+            );
+
+            let mut other = ptr::null_mut();
+            assert_eq!(
+                gol_board_deserialize(cells.as_ptr(), cells.len(), &mut other),
+                GolStatus::File
+            );
+            assert!(other.is_null());
+
+            let path = CString::new("/nonexistent/keys.bin").unwrap();
+            let mut loaded = ptr::null_mut();
+            assert_eq!(gol_keys_load(path.as_ptr(), &mut loaded), GolStatus::File);
+            assert!(loaded.is_null());
+
+            gol_board_free(board);
+            gol_keys_free(keys);
+        }
+    }
+
+    #[test]
+    fn test_panics_are_caught() {
+        let status = ffi_call(|| panic!("boom"));
+        assert_eq!(status, GolStatus::Panic);
+        let message = unsafe { CStr::from_ptr(gol_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "boom");
+    }
+}
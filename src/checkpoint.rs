@@ -0,0 +1,561 @@
+//! Chains of checkpoints, for boards too big to be written whole after every
+//! generation: a full checkpoint every few checkpoints and, in between,
+//! deltas holding only the cells that changed since the previous one.
+//!
+//! A chain lives in a `Storage`, such as a directory: `full-<generation>`
+//! is a board as written by `Board::save`, `delta-<generation>` the changed
+//! cells and their indices, and `index` lists the checkpoints of the chain
+//! in order, so that a chain missing one of them is refused before anything
+//! is restored.
+//!
+//! The index is the commit record of the chain: a checkpoint is written
+//! before the index listing it, and the checkpoints of a chain are only
+//! deleted once the index no longer lists them, so that a run interrupted
+//! at any point leaves a chain that restores to one of its checkpoints, even
+//! in an object store, where nothing can be renamed.
+
+use crate::keys::{self, FileError};
+use crate::storage::Storage;
+use crate::{decrypt_cells, Board, Saved, Snapshot};
+use concrete::{ClientKey, FheBool};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::io::ErrorKind;
+
+pub(crate) const INDEX: &str = "index";
+const FULL: &str = "full";
+pub(crate) const DELTA: &str = "delta";
+
+/// The checkpoints of a chain: the generation of its full checkpoint, and
+/// those of its deltas in the order they apply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Index {
+    full: usize,
+    deltas: Vec<usize>,
+}
+
+impl Index {
+    fn last(&self) -> usize {
+        self.deltas.last().copied().unwrap_or(self.full)
+    }
+}
+
+/// What a delta file holds after the header: its generation, the generation
+/// of the checkpoint it applies to, and the changed cells with their indices.
+pub(crate) type Delta = (usize, usize, Vec<(usize, FheBool)>);
+
+#[derive(Debug)]
+pub enum ChainError {
+    /// A file of the chain could not be read.
+    File { location: String, err: FileError },
+    /// A checkpoint listed in the index is missing.
+    Missing { generation: usize, location: String },
+    /// A delta applies to another checkpoint than the one before it.
+    Broken {
+        generation: usize,
+        after: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::File { location, err } => write!(f, "{}: {}", location, err),
+            Self::Missing {
+                generation,
+                location,
+            } => write!(
+                f,
+                "broken chain of checkpoints: the checkpoint of generation {} is missing ({})",
+                generation, location
+            ),
+            Self::Broken {
+                generation,
+                after,
+                expected,
+            } => write!(
+                f,
+                "broken chain of checkpoints: the delta of generation {} follows generation {}, \
+                 not {}",
+                generation, after, expected
+            ),
+        }
+    }
+}
+
+impl Error for ChainError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::File { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn checkpoint_key(kind: &str, generation: usize) -> String {
+    format!("{}-{}", kind, generation)
+}
+
+fn invalid(location: String, message: String) -> ChainError {
+    ChainError::File {
+        location,
+        err: FileError::Format(Box::new(bincode::ErrorKind::Custom(message))),
+    }
+}
+
+/// Writes `value` as `keys::write_file` does, to the object `key`.
+fn write<T: Serialize>(
+    storage: &dyn Storage,
+    key: &str,
+    parameters: &str,
+    value: &T,
+) -> Result<(), FileError> {
+    let mut bytes = Vec::new();
+    keys::write_to(&mut bytes, parameters, value)?;
+    storage.put(key, &mut bytes.as_slice())?;
+    Ok(())
+}
+
+/// Reads a value written by `write` with the same parameters.
+fn read<T: DeserializeOwned>(
+    storage: &dyn Storage,
+    key: &str,
+    parameters: &str,
+) -> Result<T, ChainError> {
+    storage
+        .get(key)
+        .map_err(FileError::from)
+        .and_then(|body| keys::read_from(body, parameters))
+        .map_err(|err| ChainError::File {
+            location: storage.location(key),
+            err,
+        })
+}
+
+/// Writes the checkpoints of a run to a chain.
+pub struct CheckpointChain {
+    storage: Box<dyn Storage>,
+    parameters: String,
+    full_every: usize,
+    /// The chain being written, or the one found in the directory until the
+    /// first full checkpoint replaces it.
+    index: Option<Index>,
+    /// The board at the last checkpoint, which the deltas are taken against.
+    last: Option<Snapshot>,
+}
+
+impl CheckpointChain {
+    /// Writes a chain to `storage`, with the named parameters and a full
+    /// checkpoint every `full_every` checkpoints. A chain already there is
+    /// removed by the first checkpoint, which is a full one. Fails if there
+    /// is an index that cannot be read, rather than take it for no chain.
+    pub fn create(
+        storage: Box<dyn Storage>,
+        parameters: &str,
+        full_every: usize,
+    ) -> Result<Self, ChainError> {
+        assert!(
+            full_every > 0,
+            "full checkpoints must be at least every checkpoint"
+        );
+        let index = match read(&*storage, INDEX, parameters) {
+            Ok(index) => Some(index),
+            Err(ChainError::File {
+                err: FileError::Io(err),
+                ..
+            }) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            index,
+            storage,
+            parameters: parameters.to_string(),
+            full_every,
+            last: None,
+        })
+    }
+
+    /// Where the chain is written.
+    pub fn storage(&self) -> &dyn Storage {
+        &*self.storage
+    }
+
+    /// Whether the next checkpoint is a full one.
+    pub fn full_due(&self) -> bool {
+        match (&self.index, &self.last) {
+            (Some(index), Some(_)) => index.deltas.len() + 1 >= self.full_every,
+            _ => true,
+        }
+    }
+
+    /// Writes a checkpoint of `board`: a full one when due, the cells that
+    /// changed since the last checkpoint otherwise, which the client finds
+    /// by decrypting `Board::changes`. Returns the number of cells written.
+    pub fn record(&mut self, board: &Board, client_key: &ClientKey) -> Result<usize, FileError> {
+        match self.last.as_ref().filter(|_| !self.full_due()) {
+            Some(last) => {
+                let changed = decrypt_cells(&board.changes(last), client_key);
+                self.write_delta(board, &changed)
+            }
+            None => self.write_full(board),
+        }
+    }
+
+    /// `record`, with the cells that changed since the last checkpoint known
+    /// in plaintext, in row-major order. They are ignored when a full
+    /// checkpoint is due.
+    pub fn record_changes(&mut self, board: &Board, changed: &[bool]) -> Result<usize, FileError> {
+        if self.full_due() {
+            self.write_full(board)
+        } else {
+            self.write_delta(board, changed)
+        }
+    }
+
+    fn write_full(&mut self, board: &Board) -> Result<usize, FileError> {
+        let generation = board.generation();
+        let full = checkpoint_key(FULL, generation);
+        let storage = &*self.storage;
+        write(storage, &full, &self.parameters, &board.saved())?;
+        let index = Index {
+            full: generation,
+            deltas: Vec::new(),
+        };
+        write(storage, INDEX, &self.parameters, &index)?;
+
+        self.last = Some(board.snapshot());
+
+        // the previous chain is only removed once the index no longer lists it
+        if let Some(previous) = self.index.replace(index) {
+            let deltas = previous.deltas.iter();
+            let stale = std::iter::once(checkpoint_key(FULL, previous.full))
+                .chain(deltas.map(|&g| checkpoint_key(DELTA, g)))
+                .filter(|key| *key != full);
+            for key in stale {
+                storage.delete(&key)?;
+            }
+        }
+        Ok(board.states().len())
+    }
+
+    fn write_delta(&mut self, board: &Board, changed: &[bool]) -> Result<usize, FileError> {
+        assert_eq!(
+            changed.len(),
+            board.states().len(),
+            "the changes are not those of the board"
+        );
+        let index = self
+            .index
+            .as_mut()
+            .expect("a delta follows a full checkpoint");
+        let (generation, after) = (board.generation(), index.last());
+        assert!(
+            generation > after,
+            "a checkpoint of generation {} cannot follow one of generation {}",
+            generation,
+            after
+        );
+        let cells: Vec<(usize, &FheBool)> = changed
+            .iter()
+            .zip(board.states())
+            .enumerate()
+            .filter(|(_, (&changed, _))| changed)
+            .map(|(k, (_, cell))| (k, cell))
+            .collect();
+        let storage = &*self.storage;
+        let delta = (generation, after, &cells);
+        write(
+            storage,
+            &checkpoint_key(DELTA, generation),
+            &self.parameters,
+            &delta,
+        )?;
+        index.deltas.push(generation);
+        if let Err(err) = write(storage, INDEX, &self.parameters, index) {
+            index.deltas.pop();
+            return Err(err);
+        }
+        self.last = Some(board.snapshot());
+        Ok(cells.len())
+    }
+}
+
+/// Reads the board at the last checkpoint of the chain in `storage`,
+/// written with the named parameters, after checking that every checkpoint
+/// listed in its index is there.
+pub fn restore(storage: &dyn Storage, parameters: &str) -> Result<Board, ChainError> {
+    let index: Index = read(storage, INDEX, parameters)?;
+    let full = (index.full, checkpoint_key(FULL, index.full));
+    let deltas: Vec<_> = index
+        .deltas
+        .iter()
+        .map(|&g| (g, checkpoint_key(DELTA, g)))
+        .collect();
+    let stored = storage.list().map_err(|err| ChainError::File {
+        location: storage.location(INDEX),
+        err: err.into(),
+    })?;
+    if let Some((generation, key)) = std::iter::once(&full)
+        .chain(&deltas)
+        .find(|(_, key)| stored.binary_search(key).is_err())
+    {
+        return Err(ChainError::Missing {
+            generation: *generation,
+            location: storage.location(key),
+        });
+    }
+
+    let (generation, key) = full;
+    let saved: Saved = read(storage, &key, parameters)?;
+    let mut board = Board::from_saved(saved).map_err(|err| ChainError::File {
+        location: storage.location(&key),
+        err,
+    })?;
+    if board.generation() != generation {
+        return Err(invalid(
+            storage.location(&key),
+            format!(
+                "a board of generation {} instead of {}",
+                board.generation(),
+                generation
+            ),
+        ));
+    }
+    for (generation, key) in deltas {
+        let (found, after, cells): Delta = read(storage, &key, parameters)?;
+        if found != generation {
+            return Err(invalid(
+                storage.location(&key),
+                format!("a delta of generation {} instead of {}", found, generation),
+            ));
+        }
+        if after != board.generation {
+            return Err(ChainError::Broken {
+                generation,
+                after,
+                expected: board.generation,
+            });
+        }
+        for (k, cell) in cells {
+            if k >= board.states.len() {
+                return Err(invalid(
+                    storage.location(&key),
+                    format!("cell {} of a board of {} cells", k, board.states.len()),
+                ));
+            }
+            board.states[k] = cell;
+        }
+        board.generation = generation;
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::reference::next_generation;
+    use crate::storage::{LocalDir, Memory};
+    use crate::testing::{assert_boards_eq, encrypt_board, set_up_keys, Interrupted};
+    use std::path::Path;
+
+    fn local_dir(directory: &Path) -> Box<dyn Storage> {
+        Box::new(LocalDir::create(directory).unwrap())
+    }
+
+    #[test]
+    fn test_chain_round_trip() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 8);
+        let directory = std::env::temp_dir().join(format!("chain-{}", std::process::id()));
+        let mut chain = CheckpointChain::create(local_dir(&directory), "default", 4).unwrap();
+
+        // a full checkpoint and 3 deltas
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+        assert_eq!(chain.record(&board, &client_key).unwrap(), 30);
+        let mut expected = cells.clone();
+        for _ in 0..3 {
+            board.update();
+            let next = next_generation(&expected, dimensions);
+            let n_changed = (0..30).filter(|&k| next[k] != expected[k]).count();
+            assert_eq!(chain.record(&board, &client_key).unwrap(), n_changed);
+            expected = next;
+        }
+        assert!(chain.full_due());
+
+        let restored = restore(chain.storage(), "default").unwrap();
+        assert_eq!(restored.generation(), 3);
+        assert_boards_eq(
+            &expected,
+            &restored.decrypt(&client_key),
+            dimensions,
+            "restored",
+        );
+
+        // the next full checkpoint replaces the chain
+        board.update();
+        assert_eq!(chain.record(&board, &client_key).unwrap(), 30);
+        let mut files: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["full-4", "index"]);
+        let restored = restore(chain.storage(), "default").unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(restored.generation(), 4);
+        assert_boards_eq(
+            &next_generation(&expected, dimensions),
+            &restored.decrypt(&client_key),
+            dimensions,
+            "restored after a full checkpoint",
+        );
+    }
+
+    #[test]
+    fn test_missing_delta() {
+        let client_key = set_up_keys();
+
+        let dimensions = (4, 5);
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 2);
+        let directory = std::env::temp_dir().join(format!("broken-chain-{}", std::process::id()));
+        let mut chain = CheckpointChain::create(local_dir(&directory), "default", 8).unwrap();
+
+        // changes tracked in plaintext
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+        chain.record_changes(&board, &[]).unwrap();
+        let mut expected = cells;
+        for _ in 0..3 {
+            board.update();
+            let next = next_generation(&expected, dimensions);
+            let changed: Vec<bool> = next.iter().zip(&expected).map(|(a, b)| a != b).collect();
+            chain.record_changes(&board, &changed).unwrap();
+            expected = next;
+        }
+        assert_boards_eq(
+            &expected,
+            &restore(chain.storage(), "default")
+                .unwrap()
+                .decrypt(&client_key),
+            dimensions,
+            "restored",
+        );
+
+        let missing = directory.join("delta-2");
+        std::fs::remove_file(&missing).unwrap();
+        let err = restore(chain.storage(), "default").err().unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(err, ChainError::Missing { generation: 2, .. }));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "broken chain of checkpoints: the checkpoint of generation 2 is missing ({})",
+                missing.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_interrupted_chain() {
+        let client_key = set_up_keys();
+
+        let dimensions = (4, 5);
+        let mut frames = vec![generate(
+            &BoardProfile::Soup { density: 0.4 },
+            dimensions,
+            3,
+        )];
+        let mut boards = vec![encrypt_board(&frames[0], dimensions.1, &client_key)];
+        for _ in 0..5 {
+            frames.push(next_generation(frames.last().unwrap(), dimensions));
+            let mut board = boards.last().unwrap().clone();
+            board.update();
+            boards.push(board);
+        }
+
+        // 6 checkpoints, a full one every 3, take 15 writes to an object
+        // store: interrupt the run before each of them
+        for writes in 0..=15 {
+            let storage = Interrupted::new(Memory::default(), writes);
+            let mut chain = CheckpointChain::create(Box::new(storage), "default", 3).unwrap();
+            let (mut recorded, mut failed) = (None, None);
+            for (generation, board) in boards.iter().enumerate() {
+                let changed: Vec<bool> = match generation {
+                    0 => Vec::new(),
+                    g => frames[g]
+                        .iter()
+                        .zip(&frames[g - 1])
+                        .map(|(a, b)| a != b)
+                        .collect(),
+                };
+                match chain.record_changes(board, &changed) {
+                    Ok(_) => recorded = Some(generation),
+                    Err(_) => {
+                        failed = Some(generation);
+                        break;
+                    }
+                }
+            }
+            assert_eq!(failed.is_none(), writes == 15);
+
+            // the index names a whole chain, the one before the interrupted
+            // checkpoint or the one after it
+            match (recorded, restore(chain.storage(), "default")) {
+                (None, Err(ChainError::File { .. })) => {}
+                (Some(recorded), Ok(restored)) => {
+                    let generation = restored.generation();
+                    assert!(generation == recorded || Some(generation) == failed);
+                    assert_boards_eq(
+                        &frames[generation],
+                        &restored.decrypt(&client_key),
+                        dimensions,
+                        &format!("restored after {} writes", writes),
+                    );
+                }
+                (recorded, restored) => panic!(
+                    "{} after {} writes, with generation {:?} recorded",
+                    if restored.is_ok() {
+                        "restored"
+                    } else {
+                        "not restored"
+                    },
+                    writes,
+                    recorded
+                ),
+            }
+            if writes == 15 {
+                let keys = chain.storage().list().unwrap();
+                assert_eq!(keys, ["delta-4", "delta-5", "full-3", "index"]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_refuses_unreadable_index() {
+        assert!(CheckpointChain::create(Box::new(Memory::default()), "default", 2).is_ok());
+
+        // an index of other parameters, and one that is not an index
+        let storage = Memory::default();
+        let index = Index {
+            full: 0,
+            deltas: vec![1],
+        };
+        write(&storage, INDEX, "other", &index).unwrap();
+        let err = CheckpointChain::create(Box::new(storage), "default", 2)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ChainError::File { .. }), "{}", err);
+
+        let storage = Memory::default();
+        storage.put(INDEX, &mut &b"not an index"[..]).unwrap();
+        let err = CheckpointChain::create(Box::new(storage), "default", 2)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ChainError::File { .. }), "{}", err);
+    }
+}
@@ -0,0 +1,124 @@
+//! Help with the command line: suggestions for mistyped options and names,
+//! and shell completion.
+
+/// Number of single character insertions, deletions and substitutions that
+/// turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The candidate closest to `input`, if it is close enough to be a typo: at
+/// most a third of its characters, and at least one, differ.
+pub fn closest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let threshold = (input.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Appends " (did you mean ...?)" to `message` when a candidate is close to
+/// `input`.
+pub fn with_suggestion<'a>(
+    message: String,
+    input: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> String {
+    match closest(input, candidates) {
+        Some(suggestion) => format!("{} (did you mean {}?)", message, suggestion),
+        None => message,
+    }
+}
+
+/// A bash completion script for `program`, completing the options and the
+/// values of the options listed in `values`.
+pub fn bash_completion(program: &str, options: &[&str], values: &[(&str, Vec<&str>)]) -> String {
+    let function = format!("_{}", program.replace('-', "_"));
+    let mut script = format!(
+        "{}() {{\n    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    \
+         local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    case \"$prev\" in\n",
+        function
+    );
+    for (option, option_values) in values {
+        script += &format!(
+            "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+            option,
+            option_values.join(" ")
+        );
+    }
+    script += &format!(
+        "    esac\n    COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n}}\ncomplete -F {} {}\n",
+        options.join(" "),
+        function,
+        program
+    );
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use homomorphic_game_of_life_2::pattern::CATALOG;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("grid", ""), 4);
+        assert_eq!(edit_distance("grid", "grid"), 0);
+        assert_eq!(edit_distance("grdi", "grid"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let options = [
+            "--grid",
+            "--generations",
+            "--neighbour-counts",
+            "--seed",
+            "--size",
+        ];
+        assert_eq!(closest("--generation", options), Some("--generations"));
+        assert_eq!(closest("--gird", options), Some("--grid"));
+        assert_eq!(
+            closest("--neighbor-counts", options),
+            Some("--neighbour-counts")
+        );
+        assert_eq!(closest("--sed", options), Some("--seed"));
+        assert_eq!(closest("--verbose", options), None);
+
+        let names = CATALOG.iter().map(|pattern| pattern.name);
+        assert_eq!(closest("glidr", names.clone()), Some("glider"));
+        assert_eq!(closest("pulsr", names.clone()), Some("pulsar"));
+        assert_eq!(closest("penta", names), None);
+
+        assert_eq!(
+            with_suggestion("unknown argument: --gird".to_string(), "--gird", options),
+            "unknown argument: --gird (did you mean --grid?)"
+        );
+    }
+
+    #[test]
+    fn test_bash_completion() {
+        let names: Vec<&str> = CATALOG.iter().map(|pattern| pattern.name).collect();
+        let script = bash_completion("life", &["--grid", "--catalog"], &[("--catalog", names)]);
+
+        assert!(script.contains("compgen -W \"--grid --catalog\""));
+        for pattern in &CATALOG {
+            assert!(script.contains(pattern.name));
+        }
+        assert!(script.ends_with("complete -F _life life\n"));
+    }
+}
@@ -0,0 +1,246 @@
+//! Running estimate of the population of a generation still being computed,
+//! from a few cells of every row decrypted as soon as the row is done (see
+//! `Board::update_by_rows`), for boards whose generations take minutes.
+//!
+//! The sampled cells are a sample without replacement of the cells computed
+//! so far, so the confidence bounds use the finite population correction:
+//! they shrink to nothing once every computed cell is sampled. The rows not
+//! computed yet are assumed to be like those already computed.
+
+use crate::benchgen::Rng;
+use concrete::prelude::*;
+use concrete::{ClientKey, FheBool};
+use std::fmt;
+
+/// Half width of a 95% confidence interval, in standard errors.
+const Z_95: f64 = 1.96;
+
+/// `k` distinct indices below `n` picked at random, all of them if `k` is
+/// larger.
+pub fn pick(n: usize, k: usize, rng: &mut Rng) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..n).collect();
+    let k = k.min(n);
+    for i in 0..k {
+        let j = i + rng.below(n - i);
+        indices.swap(i, j);
+    }
+    indices.truncate(k);
+    indices
+}
+
+/// Decrypts `k` cells of `cells` picked at random. Runs on the client.
+pub fn sample_cells(
+    cells: &[FheBool],
+    k: usize,
+    rng: &mut Rng,
+    client_key: &ClientKey,
+) -> Vec<bool> {
+    pick(cells.len(), k, rng)
+        .into_iter()
+        .map(|k| cells[k].decrypt(client_key))
+        .collect()
+}
+
+/// The samples of the cells computed so far in a generation.
+#[derive(Debug, Clone)]
+pub struct PopulationEstimator {
+    n_cells: usize,
+    seen: usize,
+    sampled: usize,
+    alive: usize,
+}
+
+impl PopulationEstimator {
+    /// An estimator for a board of `n_cells` cells, none of them computed.
+    pub fn new(n_cells: usize) -> Self {
+        Self {
+            n_cells,
+            seen: 0,
+            sampled: 0,
+            alive: 0,
+        }
+    }
+
+    /// Records `computed` more computed cells, `sample` being some of them.
+    pub fn observe(&mut self, computed: usize, sample: &[bool]) {
+        assert!(
+            sample.len() <= computed,
+            "{} cells sampled out of {}",
+            sample.len(),
+            computed
+        );
+        self.seen += computed;
+        self.sampled += sample.len();
+        self.alive += sample.iter().filter(|&&alive| alive).count();
+    }
+
+    /// The estimate so far, once a cell was sampled.
+    pub fn estimate(&self) -> Option<Estimate> {
+        if self.sampled == 0 {
+            return None;
+        }
+        let (n, m) = (self.sampled as f64, self.seen as f64);
+        let fraction = self.alive as f64 / n;
+        let margin = if self.sampled == self.seen {
+            0.0
+        } else if self.sampled < 2 {
+            // no idea of the variance yet
+            1.0
+        } else {
+            let variance = fraction * (1.0 - fraction) / (n - 1.0) * (1.0 - n / m);
+            Z_95 * variance.sqrt()
+        };
+        Some(Estimate {
+            population: fraction * self.n_cells as f64,
+            margin: margin * self.n_cells as f64,
+            seen: self.seen,
+            n_cells: self.n_cells,
+        })
+    }
+}
+
+/// Estimated population of the board, with the half width of its 95%
+/// confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub population: f64,
+    pub margin: f64,
+    pub seen: usize,
+    pub n_cells: usize,
+}
+
+impl Estimate {
+    /// Bounds of the confidence interval, within the board.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.population - self.margin).max(0.0),
+            (self.population + self.margin).min(self.n_cells as f64),
+        )
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (low, high) = self.bounds();
+        write!(
+            f,
+            "estimated population so far: {:.0} (95% in {:.0}..{:.0}), {} of {} cells seen",
+            self.population, low, high, self.seen, self.n_cells
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::testing::{encrypt_board, set_up_keys};
+
+    #[test]
+    fn test_pick() {
+        let mut rng = Rng::new(4);
+        let mut indices = pick(10, 4, &mut rng);
+        assert_eq!(indices.len(), 4);
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), 4);
+        assert!(indices.iter().all(|&k| k < 10));
+        let mut all = pick(3, 5, &mut rng);
+        all.sort();
+        assert_eq!(all, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_exact_when_everything_is_sampled() {
+        let cells = generate(&BoardProfile::Soup { density: 0.3 }, (8, 8), 1);
+        let mut estimator = PopulationEstimator::new(64);
+        assert_eq!(estimator.estimate(), None);
+        for row in cells.chunks(8) {
+            estimator.observe(8, row);
+        }
+        let estimate = estimator.estimate().unwrap();
+        let population = cells.iter().filter(|&&alive| alive).count();
+        assert_eq!(estimate.population, population as f64);
+        assert_eq!(estimate.margin, 0.0);
+        assert_eq!(
+            estimate.to_string(),
+            format!(
+                "estimated population so far: {} (95% in {}..{}), 64 of 64 cells seen",
+                population, population, population
+            )
+        );
+    }
+
+    #[test]
+    fn test_partial_estimate() {
+        // 32 cells seen out of 64, 4 sampled and 1 alive
+        let mut estimator = PopulationEstimator::new(64);
+        estimator.observe(16, &[true, false]);
+        estimator.observe(16, &[false, false]);
+        let estimate = estimator.estimate().unwrap();
+        assert_eq!(estimate.population, 16.0);
+        let variance: f64 = 0.25 * 0.75 / 3.0 * (1.0 - 4.0 / 32.0);
+        assert!((estimate.margin - 64.0 * Z_95 * variance.sqrt()).abs() < 1e-9);
+        assert_eq!(estimate.bounds().0, 0.0);
+
+        let mut estimator = PopulationEstimator::new(64);
+        estimator.observe(8, &[true]);
+        assert_eq!(estimator.estimate().unwrap().bounds(), (0.0, 64.0));
+    }
+
+    #[test]
+    fn test_coverage() {
+        // 400 cells, 120 alive, samples of 40 without replacement
+        let cells: Vec<bool> = (0..400).map(|k| k % 10 < 3).collect();
+        let mut rng = Rng::new(9);
+        let trials = 2000;
+        let covered = (0..trials)
+            .filter(|_| {
+                let sample: Vec<bool> = pick(400, 40, &mut rng)
+                    .into_iter()
+                    .map(|k| cells[k])
+                    .collect();
+                let mut estimator = PopulationEstimator::new(400);
+                estimator.observe(400, &sample);
+                let (low, high) = estimator.estimate().unwrap().bounds();
+                low <= 120.0 && 120.0 <= high
+            })
+            .count();
+        let coverage = covered as f64 / trials as f64;
+        assert!((0.92..0.98).contains(&coverage), "coverage {}", coverage);
+    }
+
+    #[test]
+    fn test_converges_during_update() {
+        let client_key = set_up_keys();
+
+        let dimensions = (6, 7);
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 6);
+        let board = encrypt_board(&cells, 7, &client_key);
+        let population = crate::reference::next_generation(&cells, dimensions)
+            .iter()
+            .filter(|&&alive| alive)
+            .count() as f64;
+
+        for (per_row, exact) in [(3, false), (7, true)] {
+            let mut board = board.clone();
+            let mut estimator = PopulationEstimator::new(42);
+            let mut rng = Rng::new(1);
+            let mut rows = Vec::new();
+            board.update_by_rows(&mut |row, cells| {
+                rows.push(row);
+                let sample = sample_cells(cells, per_row, &mut rng, &client_key);
+                estimator.observe(cells.len(), &sample);
+            });
+            assert_eq!(rows, [0, 1, 2, 3, 4, 5]);
+
+            // the coverage of the partial estimates is checked on plaintexts
+            let estimate = estimator.estimate().unwrap();
+            assert_eq!(estimate.seen, 42);
+            assert_eq!(estimate.margin == 0.0, exact);
+            if exact {
+                assert_eq!(estimate.population, population);
+            }
+        }
+    }
+}
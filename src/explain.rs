@@ -0,0 +1,466 @@
+//! Short explanations of the names of the command line, for `--explain`:
+//! every parameter set, rule, boundary condition, board profile, kind of
+//! leak, stop condition and feature.
+//!
+//! The entries are built from the lists the options are checked against
+//! (`keys::PARAMETER_NAMES`, `BoundaryCondition::ALL`, `Leak::ALL`...), and
+//! their constraints from those of the options, so a new name without an
+//! explanation fails the tests instead of going unexplained.
+
+use crate::audit::Leak;
+use crate::{OnFull, CONWAY_ONLY, STILL_FLAGS_PER_FRAME, TOROIDAL_ONLY};
+use homomorphic_game_of_life_2::benchgen::BoardProfile;
+use homomorphic_game_of_life_2::{keys, rule, BoundaryCondition};
+use serde::Serialize;
+use std::fmt;
+
+/// What an entry explains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Parameters,
+    Rule,
+    Boundary,
+    Profile,
+    Leak,
+    StopCondition,
+    Feature,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Parameters => "parameter set",
+            Self::Rule => "rule",
+            Self::Boundary => "boundary condition",
+            Self::Profile => "board profile",
+            Self::Leak => "leak",
+            Self::StopCondition => "stop condition",
+            Self::Feature => "feature",
+        })
+    }
+}
+
+/// The explanation of a topic.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entry {
+    pub topic: String,
+    pub kind: Kind,
+    pub summary: String,
+    /// Options it needs or cannot be combined with.
+    pub constraints: Vec<String>,
+    /// What it costs, when known.
+    pub cost: Option<String>,
+    /// Related topics.
+    pub see_also: Vec<String>,
+}
+
+impl Entry {
+    fn new(topic: &str, kind: Kind, summary: &str) -> Self {
+        Self {
+            topic: topic.to_string(),
+            kind,
+            summary: summary.to_string(),
+            constraints: Vec::new(),
+            cost: None,
+            see_also: Vec::new(),
+        }
+    }
+
+    fn constraint(mut self, constraint: String) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Refers to the other topics of `topics`.
+    fn see_also<'a>(mut self, topics: impl IntoIterator<Item = &'a str>) -> Self {
+        let own = self.topic.clone();
+        self.see_also.extend(
+            topics
+                .into_iter()
+                .filter(|topic| *topic != own)
+                .map(String::from),
+        );
+        self
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} ({})", self.topic, self.kind)?;
+        writeln!(f, "    {}", self.summary)?;
+        for constraint in &self.constraints {
+            writeln!(f, "    - {}", constraint)?;
+        }
+        if let Some(cost) = &self.cost {
+            writeln!(f, "    Cost: {}", cost)?;
+        }
+        if !self.see_also.is_empty() {
+            writeln!(f, "    See also: {}", self.see_also.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The cargo features of the crate, with whether this build has them.
+const FEATURES: [(&str, bool); 4] = [
+    ("capi", cfg!(feature = "capi")),
+    ("fixtures", cfg!(feature = "fixtures")),
+    ("latex-export", cfg!(feature = "latex-export")),
+    ("phase-timer", cfg!(feature = "phase-timer")),
+];
+
+/// The options ending a run.
+const STOP_CONDITIONS: [&str; 4] = ["generations", "time-limit", "detect-still", "on-full"];
+
+fn parameters() -> Vec<Entry> {
+    keys::PARAMETER_NAMES
+        .into_iter()
+        .map(|name| {
+            let summary = match name {
+                "default" => "The default parameters of the boolean keys of concrete.",
+                "tfhe-lib" => "The parameters of the TFHE library, to compare with it.",
+                _ => "",
+            };
+            let mut entry = Entry::new(name, Kind::Parameters, summary)
+                .constraint(format!("chosen with --params {}", name))
+                .constraint(
+                    "keys, boards, checkpoints and patches record their parameters, and are \
+                     refused under other ones"
+                        .to_string(),
+                )
+                .see_also(keys::PARAMETER_NAMES);
+            entry.cost = Some(
+                "not calibrated, `cargo run --release --bin bench` times the key generation \
+                 and the updates of every parameter set"
+                    .to_string(),
+            );
+            entry
+        })
+        .collect()
+}
+
+fn rules() -> Vec<Entry> {
+    let conway = rule::Rule::conway();
+    vec![
+        Entry::new(
+            "rule",
+            Kind::Rule,
+            "An outer totalistic rule, given as --rule B<counts>/S<counts>: the numbers of \
+             live neighbours for which a dead cell is born and a live cell survives.",
+        )
+        .constraint(format!("{} only know {}", CONWAY_ONLY.join(", "), conway))
+        .see_also(["conway"]),
+        Entry::new(
+            "conway",
+            Kind::Rule,
+            &format!("Conway's game of life, {}, the default rule.", conway),
+        )
+        .see_also(["rule"]),
+    ]
+}
+
+fn boundaries() -> Vec<Entry> {
+    let names: Vec<String> = BoundaryCondition::ALL
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    BoundaryCondition::ALL
+        .into_iter()
+        .map(|boundary| {
+            let summary = match boundary {
+                BoundaryCondition::Toroidal => {
+                    "The edges wrap around: the board is a torus. The default, also named \
+                     periodic."
+                }
+                BoundaryCondition::Fixed => {
+                    "The cells outside the board are always dead. Also named dead."
+                }
+                BoundaryCondition::Mirror => {
+                    "The edges reflect the board: the cells beyond an edge are copies of those \
+                     on it."
+                }
+            };
+            let mut entry = Entry::new(&boundary.to_string(), Kind::Boundary, summary)
+                .see_also(names.iter().map(String::as_str));
+            if boundary != BoundaryCondition::Toroidal {
+                entry = entry.constraint(format!(
+                    "cannot be combined with {}",
+                    TOROIDAL_ONLY.join(", ")
+                ));
+            }
+            if boundary == BoundaryCondition::Fixed {
+                entry = entry.constraint("needed by --quadrants".to_string());
+            }
+            entry
+        })
+        .collect()
+}
+
+fn profiles() -> Vec<Entry> {
+    let kinds: Vec<&str> = BoardProfile::SYNTAX
+        .iter()
+        .map(|syntax| syntax.split(':').next().unwrap())
+        .collect();
+    BoardProfile::SYNTAX
+        .into_iter()
+        .zip(&kinds)
+        .map(|(syntax, kind)| {
+            let summary = match *kind {
+                "soup" => "Every cell is alive with the given probability.",
+                "clusters" => "Square blobs, half filled, with sides up to the given size.",
+                "gliders" => "Gliders at random positions, in any of their 8 orientations.",
+                "ash" => "A 50% soup evolved for some generations with the plaintext game.",
+                _ => "",
+            };
+            Entry::new(kind, Kind::Profile, summary)
+                .constraint(format!("given as --board-profile {}", syntax))
+                .constraint("reproducible with --seed or --seed-manifest".to_string())
+                .see_also(kinds.iter().copied())
+        })
+        .collect()
+}
+
+fn leaks() -> Vec<Entry> {
+    Leak::ALL
+        .into_iter()
+        .map(|leak| {
+            let summary = match leak {
+                Leak::Frame => "The decrypted cells of the board.",
+                Leak::Population => "The decrypted number of live cells.",
+                Leak::NoisyAggregate => {
+                    "Counts with noise added before they are decrypted, by --noisy-population."
+                }
+                Leak::StillFlag => "Whether the board changed, decrypted by --detect-still.",
+                Leak::Changes => "Which cells changed, decrypted by --full-checkpoint-every.",
+                Leak::SampledCells => {
+                    "Cells sampled for the population estimate of --estimate-population."
+                }
+                Leak::Trace => "Intermediate values of the cell updates, for --trace.",
+                Leak::Distances => "Distances to the nearest live cell, for --distance.",
+                Leak::Lifespans => "Generations of the first deaths of the cells, for --lifespan.",
+                Leak::RuleEvidence => "Transition counts decrypted by --infer-rule.",
+            };
+            let topic = leak.to_string().replace(' ', "-");
+            Entry::new(&topic, Kind::Leak, summary)
+                .constraint("counted by --audit, and against --max-leak-bits".to_string())
+        })
+        .collect()
+}
+
+fn stop_conditions() -> Vec<Entry> {
+    STOP_CONDITIONS
+        .into_iter()
+        .map(|option| {
+            let detect_still = format!(
+                "The run ends once the board no longer changes or repeats every 2 generations, \
+                 decrypting {} flags every generation.",
+                STILL_FLAGS_PER_FRAME
+            );
+            let (summary, constraint) = match option {
+                "generations" => (
+                    "The run ends after --generations <n> updates, 5 by default.",
+                    None,
+                ),
+                "time-limit" => (
+                    "The run ends once it has lasted --time-limit <seconds>, even in a batch.",
+                    None,
+                ),
+                "detect-still" => (
+                    detect_still.as_str(),
+                    Some("cannot be combined with --batch"),
+                ),
+                "on-full" => (
+                    "--on-full stop ends the run once the live cells fill more than the \
+                     --full-threshold fraction of the board.",
+                    Some("cannot be combined with --noisy-population"),
+                ),
+                _ => ("", None),
+            };
+            let mut entry =
+                Entry::new(option, Kind::StopCondition, summary).see_also(STOP_CONDITIONS);
+            if let Some(constraint) = constraint {
+                entry = entry.constraint(constraint.to_string());
+            }
+            if option == "on-full" {
+                entry = entry.constraint(format!("one of {}", OnFull::NAMES.join(", ")));
+            }
+            entry
+        })
+        .collect()
+}
+
+fn features() -> Vec<Entry> {
+    FEATURES
+        .into_iter()
+        .map(|(feature, enabled)| {
+            let summary = match feature {
+                "capi" => "A C interface to the encrypted boards, see `capi`.",
+                "fixtures" => "The fixtures binary, which writes the files of `tests/fixtures`.",
+                "latex-export" => "--latex <path> writes the final board as a TikZ picture.",
+                "phase-timer" => "--phase-timer <n> times the phases of the cell updates.",
+                _ => "",
+            };
+            Entry::new(feature, Kind::Feature, summary).constraint(if enabled {
+                "enabled in this build".to_string()
+            } else {
+                format!(
+                    "not enabled in this build, build with --features {}",
+                    feature
+                )
+            })
+        })
+        .collect()
+}
+
+/// Every entry, by kind.
+pub fn catalog() -> Vec<Entry> {
+    [
+        parameters(),
+        rules(),
+        boundaries(),
+        profiles(),
+        leaks(),
+        stop_conditions(),
+        features(),
+    ]
+    .concat()
+}
+
+/// The entry of `topic`, written as in the catalog, as an option, or with
+/// spaces or underscores.
+pub fn lookup(topic: &str) -> Result<Entry, String> {
+    let topic = topic
+        .trim_start_matches("--")
+        .to_lowercase()
+        .replace([' ', '_'], "-");
+    // the other names of the boundary conditions
+    let topic = match topic.parse::<BoundaryCondition>() {
+        Ok(boundary) => boundary.to_string(),
+        Err(_) => topic,
+    };
+    let catalog = catalog();
+    match catalog.iter().find(|entry| entry.topic == topic) {
+        Some(entry) => Ok(entry.clone()),
+        None => Err(crate::completion::with_suggestion(
+            format!("nothing to explain about '{}'", topic),
+            &topic,
+            catalog.iter().map(|entry| entry.topic.as_str()),
+        )),
+    }
+}
+
+/// The entries of `topic`, every entry for `all`, as text or as JSON.
+pub fn render(topic: &str, json: bool) -> Result<String, String> {
+    let entries = match topic {
+        "all" => catalog(),
+        _ => vec![lookup(topic)?],
+    };
+    Ok(if json {
+        serde_json::to_string(&entries).expect("entries are always valid JSON")
+    } else {
+        entries
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topics(kind: Kind) -> Vec<String> {
+        catalog()
+            .into_iter()
+            .filter(|entry| entry.kind == kind)
+            .map(|entry| entry.topic)
+            .collect()
+    }
+
+    #[test]
+    fn test_every_item_is_explained() {
+        let catalog = catalog();
+        for entry in &catalog {
+            assert!(
+                !entry.summary.is_empty(),
+                "{} is not explained",
+                entry.topic
+            );
+            for topic in &entry.see_also {
+                assert!(lookup(topic).is_ok(), "{} refers to {}", entry.topic, topic);
+            }
+        }
+        let mut all: Vec<&str> = catalog.iter().map(|entry| entry.topic.as_str()).collect();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), catalog.len(), "a topic is explained twice");
+
+        assert_eq!(topics(Kind::Parameters), keys::PARAMETER_NAMES);
+        assert_eq!(topics(Kind::Boundary).len(), BoundaryCondition::ALL.len());
+        assert_eq!(topics(Kind::Profile).len(), BoardProfile::SYNTAX.len());
+        assert_eq!(topics(Kind::Leak).len(), Leak::ALL.len());
+        for option in STOP_CONDITIONS {
+            assert!(crate::OPTIONS.contains(&format!("--{}", option).as_str()));
+        }
+    }
+
+    #[test]
+    fn test_every_feature_is_explained() {
+        let manifest =
+            std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")).unwrap();
+        let features: Vec<&str> = manifest
+            .split("[features]")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .take_while(|line| !line.starts_with('['))
+            .filter_map(|line| Some(line.split_once('=')?.0.trim()))
+            .collect();
+        assert_eq!(topics(Kind::Feature), features);
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("mirror").unwrap().kind, Kind::Boundary);
+        assert_eq!(lookup("periodic").unwrap().topic, "toroidal");
+        assert_eq!(lookup("--detect-still").unwrap().kind, Kind::StopCondition);
+        assert_eq!(lookup("Sampled cells").unwrap().kind, Kind::Leak);
+        assert_eq!(lookup("phase_timer").unwrap().kind, Kind::Feature);
+        assert_eq!(
+            lookup("mirorr").err().unwrap(),
+            "nothing to explain about 'mirorr' (did you mean mirror?)"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("fixed", false).unwrap(),
+            "fixed (boundary condition)\n    \
+             The cells outside the board are always dead. Also named dead.\n    \
+             - cannot be combined with --shadow, --hash\n    \
+             - needed by --quadrants\n    \
+             See also: toroidal, mirror\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render("conway", true).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "topic": "conway",
+                "kind": "rule",
+                "summary": "Conway's game of life, B3/S23, the default rule.",
+                "constraints": [],
+                "cost": null,
+                "see_also": ["rule"],
+            }])
+        );
+        let text = render("all", false).unwrap();
+        let titles = text
+            .lines()
+            .filter(|line| !line.starts_with(' ') && !line.is_empty());
+        assert_eq!(titles.count(), catalog().len());
+    }
+}
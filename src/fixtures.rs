@@ -0,0 +1,356 @@
+//! Small files of every kind the crate writes, one per released version of
+//! the format (see `keys`), committed in `tests/fixtures` to keep the files
+//! of previous runs readable, or refused with a clear error.
+//!
+//! The fixtures of the current version are written by the `fixtures`
+//! binary, built with the `fixtures` feature, and only need to be written
+//! again when the version changes: they must load, and be written back byte
+//! for byte, so that a change to what a file holds without a new version
+//! fails the tests. The fixtures of the previous versions stay, and must be
+//! refused with `FileError::Version`.
+
+use crate::checkpoint::{self, CheckpointChain, Delta, Index};
+use crate::keys::{self, FileError, FORMAT_VERSION};
+use crate::patch::{Patch, PatchError};
+use crate::storage::Memory;
+use crate::{Board, BoundaryCondition};
+use concrete::prelude::*;
+use concrete::{ClientKey, FheBool};
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Where the fixtures are, from the root of the crate.
+pub const DIRECTORY: &str = "tests/fixtures";
+
+/// Parameters of the keys of the fixtures.
+const PARAMETERS: &str = "default";
+
+/// A kind of file, with a fixture for every version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A board written by `Board::save`.
+    Board,
+    /// The index of a chain of checkpoints, see `checkpoint`.
+    Index,
+    /// A delta of a chain of checkpoints.
+    Delta,
+    /// A patch, see `patch`.
+    Patch,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 4] = [Kind::Board, Kind::Index, Kind::Delta, Kind::Patch];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Board => "board",
+            Self::Index => "index",
+            Self::Delta => "delta",
+            Self::Patch => "patch",
+        }
+    }
+
+    /// Name of the fixture of version `version`.
+    pub fn file_name(self, version: u32) -> String {
+        format!("{}-v{}.bin", self.name(), version)
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The kind and version of the fixture named `file_name`.
+fn parse_file_name(file_name: &str) -> Option<(Kind, u32)> {
+    let (name, version) = file_name.strip_suffix(".bin")?.split_once("-v")?;
+    let kind = Kind::ALL.into_iter().find(|kind| kind.name() == name)?;
+    Some((kind, version.parse().ok()?))
+}
+
+#[derive(Debug)]
+pub enum FixtureError {
+    /// The name of the file is not that of a fixture.
+    Name(PathBuf),
+    /// A fixture of the current version could not be read, or written back.
+    Load { path: PathBuf, err: FileError },
+    /// A fixture of the current version is written back differently.
+    Changed { path: PathBuf },
+    /// A fixture of a previous version was not refused for its version.
+    NotRefused {
+        path: PathBuf,
+        err: Option<FileError>,
+    },
+    /// A fixture of a version after the current one.
+    Future { path: PathBuf },
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Name(path) => write!(f, "{}: not the name of a fixture", path.display()),
+            Self::Load { path, err } => write!(f, "{}: {}", path.display(), err),
+            Self::Changed { path } => write!(
+                f,
+                "{}: written back differently, the format changed without a new \
+                 FORMAT_VERSION in keys",
+                path.display()
+            ),
+            Self::NotRefused { path, err: None } => write!(
+                f,
+                "{}: a file of a previous version is read as one of version {}",
+                path.display(),
+                FORMAT_VERSION
+            ),
+            Self::NotRefused {
+                path,
+                err: Some(err),
+            } => write!(
+                f,
+                "{}: a file of a previous version is refused with '{}' instead of its version",
+                path.display(),
+                err
+            ),
+            Self::Future { path } => write!(
+                f,
+                "{}: a fixture of a version after {}",
+                path.display(),
+                FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for FixtureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Load { err, .. } | Self::NotRefused { err: Some(err), .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the file of `kind` at `path` and writes it back to `copy`.
+fn read_and_write(kind: Kind, path: &Path, copy: &Path) -> Result<(), FileError> {
+    match kind {
+        Kind::Board => Board::load(path, PARAMETERS)?.save(copy, PARAMETERS),
+        Kind::Index => {
+            let index: Index = keys::read_file(path, PARAMETERS)?;
+            keys::write_file(copy, PARAMETERS, &index, false)
+        }
+        Kind::Delta => {
+            let delta: Delta = keys::read_file(path, PARAMETERS)?;
+            keys::write_file(copy, PARAMETERS, &delta, false)
+        }
+        Kind::Patch => match Patch::load(path, PARAMETERS) {
+            Ok(patch) => patch.save(copy, PARAMETERS),
+            Err(PatchError::File(err)) => Err(err),
+            Err(err) => Err(FileError::Format(Box::new(bincode::ErrorKind::Custom(
+                err.to_string(),
+            )))),
+        },
+    }
+}
+
+/// Checks the fixture at `path`, see the module.
+pub fn check(path: &Path) -> Result<(), FixtureError> {
+    let name = path.file_name().and_then(|name| name.to_str());
+    let Some((kind, version)) = name.and_then(parse_file_name) else {
+        return Err(FixtureError::Name(path.to_path_buf()));
+    };
+    let path = path.to_path_buf();
+    if version > FORMAT_VERSION {
+        return Err(FixtureError::Future { path });
+    }
+    let copy = std::env::temp_dir().join(format!(
+        "fixture-{}-{}",
+        std::process::id(),
+        kind.file_name(version)
+    ));
+    let written = read_and_write(kind, &path, &copy).and_then(|()| Ok(std::fs::read(&copy)?));
+    let _ = std::fs::remove_file(&copy);
+
+    if version < FORMAT_VERSION {
+        return match written {
+            Err(FileError::Version(_)) => Ok(()),
+            Err(err) => Err(FixtureError::NotRefused {
+                path,
+                err: Some(err),
+            }),
+            Ok(_) => Err(FixtureError::NotRefused { path, err: None }),
+        };
+    }
+    let written = match written {
+        Ok(written) => written,
+        Err(err) => return Err(FixtureError::Load { path, err }),
+    };
+    match std::fs::read(&path) {
+        Ok(fixture) if fixture == written => Ok(()),
+        Ok(_) => Err(FixtureError::Changed { path }),
+        Err(err) => Err(FixtureError::Load {
+            path,
+            err: err.into(),
+        }),
+    }
+}
+
+/// Checks every fixture in `directory`, and that there is one of every kind
+/// for the current version. Returns the number of fixtures checked.
+pub fn check_all(directory: &Path) -> Result<usize, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut checked = 0;
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect(),
+        Err(err) => return Err(vec![format!("{}: {}", directory.display(), err)]),
+    };
+    entries.sort();
+    for path in entries {
+        match check(&path) {
+            Ok(()) => checked += 1,
+            Err(err) => errors.push(err.to_string()),
+        }
+    }
+    for kind in Kind::ALL {
+        let path = directory.join(kind.file_name(FORMAT_VERSION));
+        if !path.exists() {
+            errors.push(format!(
+                "{}: missing, write it with \
+                 `cargo run --release --features fixtures --bin fixtures`",
+                path.display()
+            ));
+        }
+    }
+    if errors.is_empty() {
+        Ok(checked)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Writes the fixtures of the current version to `directory`, with keys of
+/// the default parameters whose server key is set. Returns their paths.
+pub fn generate(directory: &Path, client_key: &ClientKey) -> Result<Vec<PathBuf>, FileError> {
+    std::fs::create_dir_all(directory)?;
+    let path = |kind: Kind| directory.join(kind.file_name(FORMAT_VERSION));
+
+    // a few cells, and a rule and boundary conditions other than the default
+    let frame: Vec<bool> = "..#.#.#..##.....".chars().map(|cell| cell == '#').collect();
+    let encrypt = |alive| FheBool::encrypt(alive, client_key);
+    let zeros = (encrypt(false), encrypt(false), encrypt(false));
+    let mut board = Board::new(
+        4,
+        frame.iter().map(|&alive| encrypt(alive)).collect(),
+        zeros,
+    )
+    .with_rule("B36/S23".parse().unwrap())
+    .with_boundary(BoundaryCondition::Mirror);
+    board.save(&path(Kind::Board), PARAMETERS)?;
+
+    // a full checkpoint and a delta
+    let mut chain = CheckpointChain::create(Box::new(Memory::default()), PARAMETERS, 2)
+        .expect("an empty store holds no chain");
+    chain.record(&board, client_key)?;
+    board.update();
+    chain.record(&board, client_key)?;
+    let keys = [
+        (Kind::Index, checkpoint::INDEX.to_string()),
+        (
+            Kind::Delta,
+            checkpoint::checkpoint_key(checkpoint::DELTA, 1),
+        ),
+    ];
+    for (kind, key) in keys {
+        let mut bytes = Vec::new();
+        chain.storage().get(&key)?.read_to_end(&mut bytes)?;
+        std::fs::write(path(kind), bytes)?;
+    }
+
+    let mut edited = board.decrypt(client_key);
+    edited[0] = !edited[0];
+    let patch = Patch::new(&board.decrypt(client_key), &edited, (4, 4), 1, client_key);
+    patch.save(&path(Kind::Patch), PARAMETERS)?;
+
+    Ok(Kind::ALL.into_iter().map(path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::set_up_keys;
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(Kind::Delta.file_name(2), "delta-v2.bin");
+        assert_eq!(parse_file_name("delta-v2.bin"), Some((Kind::Delta, 2)));
+        assert_eq!(parse_file_name("board-v10.bin"), Some((Kind::Board, 10)));
+        for name in ["board-v2", "keys-v2.bin", "board-2.bin", "board-vx.bin"] {
+            assert_eq!(parse_file_name(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_committed_fixtures() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join(DIRECTORY);
+        if let Err(errors) = check_all(&directory) {
+            panic!("\n{}", errors.join("\n"));
+        }
+    }
+
+    #[test]
+    fn test_changes_are_caught() {
+        let client_key = set_up_keys();
+        let directory = std::env::temp_dir().join(format!("fixtures-{}", std::process::id()));
+        let paths = generate(&directory, &client_key).unwrap();
+        assert_eq!(check_all(&directory), Ok(4));
+
+        // a board written with one more field
+        let board = &paths[0];
+        let mut bytes = std::fs::read(board).unwrap();
+        bytes.push(0);
+        std::fs::write(board, bytes).unwrap();
+        assert!(matches!(check(board), Err(FixtureError::Changed { .. })));
+
+        // files of the current version named as files of a previous one,
+        // and of a version to come
+        let previous = directory.join(Kind::Index.file_name(FORMAT_VERSION - 1));
+        std::fs::rename(&paths[1], &previous).unwrap();
+        let future = directory.join(Kind::Delta.file_name(FORMAT_VERSION + 1));
+        std::fs::rename(&paths[2], &future).unwrap();
+        let errors = check_all(&directory).unwrap_err();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            errors,
+            [
+                FixtureError::Changed {
+                    path: board.clone()
+                }
+                .to_string(),
+                format!(
+                    "{}: a fixture of a version after {}",
+                    future.display(),
+                    FORMAT_VERSION
+                ),
+                format!(
+                    "{}: a file of a previous version is read as one of version {}",
+                    previous.display(),
+                    FORMAT_VERSION
+                ),
+                format!(
+                    "{}: missing, write it with \
+                     `cargo run --release --features fixtures --bin fixtures`",
+                    paths[1].display()
+                ),
+                format!(
+                    "{}: missing, write it with \
+                     `cargo run --release --features fixtures --bin fixtures`",
+                    paths[2].display()
+                ),
+            ]
+        );
+    }
+}
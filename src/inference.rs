@@ -0,0 +1,144 @@
+//! Inference of a totalistic rule from one transition of a board, on the
+//! client side of `Board::rule_evidence`.
+
+use crate::reference::neighbour_counts_with_boundary;
+use crate::BoundaryCondition;
+use std::fmt;
+
+/// Number of transitions seen for every state of a cell and number of live
+/// neighbours, indexed by `[alive as usize][count]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleEvidence {
+    pub to_alive: [[usize; 9]; 2],
+    pub to_dead: [[usize; 9]; 2],
+}
+
+/// Birth and survival counts of a rule, in the usual `B3/S23` notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferredRule {
+    pub birth: Vec<usize>,
+    pub survival: Vec<usize>,
+    /// Buckets, as `(alive, count)`, that were never seen or were seen going
+    /// both ways as often.
+    pub undecided: Vec<(bool, usize)>,
+}
+
+impl RuleEvidence {
+    /// Counts the transitions from `before` to `after` in the clear.
+    pub fn from_frames(
+        before: &[bool],
+        after: &[bool],
+        dimensions: (usize, usize),
+        boundary: BoundaryCondition,
+    ) -> Self {
+        let mut evidence = Self::default();
+        let counts = neighbour_counts_with_boundary(before, dimensions, boundary);
+        for ((&alive, &next), count) in before.iter().zip(after).zip(counts) {
+            let outcomes = if next {
+                &mut evidence.to_alive
+            } else {
+                &mut evidence.to_dead
+            };
+            outcomes[usize::from(alive)][usize::from(count)] += 1;
+        }
+        evidence
+    }
+
+    /// Picks the majority outcome of every bucket.
+    pub fn infer(&self) -> InferredRule {
+        let mut rule = InferredRule {
+            birth: Vec::new(),
+            survival: Vec::new(),
+            undecided: Vec::new(),
+        };
+        for alive in [false, true] {
+            for count in 0..9 {
+                let to_alive = self.to_alive[usize::from(alive)][count];
+                let to_dead = self.to_dead[usize::from(alive)][count];
+                if to_alive == to_dead {
+                    rule.undecided.push((alive, count));
+                } else if to_alive > to_dead {
+                    if alive {
+                        rule.survival.push(count);
+                    } else {
+                        rule.birth.push(count);
+                    }
+                }
+            }
+        }
+        rule
+    }
+}
+
+impl fmt::Display for InferredRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |counts: &[usize]| counts.iter().map(usize::to_string).collect::<String>();
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::reference::neighbour_counts;
+
+    /// HighLife, B36/S23.
+    fn highlife(cells: &[bool], dimensions: (usize, usize)) -> Vec<bool> {
+        neighbour_counts(cells, dimensions)
+            .into_iter()
+            .zip(cells)
+            .map(|(count, &alive)| count == 3 || count == 6 && !alive || count == 2 && alive)
+            .collect()
+    }
+
+    #[test]
+    fn test_infer_highlife() {
+        let dimensions = (32, 32);
+        let before = generate(&BoardProfile::Soup { density: 0.5 }, dimensions, 11);
+        let evidence = RuleEvidence::from_frames(
+            &before,
+            &highlife(&before, dimensions),
+            dimensions,
+            BoundaryCondition::Toroidal,
+        );
+
+        let rule = evidence.infer();
+        // a random soup rarely has cells with no or all live neighbours
+        for (alive, count) in &rule.undecided {
+            assert!(
+                [0, 8].contains(count),
+                "({}, {}) is undecided",
+                alive,
+                count
+            );
+        }
+        assert_eq!(rule.birth, vec![3, 6]);
+        assert_eq!(rule.survival, vec![2, 3]);
+        assert_eq!(rule.to_string(), "B36/S23");
+
+        let seen: usize = evidence
+            .to_alive
+            .iter()
+            .chain(&evidence.to_dead)
+            .flatten()
+            .sum();
+        assert_eq!(seen, 32 * 32);
+    }
+
+    #[test]
+    fn test_undecided_buckets() {
+        let evidence = RuleEvidence::from_frames(
+            &[false; 9],
+            &[false; 9],
+            (3, 3),
+            BoundaryCondition::Toroidal,
+        );
+        let rule = evidence.infer();
+
+        assert_eq!(evidence.to_dead[0][0], 9);
+        assert_eq!(rule.to_string(), "B/S");
+        assert_eq!(rule.undecided.len(), 17);
+        assert!(!rule.undecided.contains(&(false, 0)));
+    }
+}
@@ -0,0 +1,392 @@
+//! Keys saved to a file between runs, for `--keys`, to skip the key
+//! generation, server keys saved alone for `server`, and the files of
+//! encrypted boards of `Board::save`.
+//!
+//! Every file starts with `MAGIC`, the version of its format and the name of
+//! the parameters of the keys, so that files of other versions, keys and
+//! ciphertexts of other parameters are caught instead of silently giving
+//! wrong results.
+//!
+//! Files of version 2 started with the version, without `MAGIC`, and those
+//! of version 1 with the parameters: both are still recognised, to be
+//! refused for their version.
+
+use bincode::Options;
+use concrete::{ClientKey, FheBoolParameters, ServerKey};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+/// Longest name of parameters read from a key file, in bytes with its length.
+const MAX_PARAMETERS_LEN: u64 = 256;
+
+/// First bytes of every file, before the version.
+const MAGIC: [u8; 4] = *b"GoL\xfe";
+
+/// Version of the format of the files, to change along with what they hold.
+pub const FORMAT_VERSION: u32 = 3;
+
+/// Names of the parameter sets of the boolean keys, as recorded in the files.
+pub const PARAMETER_NAMES: [&str; 2] = ["default", "tfhe-lib"];
+
+/// The parameter set named `name`, one of `PARAMETER_NAMES`.
+pub fn parameters(name: &str) -> Result<FheBoolParameters, String> {
+    match name {
+        "default" => Ok(FheBoolParameters::default()),
+        "tfhe-lib" => Ok(FheBoolParameters::tfhe_lib()),
+        _ => Err(format!(
+            "unknown parameters '{}', expected one of {}",
+            name,
+            PARAMETER_NAMES.join(", ")
+        )),
+    }
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(std::io::Error),
+    Format(bincode::Error),
+    /// The file was written in another version of the format.
+    Version(u32),
+    /// The file was not written by this crate.
+    Unrecognised,
+    /// The keys were generated with other parameters.
+    Parameters {
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access the file: {}", err),
+            Self::Format(err) => write!(f, "invalid file: {}", err),
+            Self::Version(found) => write!(
+                f,
+                "the file is in version {} of the format, not {}",
+                found, FORMAT_VERSION
+            ),
+            Self::Unrecognised => f.write_str("not a file of the game of life"),
+            Self::Parameters { expected, found } => write!(
+                f,
+                "the file was written for the {} parameters, not the {} ones",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Format(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for FileError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<bincode::Error> for FileError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Format(err)
+    }
+}
+
+/// Writes `value` after `MAGIC`, the version and the name of the
+/// parameters, in a file only readable by its owner when `secret` is set.
+///
+/// The file is written next to `path` and then renamed, so that a run
+/// interrupted while writing leaves the previous file whole.
+pub(crate) fn write_file<T: Serialize>(
+    path: &Path,
+    parameters: &str,
+    value: &T,
+    secret: bool,
+) -> Result<(), FileError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if secret {
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = Path::new(&partial);
+    let mut file = BufWriter::new(options.open(partial)?);
+    write_to(&mut file, parameters, value)?;
+    file.into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+    std::fs::rename(partial, path)?;
+    Ok(())
+}
+
+/// Writes what `write_file` writes in a file, to `writer`.
+pub(crate) fn write_to<T: Serialize>(
+    mut writer: impl Write,
+    parameters: &str,
+    value: &T,
+) -> Result<(), FileError> {
+    writer.write_all(&MAGIC)?;
+    bincode::serialize_into(&mut writer, &FORMAT_VERSION)?;
+    bincode::serialize_into(&mut writer, parameters)?;
+    bincode::serialize_into(&mut writer, value)?;
+    Ok(())
+}
+
+/// Reads a value written by `write_file` with the same parameters.
+pub(crate) fn read_file<T: DeserializeOwned>(
+    path: &Path,
+    parameters: &str,
+) -> Result<T, FileError> {
+    read_from(BufReader::new(File::open(path)?), parameters)
+}
+
+/// Reads a value written by `write_to` with the same parameters.
+pub(crate) fn read_from<T: DeserializeOwned>(
+    mut file: impl Read,
+    parameters: &str,
+) -> Result<T, FileError> {
+    let mut magic = [0; 4];
+    file.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(FileError::Version(version_before_magic(magic, file)?));
+    }
+    let version: u32 = bincode::deserialize_from(&mut file)?;
+    if version != FORMAT_VERSION {
+        return Err(FileError::Version(version));
+    }
+    let found = read_parameters(&mut file)?;
+    if found != parameters {
+        return Err(FileError::Parameters {
+            expected: parameters.to_string(),
+            found,
+        });
+    }
+    Ok(bincode::deserialize_from(file)?)
+}
+
+/// The name of the parameters, with the same encoding as
+/// `bincode::serialize_into`.
+fn read_parameters(file: impl Read) -> Result<String, FileError> {
+    // a file that is not one of ours must not make us allocate whatever
+    // length it starts with
+    Ok(bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_PARAMETERS_LEN)
+        .deserialize_from(file)?)
+}
+
+/// The version of a file without `MAGIC`, starting with `first_bytes`.
+fn version_before_magic(first_bytes: [u8; 4], file: impl Read) -> Result<u32, FileError> {
+    if u32::from_le_bytes(first_bytes) == 2 {
+        return Ok(2);
+    }
+    // version 1 started with the length of the parameters, on 8 bytes
+    let start = (&first_bytes[..]).chain(file);
+    match read_parameters(start) {
+        Ok(parameters) if PARAMETER_NAMES.contains(&parameters.as_str()) => Ok(1),
+        _ => Err(FileError::Unrecognised),
+    }
+}
+
+/// Writes the keys generated with the named parameters. The file is only
+/// readable by its owner, the client key being secret.
+pub fn save(
+    path: &Path,
+    parameters: &str,
+    client_key: &ClientKey,
+    server_key: &ServerKey,
+) -> Result<(), FileError> {
+    write_file(path, parameters, &(client_key, server_key), true)
+}
+
+/// Reads keys generated with the named parameters.
+pub fn load(path: &Path, parameters: &str) -> Result<(ClientKey, ServerKey), FileError> {
+    read_file(path, parameters)
+}
+
+/// Writes the server key alone, for a server to update boards without being
+/// able to decrypt them (see `server`).
+pub fn save_server_key(
+    path: &Path,
+    parameters: &str,
+    server_key: &ServerKey,
+) -> Result<(), FileError> {
+    write_file(path, parameters, server_key, false)
+}
+
+/// Reads a server key written by `save_server_key`.
+pub fn load_server_key(path: &Path, parameters: &str) -> Result<ServerKey, FileError> {
+    read_file(path, parameters)
+}
+
+/// Where the keys of `load_or_generate` come from.
+#[derive(Debug)]
+pub enum Origin {
+    Loaded,
+    /// There was no key file, or regenerating was asked for.
+    Generated,
+    /// The key file could not be used and was replaced.
+    Replaced(FileError),
+}
+
+/// Reads the keys from the file, or generates and writes them when the file
+/// is missing, holds keys of other parameters, cannot be parsed, or when
+/// `regenerate` is set.
+///
+/// Fails if the file is of another version of the format or not a file of
+/// ours, which `regenerate` is needed to overwrite, if it cannot be read for
+/// another reason, or written.
+pub fn load_or_generate(
+    path: &Path,
+    parameters: &str,
+    regenerate: bool,
+    generate: impl FnOnce() -> (ClientKey, ServerKey),
+) -> Result<(ClientKey, ServerKey, Origin), FileError> {
+    let origin = if regenerate {
+        Origin::Generated
+    } else {
+        match load(path, parameters) {
+            Ok((client_key, server_key)) => return Ok((client_key, server_key, Origin::Loaded)),
+            Err(FileError::Io(err)) if err.kind() == ErrorKind::NotFound => Origin::Generated,
+            Err(err @ (FileError::Io(_) | FileError::Version(_) | FileError::Unrecognised)) => {
+                return Err(err)
+            }
+            Err(err) => Origin::Replaced(err),
+        }
+    };
+    let (client_key, server_key) = generate();
+    save(path, parameters, &client_key, &server_key)?;
+    Ok((client_key, server_key, origin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::default_keys;
+    use concrete::prelude::*;
+    use concrete::{set_server_key, FheBool};
+
+    #[test]
+    fn test_round_trip() {
+        let (client_key, server_key) = default_keys();
+        let path = std::env::temp_dir().join(format!("keys-{}.bin", std::process::id()));
+
+        save(&path, "default", &client_key, &server_key).unwrap();
+        let encrypted = FheBool::encrypt(true, &client_key);
+        let (client_key, server_key) = load(&path, "default").unwrap();
+        set_server_key(server_key);
+        let other = FheBool::encrypt(true, &client_key);
+        assert!((encrypted & other).decrypt(&client_key));
+
+        let err = load(&path, "tfhe-lib").err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            err.to_string(),
+            "the file was written for the default parameters, not the tfhe-lib ones"
+        );
+        assert!(matches!(load(&path, "default"), Err(FileError::Io(_))));
+    }
+
+    #[test]
+    fn test_parameters() {
+        for name in PARAMETER_NAMES {
+            assert!(parameters(name).is_ok(), "{}", name);
+        }
+        assert_eq!(
+            parameters("msg4_carry1").err().unwrap(),
+            "unknown parameters 'msg4_carry1', expected one of default, tfhe-lib"
+        );
+    }
+
+    #[test]
+    fn test_versions() {
+        let path = std::env::temp_dir().join(format!("version-{}.bin", std::process::id()));
+        write_file(&path, "default", &42u8, false).unwrap();
+        assert_eq!(read_file::<u8>(&path, "default").unwrap(), 42);
+        let mut partial = path.clone().into_os_string();
+        partial.push(".partial");
+        assert!(!Path::new(&partial).exists());
+
+        // files of the first version started with the parameters, and those
+        // of the second one with the version
+        let mut first = Vec::new();
+        bincode::serialize_into(&mut first, "tfhe-lib").unwrap();
+        bincode::serialize_into(&mut first, &42u8).unwrap();
+        let mut second = bincode::serialize(&2u32).unwrap();
+        second.extend(&first);
+        let read = |bytes: &[u8]| {
+            std::fs::write(&path, bytes).unwrap();
+            read_file::<u8>(&path, "tfhe-lib").unwrap_err()
+        };
+        let err = read(&first);
+        assert!(matches!(err, FileError::Version(1)));
+        assert_eq!(
+            err.to_string(),
+            "the file is in version 1 of the format, not 3"
+        );
+        assert!(matches!(read(&second), FileError::Version(2)));
+        assert!(matches!(read(b"{\"json\": true}"), FileError::Unrecognised));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_or_generate() {
+        let path = std::env::temp_dir().join(format!("regen-keys-{}.bin", std::process::id()));
+        let origin = |regenerate| {
+            load_or_generate(&path, "default", regenerate, default_keys)
+                .unwrap()
+                .2
+        };
+
+        assert!(matches!(origin(false), Origin::Generated));
+        assert!(matches!(origin(false), Origin::Loaded));
+        assert!(matches!(origin(true), Origin::Generated));
+
+        let mut not_keys = MAGIC.to_vec();
+        not_keys.extend(bincode::serialize(&FORMAT_VERSION).unwrap());
+        not_keys.extend(b"not keys");
+        std::fs::write(&path, not_keys).unwrap();
+        assert!(matches!(
+            origin(false),
+            Origin::Replaced(FileError::Format(_))
+        ));
+        assert!(matches!(origin(false), Origin::Loaded));
+
+        let (client_key, server_key) = default_keys();
+        save(&path, "tfhe-lib", &client_key, &server_key).unwrap();
+        assert!(matches!(
+            origin(false),
+            Origin::Replaced(FileError::Parameters { .. })
+        ));
+        assert!(matches!(origin(false), Origin::Loaded));
+
+        // files of other versions and other programs are kept
+        for bytes in [&bincode::serialize(&2u32).unwrap()[..], b"{\"json\": true}"] {
+            std::fs::write(&path, bytes).unwrap();
+            let result = load_or_generate(&path, "default", false, || unreachable!());
+            assert!(matches!(
+                result.err().unwrap(),
+                FileError::Version(2) | FileError::Unrecognised
+            ));
+            assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        }
+        assert!(matches!(origin(true), Origin::Generated));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
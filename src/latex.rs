@@ -0,0 +1,61 @@
+//! Export of decrypted boards as TikZ pictures.
+
+use std::fmt::Write;
+
+/// Renders the cells as a `tikzpicture`: a grid of empty squares with the
+/// live cells filled, row 0 at the top.
+pub fn tikz_picture(cells: &[bool], dimensions: (usize, usize), cell_size_mm: f64) -> String {
+    let (n_rows, n_cols) = dimensions;
+    let mut picture = String::new();
+
+    writeln!(
+        picture,
+        "\\begin{{tikzpicture}}[x={}mm, y=-{}mm]",
+        cell_size_mm, cell_size_mm
+    )
+    .unwrap();
+    writeln!(
+        picture,
+        "  \\draw[very thin] (0,0) grid ({},{});",
+        n_cols, n_rows
+    )
+    .unwrap();
+    for (k, _) in cells.iter().enumerate().filter(|(_, alive)| **alive) {
+        writeln!(
+            picture,
+            "  \\fill ({},{}) rectangle +(1,1);",
+            k % n_cols,
+            k / n_cols
+        )
+        .unwrap();
+    }
+    writeln!(picture, "  \\draw (0,0) rectangle ({},{});", n_cols, n_rows).unwrap();
+    picture.push_str("\\end{tikzpicture}\n");
+
+    picture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tikz_picture() {
+        #[rustfmt::skip]
+        let cells = [
+            false, true, false,
+            false, false, true,
+            true, true, true,
+            false, false, false,
+        ];
+
+        let picture = tikz_picture(&cells, (4, 3), 2.5);
+
+        assert!(picture.starts_with("\\begin{tikzpicture}[x=2.5mm, y=-2.5mm]\n"));
+        assert!(picture.ends_with("\\end{tikzpicture}\n"));
+        assert_eq!(picture.matches("\\fill").count(), 5);
+        assert!(picture.contains("  \\fill (1,0) rectangle +(1,1);\n"));
+        assert!(picture.contains("  \\fill (0,2) rectangle +(1,1);\n"));
+        assert!(picture.contains("  \\draw (0,0) rectangle (3,4);\n"));
+    }
+}
@@ -0,0 +1,2143 @@
+//! Conway's game of life on a board of encrypted cells, with the boolean API
+//! of concrete: the server updates the board without learning anything about
+//! it, and only the holder of the client key can decrypt it.
+//!
+//! `Board` holds the encrypted cells and runs the game with `is_alive`, and
+//! the modules hold the plaintext tools used on the client to build, check
+//! and analyse the boards.
+
+use concrete::prelude::*;
+use concrete::{set_server_key, ClientKey, FheBool, ServerKey};
+use phase_timer::Phase;
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub mod analysis;
+pub mod benchgen;
+pub mod bits;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod checkpoint;
+pub mod estimate;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+pub mod inference;
+pub mod keys;
+#[cfg(feature = "latex-export")]
+pub mod latex;
+pub mod library;
+pub mod lifespan;
+pub mod noise;
+pub mod patch;
+pub mod pattern;
+pub mod patternfile;
+pub mod phase_timer;
+pub mod plain;
+pub mod quadrant;
+pub mod reference;
+pub mod resample;
+pub mod rule;
+pub mod seeds;
+pub mod server;
+pub mod shadow;
+pub mod storage;
+pub mod stream;
+#[cfg(test)]
+mod testing;
+pub mod trace;
+
+#[derive(Clone)]
+pub struct Accumulator(FheBool, FheBool, FheBool);
+
+impl From<(FheBool, FheBool, FheBool)> for Accumulator {
+    fn from(inner: (FheBool, FheBool, FheBool)) -> Self {
+        Self(inner.0, inner.1, inner.2)
+    }
+}
+
+impl AddAssign<&FheBool> for Accumulator {
+    // ^ -> xor
+    // & -> and
+    fn add_assign(&mut self, rhs: &FheBool) {
+        let c1 = &self.0 ^ rhs;
+        let first_carry = rhs & &self.0;
+
+        let second_carry = &first_carry & &self.1;
+        let c2 = &self.1 ^ first_carry;
+
+        self.2 = &self.2 ^ second_carry;
+        self.1 = c2;
+        self.0 = c1;
+    }
+}
+
+pub fn count_neighbours(neighbours: &[&FheBool], mut accumulator: Accumulator) -> Accumulator {
+    for neighbour in neighbours {
+        accumulator += *neighbour;
+    }
+    accumulator
+}
+
+/// Rules are
+///
+/// a live cell will survive if it has 2 or 3 neighbours alive
+/// a dead cell will birth if it has 3 neighbours alive
+///
+/// The 3 bits of the accumulator wrap 8 live neighbours to 0, which is
+/// harmless here: the cell dies or stays dead with either count.
+pub fn is_alive(cell: &FheBool, neighbours: &[&FheBool], accumulator: Accumulator) -> FheBool {
+    let accumulator = phase_timer::time(Phase::Count, || count_neighbours(neighbours, accumulator));
+
+    phase_timer::time(Phase::Rule, || {
+        // check if sum is equal to 2 or 3
+        let sum_is_2_or_3 = !accumulator.2 & accumulator.1;
+        let sum_is_3 = &sum_is_2_or_3 & accumulator.0;
+
+        sum_is_3 | cell & sum_is_2_or_3
+    })
+}
+
+/// Encrypted indicators of the number of live neighbours: the `c`th is true
+/// when exactly `c` of the 8 neighbours are alive.
+///
+/// Costs the neighbour count, 7 gates to tell 8 neighbours from none, which
+/// the accumulator wraps to the same value, and 2 gates per count.
+fn neighbour_count_indicators(
+    neighbours: &[&FheBool; 8],
+    accumulator: Accumulator,
+) -> Vec<FheBool> {
+    let sum = count_neighbours(neighbours, accumulator);
+    let all_alive = neighbours
+        .iter()
+        .map(|&neighbour| neighbour.clone())
+        .reduce(|a, b| a & b)
+        .unwrap();
+    let bits = [&sum.0, &sum.1, &sum.2];
+    let mut count_is: Vec<FheBool> = (0..8)
+        .map(|count| {
+            bits.iter()
+                .enumerate()
+                .map(|(bit, &sum_bit)| {
+                    if count >> bit & 1 == 1 {
+                        sum_bit.clone()
+                    } else {
+                        !sum_bit.clone()
+                    }
+                })
+                .reduce(|a, b| a & b)
+                .unwrap()
+        })
+        .collect();
+    count_is[0] = &count_is[0] & !all_alive.clone();
+    count_is.push(all_alive);
+    count_is
+}
+
+/// Same as `is_alive` for any rule, see `rule::Rule`.
+///
+/// Much more expensive than `is_alive`, which `Board::update` keeps for
+/// Conway's rule.
+pub fn is_alive_with_rule(
+    cell: &FheBool,
+    neighbours: &[&FheBool; 8],
+    accumulator: Accumulator,
+    rule: &rule::Rule,
+) -> FheBool {
+    let zero = accumulator.0.clone();
+    let count_is = phase_timer::time(Phase::Count, || {
+        neighbour_count_indicators(neighbours, accumulator)
+    });
+    let any_of = |counts: u16| {
+        count_is
+            .iter()
+            .enumerate()
+            .filter(|(count, _)| counts >> count & 1 == 1)
+            .map(|(_, count_is)| count_is.clone())
+            .reduce(|a, b| a | b)
+            .unwrap_or_else(|| zero.clone())
+    };
+
+    phase_timer::time(Phase::Rule, || {
+        (!cell.clone() & any_of(rule.birth)) | (cell & &any_of(rule.survival))
+    })
+}
+
+/// Adds two little-endian encrypted numbers, `zero` padding the shorter one.
+///
+/// The sum has one more bit than the longer operand.
+fn add_numbers(a: &[FheBool], b: &[FheBool], zero: &FheBool) -> Vec<FheBool> {
+    let width = a.len().max(b.len());
+    let mut sum = Vec::with_capacity(width + 1);
+    let mut carry: Option<FheBool> = None;
+    for k in 0..width {
+        let x = a.get(k).unwrap_or(zero);
+        let y = b.get(k).unwrap_or(zero);
+        let half_sum = x ^ y;
+        let half_carry = x & y;
+        carry = Some(match carry {
+            None => {
+                sum.push(half_sum);
+                half_carry
+            }
+            Some(carry) => {
+                sum.push(&half_sum ^ &carry);
+                half_carry | (&carry & &half_sum)
+            }
+        });
+    }
+    sum.extend(carry);
+    sum
+}
+
+/// Adds up encrypted bits with a tree of ripple-carry adders. Returns the
+/// bits of the sum, least significant first.
+fn sum_bits(bits: Vec<FheBool>, zero: &FheBool) -> Vec<FheBool> {
+    let mut numbers: Vec<Vec<FheBool>> = bits.into_iter().map(|bit| vec![bit]).collect();
+    while numbers.len() > 1 {
+        numbers = numbers
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => add_numbers(a, b, zero),
+                [a] => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    numbers.pop().unwrap_or_else(|| vec![zero.clone()])
+}
+
+/// Number of bits of the result of `sum_bits` over `n` bits, at most.
+pub fn sum_width(n: usize) -> usize {
+    1 + n.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Encrypted counts of the transitions of a board, see `Board::rule_evidence`.
+/// Every count is indexed by `[alive as usize][live neighbours]` and holds
+/// the bits of the number of cells, least significant first.
+pub struct EvidenceCounters {
+    to_alive: [[Vec<FheBool>; 9]; 2],
+    to_dead: [[Vec<FheBool>; 9]; 2],
+}
+
+impl EvidenceCounters {
+    /// Number of encrypted bits of all the counters.
+    pub fn n_bits(&self) -> usize {
+        self.to_alive
+            .iter()
+            .chain(&self.to_dead)
+            .flatten()
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn decrypt(&self, client_key: &ClientKey) -> inference::RuleEvidence {
+        let decrypt = |counters: &[[Vec<FheBool>; 9]; 2]| {
+            counters
+                .each_ref()
+                .map(|row| row.each_ref().map(|bits| decrypt_number(bits, client_key)))
+        };
+        inference::RuleEvidence {
+            to_alive: decrypt(&self.to_alive),
+            to_dead: decrypt(&self.to_dead),
+        }
+    }
+}
+
+/// A copy of the encrypted cells of a board at some generation.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub generation: usize,
+    pub states: Vec<FheBool>,
+}
+
+/// A snapshot was restored into a board of a different size.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SizeMismatch {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl std::fmt::Display for SizeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "a snapshot of {} cells cannot be restored into a board of {} cells",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for SizeMismatch {}
+
+/// Encrypted `true` when both lists of cells hold the same values.
+fn encrypted_equals(a: &[FheBool], b: &[FheBool]) -> FheBool {
+    assert_eq!(a.len(), b.len(), "cannot compare boards of different sizes");
+    let mut equal: Vec<FheBool> = a.iter().zip(b).map(|(x, y)| !(x ^ y)).collect();
+    while equal.len() > 1 {
+        equal = equal
+            .chunks(2)
+            .map(|pair| match pair {
+                [x, y] => x & y,
+                [x] => x.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    equal.pop().expect("a board has at least one cell")
+}
+
+/// What `Board::step_n` did.
+pub struct StepReport {
+    /// Time taken by every generation that was run.
+    pub durations: Vec<Duration>,
+    /// Retained frames.
+    pub frames: Vec<Snapshot>,
+    /// Whether the batch was stopped before running all its generations.
+    pub cancelled: bool,
+}
+
+/// What lies beyond the edges of a board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BoundaryCondition {
+    /// The edges wrap around: the board is a torus.
+    #[default]
+    Toroidal,
+    /// The cells outside the board are always dead.
+    Fixed,
+    /// The edges reflect the board: the cells beyond an edge are copies of
+    /// those on it.
+    Mirror,
+}
+
+impl BoundaryCondition {
+    pub const ALL: [BoundaryCondition; 3] = [Self::Toroidal, Self::Fixed, Self::Mirror];
+}
+
+impl std::str::FromStr for BoundaryCondition {
+    type Err = String;
+
+    fn from_str(boundary: &str) -> Result<Self, Self::Err> {
+        match boundary {
+            "toroidal" | "periodic" => Ok(Self::Toroidal),
+            "fixed" | "dead" => Ok(Self::Fixed),
+            "mirror" => Ok(Self::Mirror),
+            _ => Err(format!("unknown boundary condition '{}'", boundary)),
+        }
+    }
+}
+
+impl std::fmt::Display for BoundaryCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Toroidal => "toroidal",
+            Self::Fixed => "fixed",
+            Self::Mirror => "mirror",
+        })
+    }
+}
+
+/// Offsets of the neighbours of a cell, in the order of
+/// `Board::neighbour_indices`.
+const NEIGHBOUR_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A board of encrypted cells, with periodic boundary conditions unless
+/// `Board::with_boundary` says otherwise.
+#[derive(Clone)]
+pub struct Board {
+    dimensions: (usize, usize),
+    states: Vec<FheBool>,
+    clean_accumulator: Accumulator,
+    /// Number of updates since the board was created.
+    generation: usize,
+    /// Updated along the cells, see `attach_lifespan_tracker`.
+    lifespan: Option<lifespan::LifespanTracker>,
+    rule: rule::Rule,
+    boundary: BoundaryCondition,
+}
+
+/// What `Board::save` writes after the header of the file: the dimensions,
+/// the generation, the rule, the boundary, the cells and the zeros.
+type Saved = (
+    (usize, usize),
+    usize,
+    rule::Rule,
+    BoundaryCondition,
+    Vec<FheBool>,
+    (FheBool, FheBool, FheBool),
+);
+
+/// `Saved` borrowing from the board.
+type SavedRef<'a> = (
+    (usize, usize),
+    usize,
+    rule::Rule,
+    BoundaryCondition,
+    &'a Vec<FheBool>,
+    (&'a FheBool, &'a FheBool, &'a FheBool),
+);
+
+impl Board {
+    /// Panics unless `states` fills rows of `n_cols` cells, which would leave
+    /// the cells of the last row without the right neighbours.
+    pub fn new(n_cols: usize, states: Vec<FheBool>, zeros: (FheBool, FheBool, FheBool)) -> Self {
+        assert!(
+            n_cols > 0 && !states.is_empty() && states.len().is_multiple_of(n_cols),
+            "{} cells cannot be split in rows of {}",
+            states.len(),
+            n_cols
+        );
+        let n_rows = states.len() / n_cols;
+
+        Self {
+            dimensions: (n_rows, n_cols),
+            states,
+            clean_accumulator: Accumulator::from(zeros),
+            generation: 0,
+            lifespan: None,
+            rule: rule::Rule::conway(),
+            boundary: BoundaryCondition::Toroidal,
+        }
+    }
+
+    /// Plays `rule` instead of Conway's game of life.
+    pub fn with_rule(mut self, rule: rule::Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn rule(&self) -> rule::Rule {
+        self.rule
+    }
+
+    /// Plays with `boundary` instead of periodic boundary conditions.
+    pub fn with_boundary(mut self, boundary: BoundaryCondition) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn boundary(&self) -> BoundaryCondition {
+        self.boundary
+    }
+
+    /// Numbers of rows and columns.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    /// The encrypted cells, in row-major order.
+    pub fn states(&self) -> &[FheBool] {
+        &self.states
+    }
+
+    /// Number of updates since the board was created.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The indices of the neighbours of cell `(i, j)`, with periodic boundary
+    /// conditions, from the top left to the bottom right.
+    fn neighbour_indices(&self, i: usize, j: usize) -> [usize; 8] {
+        let nx = self.dimensions.0;
+        let ny = self.dimensions.1;
+        let im = if i == 0 { nx - 1 } else { i - 1 };
+        let ip = if i == nx - 1 { 0 } else { i + 1 };
+        let jm = if j == 0 { ny - 1 } else { j - 1 };
+        let jp = if j == ny - 1 { 0 } else { j + 1 };
+
+        [
+            im * ny + jm,
+            im * ny + j,
+            im * ny + jp,
+            i * ny + jm,
+            i * ny + jp,
+            ip * ny + jm,
+            ip * ny + j,
+            ip * ny + jp,
+        ]
+    }
+
+    /// The indices of the neighbours of cell `(i, j)` that are on the board:
+    /// the 8 of `neighbour_indices` on a toroidal board, only those inside
+    /// the edges on the others.
+    fn neighbour_indices_on_board(&self, i: usize, j: usize) -> Vec<usize> {
+        let indices = self.neighbour_indices(i, j);
+        if self.boundary == BoundaryCondition::Toroidal {
+            return indices.to_vec();
+        }
+        let (nx, ny) = self.dimensions;
+        indices
+            .into_iter()
+            .zip(NEIGHBOUR_OFFSETS)
+            .filter(|(_, (di, dj))| {
+                i.checked_add_signed(*di).is_some_and(|row| row < nx)
+                    && j.checked_add_signed(*dj).is_some_and(|col| col < ny)
+            })
+            .map(|(k, _)| k)
+            .collect()
+    }
+
+    /// The neighbours of cell `(i, j)`, see `neighbour_indices`. With fixed
+    /// boundary conditions, those outside the board are the encrypted zero of
+    /// the accumulator, so that corner and edge cells still have 8
+    /// neighbours to count. With mirror ones, they are the nearest cells of
+    /// the edge.
+    fn neighbours(&self, i: usize, j: usize) -> [&FheBool; 8] {
+        let mut neighbours = self.neighbour_indices(i, j).map(|k| &self.states[k]);
+        if self.boundary == BoundaryCondition::Toroidal {
+            return neighbours;
+        }
+        let (nx, ny) = self.dimensions;
+        for (neighbour, (di, dj)) in neighbours.iter_mut().zip(NEIGHBOUR_OFFSETS) {
+            let row = i.checked_add_signed(di).filter(|&row| row < nx);
+            let col = j.checked_add_signed(dj).filter(|&col| col < ny);
+            *neighbour = match (row, col, self.boundary) {
+                (Some(_), Some(_), _) => continue,
+                (_, _, BoundaryCondition::Fixed) => &self.clean_accumulator.0,
+                // the edge is reflected: beyond it lies the edge cell again
+                (row, col, _) => &self.states[row.unwrap_or(i) * ny + col.unwrap_or(j)],
+            };
+        }
+        neighbours
+    }
+
+    /// The next state of `cell`, with the gates of `is_alive` for Conway's
+    /// rule and those of `is_alive_with_rule` for the others.
+    fn next_state(&self, cell: &FheBool, neighbours: &[&FheBool; 8]) -> FheBool {
+        let accumulator = self.clean_accumulator.clone();
+        if self.rule == rule::Rule::conway() {
+            is_alive(cell, neighbours, accumulator)
+        } else {
+            is_alive_with_rule(cell, neighbours, accumulator, &self.rule)
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.update_by_rows(&mut |_, _| {});
+    }
+
+    /// Same as `update`, calling `on_row` with the index and the new cells
+    /// of every row as soon as it is computed, to follow a long generation.
+    pub fn update_by_rows(&mut self, on_row: &mut dyn FnMut(usize, &[FheBool])) {
+        let mut new_states = Vec::<FheBool>::with_capacity(self.states.len());
+
+        let nx = self.dimensions.0;
+        let ny = self.dimensions.1;
+        for i in 0..nx {
+            for j in 0..ny {
+                // see if the cell is alive of dead
+                phase_timer::start_cell();
+                let cell = &self.states[i * ny + j];
+                let neighbours = phase_timer::time(Phase::Gather, || self.neighbours(i, j));
+                new_states.push(self.next_state(cell, &neighbours));
+            }
+            on_row(i, &new_states[i * ny..]);
+        }
+
+        // update the board
+        self.advance(new_states);
+    }
+
+    /// Replaces the cells with those of the next generation.
+    fn advance(&mut self, new_states: Vec<FheBool>) {
+        self.states = new_states;
+        self.generation += 1;
+        if let Some(tracker) = &mut self.lifespan {
+            tracker.observe(&self.states, self.generation);
+        }
+    }
+
+    /// Records, for the next `max_generations` generations, the generation
+    /// at which every cell first dies (see `lifespan::LifespanTracker`).
+    /// Snapshots do not include the tracker.
+    pub fn attach_lifespan_tracker(&mut self, max_generations: usize) {
+        self.lifespan = Some(lifespan::LifespanTracker::new(
+            &self.states,
+            self.generation,
+            max_generations,
+            &self.clean_accumulator.0,
+        ));
+    }
+
+    /// The encrypted generations of the first deaths, if a tracker is
+    /// attached.
+    pub fn lifespans(&self) -> Option<Vec<Vec<FheBool>>> {
+        self.lifespan
+            .as_ref()
+            .map(lifespan::LifespanTracker::lifespans)
+    }
+
+    /// Computes the next state of cell `(i, j)` and decrypts its inputs, the
+    /// sum of its neighbours and its new state, whatever the rule.
+    fn traced_next_state(
+        &self,
+        i: usize,
+        j: usize,
+        client_key: &ClientKey,
+    ) -> (FheBool, trace::CellTrace) {
+        let cell = &self.states[i * self.dimensions.1 + j];
+        let neighbours = self.neighbours(i, j);
+        let sum = count_neighbours(&neighbours, self.clean_accumulator.clone());
+        // the same gates as update
+        let next = self.next_state(cell, &neighbours);
+
+        let trace = trace::CellTrace {
+            row: i,
+            col: j,
+            generation: self.generation,
+            alive: cell.decrypt(client_key),
+            neighbours: neighbours.map(|neighbour| neighbour.decrypt(client_key)),
+            sum_bits: [
+                sum.0.decrypt(client_key),
+                sum.1.decrypt(client_key),
+                sum.2.decrypt(client_key),
+            ],
+            next: next.decrypt(client_key),
+            rule: self.rule,
+        };
+        (next, trace)
+    }
+
+    /// Same as `update`, but also decrypts the inputs and the intermediate
+    /// values of every cell, for debugging.
+    pub fn update_traced(&mut self, client_key: &ClientKey) -> Vec<trace::CellTrace> {
+        let (new_states, traces) = (0..self.dimensions.0)
+            .flat_map(|i| (0..self.dimensions.1).map(move |j| (i, j)))
+            .map(|(i, j)| self.traced_next_state(i, j, client_key))
+            .unzip();
+
+        self.advance(new_states);
+        traces
+    }
+
+    /// Decrypts the inputs and the intermediate values of the next update of
+    /// the single cell `(i, j)`, without changing the board.
+    ///
+    /// Only costs the gates of one cell update, and decrypts
+    /// `trace::DECRYPTED_BITS` bits.
+    pub fn trace_cell(&self, i: usize, j: usize, client_key: &ClientKey) -> trace::CellTrace {
+        self.traced_next_state(i, j, client_key).1
+    }
+
+    /// Encrypts a board of `n_rows` by `n_cols` cells holding the named
+    /// pattern of a JSON library (see `library`), and installs the server key.
+    pub fn from_named_pattern(
+        library_path: &Path,
+        pattern_name: &str,
+        n_rows: usize,
+        n_cols: usize,
+        client_key: &ClientKey,
+        server_key: ServerKey,
+    ) -> Result<Board, library::LibraryError> {
+        let cells = library::Library::load(library_path)?.board(pattern_name, (n_rows, n_cols))?;
+
+        let zeros = (
+            FheBool::encrypt(false, client_key),
+            FheBool::encrypt(false, client_key),
+            FheBool::encrypt(false, client_key),
+        );
+        let states = cells
+            .into_iter()
+            .map(|x| FheBool::encrypt(x, client_key))
+            .collect();
+
+        set_server_key(server_key);
+        Ok(Board::new(n_cols, states, zeros))
+    }
+
+    /// Runs `n` generations in one call.
+    ///
+    /// With `retain_every: Some(k)`, a copy of the encrypted board is kept
+    /// every `k` generations of the batch, otherwise only the board itself
+    /// holds the last one. `should_stop` is checked before every generation;
+    /// once it returns `true` the batch ends and the board stays at the last
+    /// completed generation.
+    pub fn step_n(
+        &mut self,
+        n: usize,
+        retain_every: Option<usize>,
+        should_stop: &dyn Fn() -> bool,
+    ) -> StepReport {
+        let mut report = StepReport {
+            durations: Vec::with_capacity(n),
+            frames: Vec::new(),
+            cancelled: false,
+        };
+        for k in 1..=n {
+            if should_stop() {
+                report.cancelled = true;
+                break;
+            }
+            let start = Instant::now();
+            self.update();
+            report.durations.push(start.elapsed());
+            if retain_every.is_some_and(|every| k.is_multiple_of(every)) {
+                report.frames.push(self.snapshot());
+            }
+        }
+        report
+    }
+
+    /// Copies the current generation, to come back to it later.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            generation: self.generation,
+            states: self.states.clone(),
+        }
+    }
+
+    /// Puts the board back to the generation of `snapshot`, which must have
+    /// been taken from a board of the same size.
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<(), SizeMismatch> {
+        if snapshot.states.len() != self.states.len() {
+            return Err(SizeMismatch {
+                expected: self.states.len(),
+                actual: snapshot.states.len(),
+            });
+        }
+        self.states = snapshot.states;
+        self.generation = snapshot.generation;
+        Ok(())
+    }
+
+    /// Scrolls the board by one row away from `edge`, `row` entering on that
+    /// edge and the row on the opposite edge dropping off. The generation is
+    /// left unchanged.
+    pub fn scroll_in_row(
+        &mut self,
+        row: Vec<FheBool>,
+        edge: stream::Edge,
+    ) -> Result<(), stream::RowLengthMismatch> {
+        if row.len() != self.dimensions.1 {
+            return Err(stream::RowLengthMismatch {
+                expected: self.dimensions.1,
+                actual: row.len(),
+            });
+        }
+        let (dropped, entered_at) = stream::scroll_ranges(self.dimensions, edge);
+        self.states.drain(dropped);
+        self.states.splice(entered_at..entered_at, row);
+        Ok(())
+    }
+
+    /// Replaces the cells of `patch`, made for this generation of the board,
+    /// leaving the generation unchanged. Returns the number of cells
+    /// replaced, or an error before replacing any if the patch was made for
+    /// another board.
+    pub fn apply_patch(&mut self, patch: &patch::Patch) -> Result<usize, patch::PatchError> {
+        patch.check(self.dimensions, self.generation)?;
+        for (k, cell) in &patch.cells {
+            self.states[*k] = cell.clone();
+        }
+        Ok(patch.len())
+    }
+
+    /// Encrypted `true` when the board holds the same cells as `snapshot`,
+    /// so that the client learns whether the board changed without
+    /// decrypting it.
+    pub fn equals(&self, snapshot: &Snapshot) -> FheBool {
+        encrypted_equals(&self.states, &snapshot.states)
+    }
+
+    /// Encrypted `true` for every cell that differs from `snapshot`, in
+    /// row-major order, so that the client learns which cells changed
+    /// without decrypting them.
+    pub fn changes(&self, snapshot: &Snapshot) -> Vec<FheBool> {
+        assert_eq!(
+            self.states.len(),
+            snapshot.states.len(),
+            "cannot compare boards of different sizes"
+        );
+        self.states
+            .iter()
+            .zip(&snapshot.states)
+            .map(|(x, y)| x ^ y)
+            .collect()
+    }
+
+    /// Updates the board and returns an encrypted `true` when any cell
+    /// changed, so that the client can stop once the board is still by
+    /// decrypting a single bit.
+    pub fn update_with_change_flag(&mut self) -> FheBool {
+        let previous = self.states.clone();
+        self.update();
+        !encrypted_equals(&self.states, &previous)
+    }
+
+    /// Updates the board and returns a checksum of the new generation.
+    ///
+    /// Comparing the checksums of two runs from the same initial board
+    /// detects non-deterministic results without storing the boards.
+    pub fn step_with_checksum(&mut self, client_key: &ClientKey) -> u32 {
+        self.update();
+        frame_checksum(&self.decrypt(client_key), self.generation)
+    }
+
+    /// Copies the `2 * half_size + 1` wide square centered on
+    /// `(center_row, center_col)` into a new board. The square wraps around
+    /// the edges of a toroidal board; beyond the edges of the others its
+    /// cells are dead.
+    ///
+    /// Panics if the center is outside the board or the square is wider
+    /// than the board along either of its sides.
+    pub fn extract_pattern_at(
+        &self,
+        center_row: usize,
+        center_col: usize,
+        half_size: usize,
+    ) -> Board {
+        let (n_rows, n_cols) = self.dimensions;
+        assert!(
+            center_row < n_rows && center_col < n_cols,
+            "the center is outside the board"
+        );
+        assert!(
+            half_size <= (n_rows.min(n_cols) - 1) / 2,
+            "the square is wider than the board"
+        );
+        let size = 2 * half_size + 1;
+
+        // the index along an axis of `length` cells of the `d`-th cell of the
+        // square, none beyond the edge of a board that does not wrap
+        let toroidal = self.boundary == BoundaryCondition::Toroidal;
+        let index = |center: usize, d: usize, length: usize| {
+            if toroidal {
+                Some((center + d + size * length - half_size) % length)
+            } else {
+                (center + d).checked_sub(half_size).filter(|&k| k < length)
+            }
+        };
+        let mut states = Vec::with_capacity(size * size);
+        for di in 0..size {
+            for dj in 0..size {
+                let state = match (index(center_row, di, n_rows), index(center_col, dj, n_cols)) {
+                    (Some(i), Some(j)) => &self.states[i * n_cols + j],
+                    _ => &self.clean_accumulator.0,
+                };
+                states.push(state.clone());
+            }
+        }
+
+        Self {
+            dimensions: (size, size),
+            states,
+            clean_accumulator: self.clean_accumulator.clone(),
+            generation: self.generation,
+            lifespan: None,
+            rule: self.rule,
+            boundary: self.boundary,
+        }
+    }
+
+    /// Prints a heat map of the number of live neighbours of every cell, to
+    /// see where cells are about to be born or to die.
+    pub fn display_neighbour_counts(&self, client_key: &ClientKey) {
+        let counts = reference::neighbour_counts_with_boundary(
+            &self.decrypt(client_key),
+            self.dimensions,
+            self.boundary,
+        );
+        print!(
+            "{}",
+            reference::render_neighbour_counts(&counts, self.dimensions.1)
+        );
+    }
+
+    /// Toy hash: the bits of `input` become the initial board, which evolves
+    /// for `rounds` generations before being decrypted back into bytes.
+    ///
+    /// This only demonstrates a private computation, the game of life is not
+    /// a secure compression function: the all-zero input hashes to zeros and
+    /// small patterns can die out or settle quickly.
+    pub fn cellular_hash(
+        input: &[u8],
+        rounds: usize,
+        client_key: &ClientKey,
+        server_key: ServerKey,
+    ) -> Vec<u8> {
+        let (cells, n_cols) = hash_cells(input);
+        let zeros = (
+            FheBool::encrypt(false, client_key),
+            FheBool::encrypt(false, client_key),
+            FheBool::encrypt(false, client_key),
+        );
+        let states = cells
+            .into_iter()
+            .map(|x| FheBool::encrypt(x, client_key))
+            .collect();
+
+        set_server_key(server_key);
+        let mut board = Board::new(n_cols, states, zeros);
+        for _ in 0..rounds {
+            board.update();
+        }
+
+        bits::pack_cells(&board.decrypt(client_key))
+    }
+
+    /// Counts the live cells homomorphically, with a tree of ripple-carry
+    /// adders. Returns the bits of the count, least significant first.
+    pub fn encrypted_population_count(&self) -> Vec<FheBool> {
+        sum_bits(self.states.clone(), &self.clean_accumulator.0)
+    }
+
+    /// Counts homomorphically, for every state of a cell and number of live
+    /// neighbours on this board, how many cells are alive and dead on
+    /// `after`, so that the client can infer the rule that produced `after`
+    /// (see `inference::RuleEvidence::infer`).
+    ///
+    /// Costs the neighbour count of an update, some 80 more gates per cell to
+    /// sort the cells into buckets, and 36 adder trees over the board.
+    pub fn rule_evidence(&self, after: &Board) -> EvidenceCounters {
+        assert_eq!(
+            self.dimensions, after.dimensions,
+            "the boards differ in size"
+        );
+        let zero = &self.clean_accumulator.0;
+        let mut to_alive: [[Vec<FheBool>; 9]; 2] = Default::default();
+        let mut to_dead: [[Vec<FheBool>; 9]; 2] = Default::default();
+
+        let (nx, ny) = self.dimensions;
+        for i in 0..nx {
+            for j in 0..ny {
+                let count_is = neighbour_count_indicators(
+                    &self.neighbours(i, j),
+                    self.clean_accumulator.clone(),
+                );
+
+                let cell = &self.states[i * ny + j];
+                let next = &after.states[i * ny + j];
+                let dies = !next.clone();
+                for (state, in_state) in [!cell.clone(), cell.clone()].iter().enumerate() {
+                    for (count, count_is) in count_is.iter().enumerate() {
+                        let in_bucket = in_state & count_is;
+                        to_alive[state][count].push(&in_bucket & next);
+                        to_dead[state][count].push(in_bucket & dies.clone());
+                    }
+                }
+            }
+        }
+
+        let sum_all = |counters: [[Vec<FheBool>; 9]; 2]| {
+            counters.map(|row| row.map(|bits| sum_bits(bits, zero)))
+        };
+        EvidenceCounters {
+            to_alive: sum_all(to_alive),
+            to_dead: sum_all(to_dead),
+        }
+    }
+
+    /// Computes homomorphically the Chebyshev distance from every cell to the
+    /// nearest live cell, capped at `max_distance`, by dilating the live cells
+    /// one ring per round, across the edges only on a toroidal board (see
+    /// `reference::distance_field_with_boundary`). Returns the bits of every
+    /// distance, least significant first, in row-major order.
+    ///
+    /// Costs `max_distance - 1` passes of 8 ORs per cell, plus the updates of
+    /// the counters.
+    pub fn distance_field(&self, max_distance: usize) -> Vec<Vec<FheBool>> {
+        assert!(max_distance > 0, "the maximum distance must be at least 1");
+        let zero = &self.clean_accumulator.0;
+        let n_bits = (usize::BITS - max_distance.leading_zeros()) as usize;
+        let mut distances = vec![vec![zero.clone(); n_bits]; self.states.len()];
+        let mut covered = self.states.clone();
+
+        let (nx, ny) = self.dimensions;
+        for distance in 1..=max_distance {
+            let newly_covered: Vec<FheBool> = (0..nx * ny)
+                .map(|k| {
+                    let uncovered = !covered[k].clone();
+                    if distance == max_distance {
+                        // everything still uncovered is at least that far
+                        return uncovered;
+                    }
+                    // the cells beyond the edges of a board that does not wrap
+                    // are dead, or copies of cells already among the neighbours
+                    self.neighbour_indices_on_board(k / ny, k % ny)
+                        .iter()
+                        .map(|&l| covered[l].clone())
+                        .reduce(|a, b| a | b)
+                        .map_or_else(|| zero.clone(), |reached| reached & uncovered)
+                })
+                .collect();
+
+            for (k, newly) in newly_covered.iter().enumerate() {
+                for (bit, distance_bit) in distances[k].iter_mut().enumerate() {
+                    if distance >> bit & 1 == 1 {
+                        *distance_bit = &*distance_bit | newly;
+                    }
+                }
+                if distance < max_distance {
+                    covered[k] = &covered[k] | newly;
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Decrypts the board and shrinks it by `factor` for display, see
+    /// `resample::downsample`.
+    pub fn downsample(&self, client_key: &ClientKey, factor: usize) -> (Vec<bool>, (usize, usize)) {
+        resample::downsample(&self.decrypt(client_key), self.dimensions, factor)
+    }
+
+    /// Runs `temporal_window` generations on a copy of the board and
+    /// estimates the topological entropy from the trajectories of the cells
+    /// (see `analysis::topological_entropy`).
+    pub fn estimate_topological_entropy(
+        &self,
+        client_key: &ClientKey,
+        temporal_window: usize,
+    ) -> f64 {
+        let mut board = self.clone();
+        let frames: Vec<_> = (0..temporal_window)
+            .map(|_| {
+                board.update();
+                board.decrypt(client_key)
+            })
+            .collect();
+
+        analysis::topological_entropy(&frames)
+    }
+
+    /// Writes the encrypted cells, the generation, the rule and the boundary
+    /// conditions of the board, to carry on with `load` in a later run.
+    /// `parameters` names the parameters of the keys, see `keys`.
+    pub fn save(&self, path: &Path, parameters: &str) -> Result<(), keys::FileError> {
+        keys::write_file(path, parameters, &self.saved(), false)
+    }
+
+    /// What `save` writes, in memory.
+    pub fn to_bytes(&self, parameters: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        keys::write_to(&mut bytes, parameters, &self.saved())
+            .expect("writing to memory cannot fail");
+        bytes
+    }
+
+    fn saved(&self) -> SavedRef<'_> {
+        let accumulator = &self.clean_accumulator;
+        (
+            self.dimensions,
+            self.generation,
+            self.rule,
+            self.boundary,
+            &self.states,
+            (&accumulator.0, &accumulator.1, &accumulator.2),
+        )
+    }
+
+    /// Reads a board written by `save` with the same parameters. Its cells
+    /// can only be updated and decrypted with the keys it was encrypted with.
+    pub fn load(path: &Path, parameters: &str) -> Result<Board, keys::FileError> {
+        Self::from_saved(keys::read_file(path, parameters)?)
+    }
+
+    /// Reads a board from the bytes of `to_bytes`, see `load`.
+    pub fn from_bytes(bytes: &[u8], parameters: &str) -> Result<Board, keys::FileError> {
+        Self::from_saved(keys::read_from(bytes, parameters)?)
+    }
+
+    fn from_saved(saved: Saved) -> Result<Board, keys::FileError> {
+        let (dimensions, generation, rule, boundary, states, zeros) = saved;
+        if dimensions.0.checked_mul(dimensions.1) != Some(states.len()) || states.is_empty() {
+            return Err(keys::FileError::Format(Box::new(
+                bincode::ErrorKind::Custom(format!(
+                    "{} cells for a {}x{} board",
+                    states.len(),
+                    dimensions.0,
+                    dimensions.1
+                )),
+            )));
+        }
+        let mut board = Board::new(dimensions.1, states, zeros)
+            .with_rule(rule)
+            .with_boundary(boundary);
+        board.generation = generation;
+        Ok(board)
+    }
+
+    /// Decrypts every cell, returning the states in row-major order.
+    pub fn decrypt(&self, client_key: &ClientKey) -> Vec<bool> {
+        decrypt_cells(&self.states, client_key)
+    }
+
+    /// Renders the decrypted board as a TikZ picture, `cell_size_mm` wide
+    /// squares filled for the live cells.
+    #[cfg(feature = "latex-export")]
+    pub fn to_latex_tikz(&self, client_key: &ClientKey, cell_size_mm: f64) -> String {
+        latex::tikz_picture(&self.decrypt(client_key), self.dimensions, cell_size_mm)
+    }
+}
+
+/// XOR of the cells packed in 32 bit words (cell `k` is bit `k % 32` of word
+/// `k / 32`), XORed with the generation number.
+fn frame_checksum(cells: &[bool], generation: usize) -> u32 {
+    bits::pack_cells(cells)
+        .chunks(4)
+        .map(|word| {
+            let mut le_bytes = [0; 4];
+            le_bytes[..word.len()].copy_from_slice(word);
+            u32::from_le_bytes(le_bytes)
+        })
+        .fold(generation as u32, |acc, word| acc ^ word)
+}
+
+/// Number of rows of a `cellular_hash` board at least, so that no cell is its
+/// own neighbour.
+const MIN_HASH_ROWS: usize = 3;
+
+/// Lays out `input` as a board with one byte per row, unpacked with
+/// `bits::unpack_cells`, padded with dead rows up to `MIN_HASH_ROWS`. Returns
+/// the cells and the number of columns; `bits::pack_cells` is the inverse.
+fn hash_cells(input: &[u8]) -> (Vec<bool>, usize) {
+    let n_rows = input.len().max(MIN_HASH_ROWS);
+    (bits::unpack_cells(input, n_rows * 8), 8)
+}
+
+/// Decrypts cells stored apart from a board.
+pub fn decrypt_cells(cells: &[FheBool], client_key: &ClientKey) -> Vec<bool> {
+    cells.iter().map(|cell| cell.decrypt(client_key)).collect()
+}
+
+/// Decrypts a little-endian encrypted number.
+pub fn decrypt_number(bits: &[FheBool], client_key: &ClientKey) -> usize {
+    bits.iter().enumerate().fold(0, |acc, (k, bit)| {
+        acc | (usize::from(bit.decrypt(client_key)) << k)
+    })
+}
+
+/// Returns the `(row, col)` coordinates of the live cells of a decrypted board.
+///
+/// Cells are visited in row-major order, so the coordinates come out sorted.
+pub fn live_coordinates(cells: &[bool], n_cols: usize) -> Vec<(usize, usize)> {
+    cells
+        .iter()
+        .enumerate()
+        .filter(|(_, alive)| **alive)
+        .map(|(k, _)| (k / n_cols, k % n_cols))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::bits::pack_cells;
+    use crate::inference::RuleEvidence;
+    use crate::keys::FileError;
+    use crate::lifespan::plaintext_lifespans;
+    use crate::pattern::CATALOG_MARGIN;
+    use crate::pattern::{catalog_pattern, centered, tile, GLIDER};
+    use crate::reference::{
+        distance_field, distance_field_with_boundary, neighbour_counts, next_generation,
+        next_generation_with,
+    };
+    use crate::rule::Rule;
+    use crate::shadow::Shadow;
+    use crate::stream::{scroll_in_row, Edge, RowLengthMismatch};
+    use crate::testing::{assert_boards_eq, default_keys, encrypt_board, set_up_keys};
+    use crate::trace::{first_mismatch, CellTrace};
+    use crate::{
+        decrypt_cells, decrypt_number, frame_checksum, hash_cells, is_alive, live_coordinates,
+        Accumulator, Board, BoundaryCondition, SizeMismatch,
+    };
+    use concrete::prelude::*;
+    use concrete::{ClientKey, FheBool};
+    use std::cell::Cell;
+
+    fn decrypt_acc(acc: &Accumulator, client_key: &ClientKey) -> (bool, bool, bool) {
+        (
+            acc.2.decrypt(client_key),
+            acc.1.decrypt(client_key),
+            acc.0.decrypt(client_key),
+        )
+    }
+
+    #[test]
+    fn test_accumulator() {
+        let client_key = set_up_keys();
+
+        let zeros = (
+            FheBool::encrypt(false, &client_key),
+            FheBool::encrypt(false, &client_key),
+            FheBool::encrypt(false, &client_key),
+        );
+
+        let mut accumulator = Accumulator::from(zeros);
+        let ftrue = FheBool::encrypt(true, &client_key);
+
+        let bits = decrypt_acc(&accumulator, &client_key);
+        assert_eq!(bits, (false, false, false));
+
+        accumulator += &ftrue;
+        let bits = decrypt_acc(&accumulator, &client_key);
+        assert_eq!(bits, (false, false, true));
+    }
+
+    #[test]
+    fn test_is_alive_every_count() {
+        let client_key = set_up_keys();
+
+        let encrypt = |alive| FheBool::encrypt(alive, &client_key);
+        let zeros = || (encrypt(false), encrypt(false), encrypt(false));
+        for count in 0..=8 {
+            let neighbours: Vec<FheBool> = (0..8).map(|k| encrypt(k < count)).collect();
+            let neighbours: Vec<&FheBool> = neighbours.iter().collect();
+            for alive in [false, true] {
+                let next = is_alive(&encrypt(alive), &neighbours, Accumulator::from(zeros()));
+                assert_eq!(
+                    next.decrypt(&client_key),
+                    Rule::conway().next_state(alive, count),
+                    "alive: {}, {} neighbours",
+                    alive,
+                    count
+                );
+            }
+        }
+
+        // every cell of a full board has 8 live neighbours
+        let mut board = encrypt_board(&[true; 16], 4, &client_key);
+        board.update();
+        let expected = next_generation(&[true; 16], (4, 4));
+        assert_eq!(board.decrypt(&client_key), expected);
+        assert_eq!(expected, vec![false; 16]);
+    }
+
+    #[test]
+    fn test_update_matches_reference() {
+        let client_key = set_up_keys();
+
+        // a blinker
+        let dimensions = (5, 5);
+        let mut expected = vec![false; 25];
+        expected[7] = true;
+        expected[12] = true;
+        expected[17] = true;
+
+        let mut board = encrypt_board(&expected, dimensions.1, &client_key);
+
+        for generation in 1..=2 {
+            board.update();
+            expected = next_generation(&expected, dimensions);
+            assert_boards_eq(
+                &expected,
+                &board.decrypt(&client_key),
+                dimensions,
+                &format!("blinker, generation {}", generation),
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "10 cells cannot be split in rows of 3")]
+    fn test_ragged_board() {
+        let (client_key, _) = default_keys();
+        encrypt_board(&[false; 10], 3, &client_key);
+    }
+
+    #[test]
+    fn test_update_with_rule() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        for rule in ["B36/S23", "B2/S", "B1357/S1357"] {
+            let rule: Rule = rule.parse().unwrap();
+            let mut expected = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 5);
+            let mut board = encrypt_board(&expected, dimensions.1, &client_key).with_rule(rule);
+
+            for generation in 1..=2 {
+                board.update();
+                expected =
+                    next_generation_with(&expected, dimensions, &rule, BoundaryCondition::Toroidal);
+                assert_boards_eq(
+                    &expected,
+                    &board.decrypt(&client_key),
+                    dimensions,
+                    &format!("{}, generation {}", rule, generation),
+                );
+            }
+        }
+
+        // 8 neighbours, which the accumulator wraps to 0
+        let mut board =
+            encrypt_board(&[true; 9], 3, &client_key).with_rule("B0/S8".parse().unwrap());
+        board.update();
+        assert_eq!(board.decrypt(&client_key), vec![true; 9]);
+        board.update();
+        assert_eq!(board.decrypt(&client_key), vec![true; 9]);
+    }
+
+    #[test]
+    fn test_seeds() {
+        let client_key = set_up_keys();
+
+        // B2/S: the domino dies, and the cells next to both of its cells are
+        // born
+        #[rustfmt::skip]
+        let domino = [
+            false, false, false, false,
+            false, true, true, false,
+            false, false, false, false,
+            false, false, false, false,
+        ];
+        let mut board = encrypt_board(&domino, 4, &client_key).with_rule("B2/S".parse().unwrap());
+        board.update();
+        #[rustfmt::skip]
+        assert_eq!(board.decrypt(&client_key), vec![
+            false, true, true, false,
+            false, false, false, false,
+            false, true, true, false,
+            false, false, false, false,
+        ]);
+    }
+
+    #[test]
+    fn test_update_bounded() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        let mut expected = generate(&BoardProfile::Soup { density: 0.5 }, dimensions, 8);
+        let mut board = encrypt_board(&expected, dimensions.1, &client_key)
+            .with_boundary(BoundaryCondition::Fixed);
+        for generation in 1..=2 {
+            board.update();
+            expected = next_generation_with(
+                &expected,
+                dimensions,
+                &Rule::conway(),
+                BoundaryCondition::Fixed,
+            );
+            assert_boards_eq(
+                &expected,
+                &board.decrypt(&client_key),
+                dimensions,
+                &format!("generation {}", generation),
+            );
+        }
+
+        let mut expected = generate(&BoardProfile::Soup { density: 0.5 }, dimensions, 9);
+        let mut board = encrypt_board(&expected, dimensions.1, &client_key)
+            .with_boundary(BoundaryCondition::Mirror);
+        for generation in 1..=2 {
+            board.update();
+            expected = next_generation_with(
+                &expected,
+                dimensions,
+                &Rule::conway(),
+                BoundaryCondition::Mirror,
+            );
+            assert_boards_eq(
+                &expected,
+                &board.decrypt(&client_key),
+                dimensions,
+                &format!("mirror, generation {}", generation),
+            );
+        }
+
+        // a full board only keeps its corners, which have 3 neighbours, as the
+        // edges have 5 and the centre 8
+        let mut board =
+            encrypt_board(&[true; 9], 3, &client_key).with_boundary(BoundaryCondition::Fixed);
+        board.update();
+        #[rustfmt::skip]
+        assert_eq!(board.decrypt(&client_key), vec![
+            true, false, true,
+            false, false, false,
+            true, false, true,
+        ]);
+    }
+
+    #[test]
+    fn test_glider_at_the_edge() {
+        let client_key = set_up_keys();
+
+        // a glider in the bottom right corner of a 5x5 board, heading there
+        let glider_at = |row: usize, col: usize| {
+            let mut cells = vec![false; 25];
+            for (k, _) in GLIDER.iter().enumerate().filter(|(_, alive)| **alive) {
+                cells[(row + k / 3) % 5 * 5 + (col + k % 3) % 5] = true;
+            }
+            cells
+        };
+        let cells = glider_at(2, 2);
+
+        let mut periodic = encrypt_board(&cells, 5, &client_key);
+        let mut dead = periodic.clone().with_boundary("dead".parse().unwrap());
+        for _ in 0..4 {
+            periodic.update();
+            dead.update();
+        }
+
+        // the glider comes back through the opposite edges of the torus, and
+        // hits the corner of the fixed board, where it settles into a block
+        assert_eq!(periodic.decrypt(&client_key), glider_at(3, 3));
+        let mut block = vec![false; 25];
+        for k in [18, 19, 23, 24] {
+            block[k] = true;
+        }
+        assert_eq!(dead.decrypt(&client_key), block);
+    }
+
+    #[test]
+    fn test_step_with_checksum_is_deterministic() {
+        let client_key = set_up_keys();
+
+        #[rustfmt::skip]
+        let cells = [
+            false, true, false,
+            true, true, false,
+            false, false, true,
+        ];
+
+        let run = || {
+            let mut board = encrypt_board(&cells, 3, &client_key);
+            (0..5)
+                .map(|_| board.step_with_checksum(&client_key))
+                .collect::<Vec<_>>()
+        };
+
+        let checksums = run();
+        assert_eq!(checksums, run());
+        // the checksums are those of the plaintext evolution
+        let mut expected = cells.to_vec();
+        for (generation, checksum) in checksums.into_iter().enumerate() {
+            expected = next_generation(&expected, (3, 3));
+            assert_eq!(checksum, frame_checksum(&expected, generation + 1));
+        }
+    }
+
+    #[test]
+    fn test_step_n_retains_frames() {
+        let client_key = set_up_keys();
+
+        let dimensions = (6, 6);
+        let initial = tile(&GLIDER, 3, dimensions, (6, 6)).unwrap();
+        let mut expected = vec![initial.clone()];
+        for _ in 0..5 {
+            expected.push(next_generation(expected.last().unwrap(), dimensions));
+        }
+
+        let mut board = encrypt_board(&initial, dimensions.1, &client_key);
+        let report = board.step_n(5, Some(2), &|| false);
+        assert_eq!(report.durations.len(), 5);
+        assert!(!report.cancelled);
+        let generations: Vec<_> = report.frames.iter().map(|frame| frame.generation).collect();
+        assert_eq!(generations, vec![2, 4]);
+        for frame in &report.frames {
+            assert_boards_eq(
+                &expected[frame.generation],
+                &decrypt_cells(&frame.states, &client_key),
+                dimensions,
+                &format!("retained generation {}", frame.generation),
+            );
+        }
+        assert_boards_eq(
+            &expected[5],
+            &board.decrypt(&client_key),
+            dimensions,
+            "last",
+        );
+
+        // only the board holds the last generation
+        let mut board = encrypt_board(&initial, dimensions.1, &client_key);
+        let report = board.step_n(2, None, &|| false);
+        assert!(report.frames.is_empty());
+        assert_boards_eq(
+            &expected[2],
+            &board.decrypt(&client_key),
+            dimensions,
+            "unretained",
+        );
+    }
+
+    #[test]
+    fn test_step_n_cancelled() {
+        let client_key = set_up_keys();
+
+        let dimensions = (6, 6);
+        let initial = tile(&GLIDER, 3, dimensions, (6, 6)).unwrap();
+        let mut board = encrypt_board(&initial, dimensions.1, &client_key);
+
+        // stop after 3 of the 10 generations
+        let checks = Cell::new(0);
+        let report = board.step_n(10, Some(1), &|| {
+            checks.set(checks.get() + 1);
+            checks.get() > 3
+        });
+
+        assert!(report.cancelled);
+        assert_eq!(report.durations.len(), 3);
+        assert_eq!(report.frames.len(), 3);
+        assert_eq!(board.generation, 3);
+        let mut expected = initial;
+        for _ in 0..3 {
+            expected = next_generation(&expected, dimensions);
+        }
+        assert_boards_eq(
+            &expected,
+            &board.decrypt(&client_key),
+            dimensions,
+            "cancelled",
+        );
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let client_key = set_up_keys();
+
+        let dimensions = (6, 6);
+        let initial = tile(&GLIDER, 3, dimensions, (6, 6)).unwrap();
+        let mut board = encrypt_board(&initial, dimensions.1, &client_key);
+
+        let snapshot = board.snapshot();
+        board.update();
+        board.update();
+        assert_ne!(board.decrypt(&client_key), initial);
+
+        board.restore(snapshot).unwrap();
+        assert_eq!(board.generation, 0);
+        assert_boards_eq(
+            &initial,
+            &board.decrypt(&client_key),
+            dimensions,
+            "restored",
+        );
+
+        let small = encrypt_board(&[false; 9], 3, &client_key);
+        assert_eq!(
+            board.restore(small.snapshot()),
+            Err(SizeMismatch {
+                expected: 36,
+                actual: 9
+            })
+        );
+    }
+
+    #[test]
+    fn test_equals() {
+        let client_key = set_up_keys();
+
+        // a block next to a blinker: only the blinker changes
+        #[rustfmt::skip]
+        let cells = [
+            true, true, false, false, false, false, false, false, false,
+            true, true, false, false, false, true, false, false, false,
+            false, false, false, false, false, true, false, false, false,
+            false, false, false, false, false, true, false, false, false,
+            false, false, false, false, false, false, false, false, false,
+        ];
+        let mut board = encrypt_board(&cells, 9, &client_key);
+        let initial = board.snapshot();
+        assert!(board.equals(&initial).decrypt(&client_key));
+
+        board.update();
+        assert!(!board.equals(&initial).decrypt(&client_key));
+        board.update();
+        assert!(board.equals(&initial).decrypt(&client_key));
+
+        // a block alone is still
+        let mut block_cells = vec![false; 16];
+        for k in [5, 6, 9, 10] {
+            block_cells[k] = true;
+        }
+        let mut block = encrypt_board(&block_cells, 4, &client_key);
+        let initial_block = block.snapshot();
+        block.update();
+        assert!(block.equals(&initial_block).decrypt(&client_key));
+
+        // a single cell differs
+        let mut other = encrypt_board(&cells, 9, &client_key);
+        other.states[44] = FheBool::encrypt(true, &client_key);
+        assert!(!other.equals(&initial).decrypt(&client_key));
+    }
+
+    #[test]
+    fn test_update_with_change_flag() {
+        let client_key = set_up_keys();
+
+        let mut cells = vec![false; 36];
+        for k in [0, 1, 6, 7] {
+            cells[k] = true;
+        }
+        let mut block =
+            encrypt_board(&cells, 6, &client_key).with_boundary(BoundaryCondition::Fixed);
+        assert!(!block.update_with_change_flag().decrypt(&client_key));
+        assert_eq!(block.generation(), 1);
+        assert_eq!(block.decrypt(&client_key), cells);
+
+        let mut cells = vec![false; 25];
+        for k in [7, 12, 17] {
+            cells[k] = true;
+        }
+        let mut blinker = encrypt_board(&cells, 5, &client_key);
+        assert!(blinker.update_with_change_flag().decrypt(&client_key));
+    }
+
+    #[test]
+    fn test_extract_pattern_at() {
+        let (client_key, _) = default_keys();
+
+        let dimensions = (7, 7);
+        let mut cells = vec![false; 49];
+        for (k, alive) in GLIDER.iter().enumerate() {
+            cells[(k / 3 + 2) * 7 + k % 3 + 2] = *alive;
+        }
+        let board = encrypt_board(&cells, dimensions.1, &client_key);
+
+        let extracted = board.extract_pattern_at(3, 3, 2);
+        assert_eq!(extracted.dimensions, (5, 5));
+        let mut expected = vec![false; 25];
+        for (k, alive) in GLIDER.iter().enumerate() {
+            expected[(k / 3 + 1) * 5 + k % 3 + 1] = *alive;
+        }
+        assert_boards_eq(
+            &expected,
+            &extracted.decrypt(&client_key),
+            (5, 5),
+            "centered",
+        );
+
+        // around the top left corner the region wraps to the other edges
+        let extracted = board.extract_pattern_at(0, 0, 3);
+        let expected: Vec<bool> = (0..49)
+            .map(|k| cells[(k / 7 + 4) % 7 * 7 + (k % 7 + 4) % 7])
+            .collect();
+        assert_boards_eq(
+            &expected,
+            &extracted.decrypt(&client_key),
+            dimensions,
+            "wrapped",
+        );
+
+        // the cells beyond the edges of a board that does not wrap are dead
+        let board = board.with_boundary(BoundaryCondition::Fixed);
+        let extracted = board.extract_pattern_at(0, 0, 3);
+        let expected: Vec<bool> = (0..49)
+            .map(|k| k / 7 >= 3 && k % 7 >= 3 && cells[(k / 7 - 3) * 7 + k % 7 - 3])
+            .collect();
+        assert_boards_eq(
+            &expected,
+            &extracted.decrypt(&client_key),
+            dimensions,
+            "fixed",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the square is wider than the board")]
+    fn test_extract_wider_than_board() {
+        let (client_key, _) = default_keys();
+        // 11 cells wide: the board is wider, but only 3 cells tall
+        let board = encrypt_board(&[false; 120], 40, &client_key);
+        board.extract_pattern_at(1, 20, 5);
+    }
+
+    #[test]
+    fn test_estimate_topological_entropy() {
+        let client_key = set_up_keys();
+
+        let mut blinker = vec![false; 25];
+        for k in [7, 12, 17] {
+            blinker[k] = true;
+        }
+        let board = encrypt_board(&blinker, 5, &client_key);
+
+        // four trajectories over two generations
+        assert_eq!(board.estimate_topological_entropy(&client_key, 2), 1.0);
+        // the board itself did not move
+        assert_eq!(board.generation, 0);
+        assert_eq!(board.decrypt(&client_key), blinker);
+    }
+
+    #[test]
+    fn test_shadow_detects_corrupted_cell() {
+        let client_key = set_up_keys();
+
+        let mut blinker = vec![false; 25];
+        for k in [7, 12, 17] {
+            blinker[k] = true;
+        }
+        let mut board = encrypt_board(&blinker, 5, &client_key);
+        let mut shadow = Shadow::new(blinker, (5, 5), 2);
+
+        board.update();
+        shadow.advance();
+        assert!(!shadow.is_due());
+
+        // a wrong ciphertext is only noticed at the next comparison
+        board.states[0] = FheBool::encrypt(true, &client_key);
+        board.update();
+        shadow.advance();
+        assert!(shadow.is_due());
+        assert!(shadow.compare(&board.decrypt(&client_key)).is_err());
+    }
+
+    #[test]
+    fn test_update_traced() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 5);
+        let mut cells = vec![false; 25];
+        for (k, alive) in GLIDER.iter().enumerate() {
+            cells[(k / 3 + 1) * 5 + k % 3 + 1] = *alive;
+        }
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+
+        let traces = board.update_traced(&client_key);
+
+        assert_eq!(traces.len(), 25);
+        assert_eq!(first_mismatch(&traces), None);
+        assert!(traces
+            .iter()
+            .all(|trace| trace.sum() == trace.expected_sum()));
+        assert_eq!(
+            traces[6].neighbours,
+            [false, false, false, false, true, false, false, false]
+        );
+        let expected = next_generation(&cells, dimensions);
+        assert_boards_eq(&expected, &board.decrypt(&client_key), dimensions, "traced");
+        assert_eq!(board.generation, 1);
+    }
+
+    #[test]
+    fn test_trace_cell() {
+        let client_key = set_up_keys();
+
+        // a blinker across the middle row, the cell above its centre is born
+        // and dies in turn
+        let mut cells = vec![false; 25];
+        for k in [11, 12, 13] {
+            cells[k] = true;
+        }
+        let mut board = encrypt_board(&cells, 5, &client_key);
+
+        let mut traces = Vec::new();
+        for _ in 0..3 {
+            traces.push(board.trace_cell(1, 2, &client_key));
+            board.update();
+        }
+
+        assert_eq!(first_mismatch(&traces), None);
+        let sums: Vec<u8> = traces.iter().map(CellTrace::sum).collect();
+        assert_eq!(sums, [3, 1, 3]);
+        let states: Vec<(bool, bool)> = traces
+            .iter()
+            .map(|trace| (trace.alive, trace.next))
+            .collect();
+        assert_eq!(states, [(false, true), (true, false), (false, true)]);
+        assert_eq!(traces[1].generation, 1);
+        // tracing leaves the board alone: after 3 generations the blinker is
+        // in its second phase
+        assert_eq!(board.decrypt(&client_key), next_generation(&cells, (5, 5)));
+    }
+
+    #[test]
+    fn test_trace_other_rule() {
+        let client_key = set_up_keys();
+
+        // 6 live neighbours: a cell is born in HighLife, B36/S23, and not in
+        // Conway's game
+        let mut cells = vec![true; 9];
+        cells[4] = false;
+        cells[1] = false;
+        cells[7] = false;
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        let board = encrypt_board(&cells, 3, &client_key)
+            .with_rule(highlife)
+            .with_boundary(BoundaryCondition::Fixed);
+
+        let trace = board.trace_cell(1, 1, &client_key);
+        assert_eq!(trace.sum(), 6);
+        assert!(trace.next);
+        assert_eq!(trace.rule, highlife);
+        assert_eq!(first_mismatch(&[trace]), None);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let client_key = set_up_keys();
+
+        let dimensions = (6, 7);
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 5);
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key)
+            .with_rule(highlife)
+            .with_boundary(BoundaryCondition::Fixed);
+        board.update();
+        let path = std::env::temp_dir().join(format!("board-{}.bin", std::process::id()));
+        board.save(&path, "default").unwrap();
+
+        let mut loaded = Board::load(&path, "default").unwrap();
+        assert_eq!(loaded.dimensions(), dimensions);
+        assert_eq!(loaded.generation(), 1);
+        assert_eq!(loaded.rule(), highlife);
+        assert_eq!(loaded.boundary(), BoundaryCondition::Fixed);
+        board.update();
+        loaded.update();
+        assert_boards_eq(
+            &board.decrypt(&client_key),
+            &loaded.decrypt(&client_key),
+            dimensions,
+            "loaded",
+        );
+
+        let err = Board::load(&path, "tfhe-lib").err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(err, FileError::Parameters { .. }));
+
+        // dimensions that do not match the cells, even once they overflow
+        for dimensions in [(6, 8), (usize::MAX / 2 + 22, 2)] {
+            let mut saved = loaded.saved();
+            saved.0 = dimensions;
+            let mut bytes = Vec::new();
+            crate::keys::write_to(&mut bytes, "default", &saved).unwrap();
+            let err = Board::from_bytes(&bytes, "default").err().unwrap();
+            assert!(matches!(err, FileError::Format(_)), "{:?}", dimensions);
+        }
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 8);
+        let path = std::env::temp_dir().join(format!("checkpoint-{}.bin", std::process::id()));
+        encrypt_board(&cells, dimensions.1, &client_key)
+            .save(&path, "default")
+            .unwrap();
+
+        // two runs of two generations, each resuming from the last checkpoint
+        for _ in 0..2 {
+            let mut board = Board::load(&path, "default").unwrap();
+            for _ in 0..2 {
+                board.update();
+                board.save(&path, "default").unwrap();
+            }
+        }
+        let resumed = Board::load(&path, "default").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+        for _ in 0..4 {
+            board.update();
+        }
+        assert_eq!(resumed.generation(), 4);
+        assert_boards_eq(
+            &board.decrypt(&client_key),
+            &resumed.decrypt(&client_key),
+            dimensions,
+            "resumed",
+        );
+    }
+
+    #[test]
+    fn test_encrypted_population_count() {
+        let client_key = set_up_keys();
+
+        let soup = generate(&BoardProfile::Soup { density: 0.4 }, (4, 5), 3);
+        for (cells, n_cols) in [(soup, 5), (vec![false; 9], 3), (vec![true; 9], 3)] {
+            let board = encrypt_board(&cells, n_cols, &client_key);
+
+            let count = board.encrypted_population_count();
+
+            let expected = cells.iter().filter(|alive| **alive).count();
+            assert_eq!(decrypt_number(&count, &client_key), expected);
+        }
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        let glider = tile(&GLIDER, 3, dimensions, (5, 6)).unwrap();
+        let soup = generate(&BoardProfile::Soup { density: 0.1 }, dimensions, 7);
+        for (cells, max_distance) in [(glider, 3), (soup, 2), (vec![false; 30], 4)] {
+            let board = encrypt_board(&cells, dimensions.1, &client_key);
+
+            let distances: Vec<usize> = board
+                .distance_field(max_distance)
+                .iter()
+                .map(|bits| decrypt_number(bits, &client_key))
+                .collect();
+
+            assert_eq!(distances, distance_field(&cells, dimensions, max_distance));
+        }
+
+        // the distances do not go across the edges of the other boundaries
+        let mut cells = vec![false; 30];
+        cells[7] = true;
+        for boundary in [BoundaryCondition::Fixed, BoundaryCondition::Mirror] {
+            let board = encrypt_board(&cells, dimensions.1, &client_key).with_boundary(boundary);
+            let distances: Vec<usize> = board
+                .distance_field(4)
+                .iter()
+                .map(|bits| decrypt_number(bits, &client_key))
+                .collect();
+            let expected = distance_field_with_boundary(&cells, dimensions, 4, boundary);
+            assert_eq!(distances, expected, "{}", boundary);
+            assert_ne!(expected, distance_field(&cells, dimensions, 4));
+        }
+    }
+
+    #[test]
+    fn test_scroll_in_row() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        let mut cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 8);
+        let rows = generate(&BoardProfile::Soup { density: 0.5 }, (4, 6), 9);
+        for edge in [Edge::Top, Edge::Bottom] {
+            let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+            for row in rows.chunks(6) {
+                let encrypted_row = row
+                    .iter()
+                    .map(|&alive| FheBool::encrypt(alive, &client_key))
+                    .collect();
+                board.scroll_in_row(encrypted_row, edge).unwrap();
+                board.update();
+
+                cells = scroll_in_row(&cells, dimensions, row, edge).unwrap();
+                cells = next_generation(&cells, dimensions);
+                assert_boards_eq(&cells, &board.decrypt(&client_key), dimensions, "scrolled");
+            }
+            assert_eq!(board.generation, 4);
+        }
+
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+        let short_row = vec![FheBool::encrypt(true, &client_key)];
+        assert_eq!(
+            board.scroll_in_row(short_row, Edge::Top),
+            Err(RowLengthMismatch {
+                expected: 6,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_lifespan_tracker() {
+        let client_key = set_up_keys();
+
+        // a glider heading for some ash: a blinker and a block
+        let dimensions = (10, 10);
+        let mut cells = tile(&GLIDER, 3, dimensions, (10, 10)).unwrap();
+        for k in [45, 46, 47, 77, 78, 87, 88] {
+            cells[k] = true;
+        }
+        let mut board = encrypt_board(&cells, dimensions.1, &client_key);
+        board.update();
+        board.attach_lifespan_tracker(5);
+
+        let mut frames = vec![board.decrypt(&client_key)];
+        for _ in 0..6 {
+            board.update();
+            frames.push(board.decrypt(&client_key));
+        }
+
+        let lifespans: Vec<usize> = board
+            .lifespans()
+            .unwrap()
+            .iter()
+            .map(|bits| decrypt_number(bits, &client_key))
+            .collect();
+        let expected = plaintext_lifespans(&frames, 5);
+        assert_eq!(lifespans, expected);
+        assert!(expected.contains(&1));
+        assert!(expected.contains(&5));
+        assert!(expected.contains(&0));
+    }
+
+    #[test]
+    fn test_rule_evidence() {
+        let client_key = set_up_keys();
+
+        let dimensions = (5, 6);
+        let before = generate(&BoardProfile::Soup { density: 0.5 }, dimensions, 4);
+        // a rule that is not the board's, B1/S: cells live alone
+        let after: Vec<bool> = neighbour_counts(&before, dimensions)
+            .into_iter()
+            .zip(&before)
+            .map(|(count, &alive)| count == 1 && !alive)
+            .collect();
+        let board = encrypt_board(&before, dimensions.1, &client_key);
+
+        let evidence = board
+            .rule_evidence(&encrypt_board(&after, dimensions.1, &client_key))
+            .decrypt(&client_key);
+
+        assert_eq!(
+            evidence,
+            RuleEvidence::from_frames(&before, &after, dimensions, BoundaryCondition::Toroidal)
+        );
+        assert!(evidence.infer().birth.iter().all(|&count| count == 1));
+        assert!(evidence.infer().survival.is_empty());
+    }
+
+    #[test]
+    fn test_rule_evidence_full_neighbourhood() {
+        let client_key = set_up_keys();
+
+        // on a full 3x3 torus every cell has 8 neighbours, counted apart from 0
+        let mut board = encrypt_board(&[true; 9], 3, &client_key);
+        let before = board.clone();
+        board.update();
+
+        let evidence = before.rule_evidence(&board).decrypt(&client_key);
+        assert_eq!(evidence.to_dead[1][8], 9);
+        assert_eq!(evidence.to_dead[1][0], 0);
+    }
+
+    #[test]
+    fn test_from_named_pattern() {
+        let (client_key, server_key) = default_keys();
+
+        let path = std::env::temp_dir().join(format!("patterns-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"patterns": {
+                "glider": [[0, 1], [1, 2], [2, 0], [2, 1], [2, 2]],
+                "blinker": [[2, 1], [2, 2], [2, 3]]
+            }}"#,
+        )
+        .unwrap();
+
+        let glider =
+            Board::from_named_pattern(&path, "glider", 5, 6, &client_key, server_key.clone())
+                .unwrap();
+        let blinker =
+            Board::from_named_pattern(&path, "blinker", 5, 5, &client_key, server_key.clone())
+                .unwrap();
+        let missing = Board::from_named_pattern(&path, "toad", 5, 5, &client_key, server_key);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(glider.dimensions, (5, 6));
+        assert_eq!(
+            live_coordinates(&glider.decrypt(&client_key), 6),
+            vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+        assert_eq!(
+            live_coordinates(&blinker.decrypt(&client_key), 5),
+            vec![(2, 1), (2, 2), (2, 3)]
+        );
+        assert!(missing.is_err());
+    }
+
+    /// Runs a catalog oscillator for its period and checks that it is back to
+    /// its initial phase.
+    fn assert_returns_to_initial_phase(name: &str, margin: usize) {
+        let client_key = set_up_keys();
+
+        let catalog_pattern = catalog_pattern(name).unwrap();
+        let (cells, pattern_cols) = catalog_pattern.cells();
+        let (initial, dimensions) = centered(&cells, pattern_cols, margin);
+        let mut board = encrypt_board(&initial, dimensions.1, &client_key);
+
+        for _ in 0..catalog_pattern.period {
+            board.update();
+        }
+
+        assert_boards_eq(
+            &initial,
+            &board.decrypt(&client_key),
+            dimensions,
+            &format!("{} after {} generations", name, catalog_pattern.period),
+        );
+    }
+
+    #[test]
+    fn test_catalog_period_2_oscillators() {
+        for name in ["blinker", "toad", "beacon"] {
+            assert_returns_to_initial_phase(name, 2);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs 3 generations of a 17x17 board"]
+    fn test_catalog_pulsar() {
+        assert_returns_to_initial_phase("pulsar", 2);
+    }
+
+    #[test]
+    #[ignore = "runs 15 generations of an 11x18 board"]
+    fn test_catalog_pentadecathlon() {
+        assert_returns_to_initial_phase("pentadecathlon", CATALOG_MARGIN);
+    }
+
+    fn plaintext_hash(input: &[u8], rounds: usize) -> Vec<u8> {
+        let (mut cells, n_cols) = hash_cells(input);
+        let dimensions = (cells.len() / n_cols, n_cols);
+        for _ in 0..rounds {
+            cells = next_generation(&cells, dimensions);
+        }
+        pack_cells(&cells)
+    }
+
+    #[test]
+    fn test_cellular_hash() {
+        let (client_key, server_key) = default_keys();
+
+        let digest = Board::cellular_hash(b"fhe", 2, &client_key, server_key.clone());
+
+        assert_eq!(
+            digest,
+            Board::cellular_hash(b"fhe", 2, &client_key, server_key)
+        );
+        assert_eq!(digest, plaintext_hash(b"fhe", 2));
+        assert_eq!(pack_cells(&hash_cells(b"a").0), vec![b'a', 0, 0]);
+    }
+
+    #[test]
+    fn test_cellular_hash_avalanche() {
+        let input = b"game of life";
+        let digest = plaintext_hash(input, 16);
+
+        let distances: Vec<u32> = (0..8 * input.len())
+            .map(|bit| {
+                let mut flipped = input.to_vec();
+                flipped[bit / 8] ^= 1 << (bit % 8);
+                digest
+                    .iter()
+                    .zip(plaintext_hash(&flipped, 16))
+                    .map(|(a, b)| (a ^ b).count_ones())
+                    .sum()
+            })
+            .collect();
+
+        // a flipped cell can die without effect, but not often, and
+        // otherwise the change spreads to a good part of the board: an ideal
+        // hash flips half of the output bits, ask for half of that
+        let unchanged = distances.iter().filter(|d| **d == 0).count();
+        assert!(unchanged * 10 < distances.len());
+        let output_bits = 8 * digest.len();
+        let mean = distances.iter().sum::<u32>() as f64 / distances.len() as f64;
+        assert!(mean > 0.25 * output_bits as f64, "mean distance {}", mean);
+    }
+
+    #[test]
+    fn test_live_coordinates() {
+        #[rustfmt::skip]
+        let cells = [
+            false, true, false,
+            false, false, true,
+            true, true, true,
+        ];
+
+        assert_eq!(
+            live_coordinates(&cells, 3),
+            vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! User provided libraries of named patterns, stored as JSON:
+//!
+//! ```json
+//! {"patterns": {"glider": [[0, 1], [1, 2], [2, 0], [2, 1], [2, 2]]}}
+//! ```
+//!
+//! Every pattern is the list of the `[row, col]` coordinates of its live
+//! cells, on a board whose top left corner is `[0, 0]`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum LibraryError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnknownPattern(String),
+    /// A live cell lies outside of the board.
+    OutOfBounds {
+        cell: (usize, usize),
+        dimensions: (usize, usize),
+    },
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the pattern library: {}", err),
+            Self::Json(err) => write!(f, "invalid pattern library: {}", err),
+            Self::UnknownPattern(name) => write!(f, "no pattern named '{}' in the library", name),
+            Self::OutOfBounds { cell, dimensions } => write!(
+                f,
+                "cell ({}, {}) is outside of a {}x{} board",
+                cell.0, cell.1, dimensions.0, dimensions.1
+            ),
+        }
+    }
+}
+
+impl Error for LibraryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LibraryError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LibraryError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Library {
+    patterns: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl Library {
+    pub fn load(path: &Path) -> Result<Self, LibraryError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, LibraryError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The cells of a board of the given dimensions holding the named
+    /// pattern.
+    pub fn board(&self, name: &str, dimensions: (usize, usize)) -> Result<Vec<bool>, LibraryError> {
+        let (n_rows, n_cols) = dimensions;
+        let coordinates = self
+            .patterns
+            .get(name)
+            .ok_or_else(|| LibraryError::UnknownPattern(name.to_string()))?;
+
+        let mut cells = vec![false; n_rows * n_cols];
+        for &(row, col) in coordinates {
+            if row >= n_rows || col >= n_cols {
+                return Err(LibraryError::OutOfBounds {
+                    cell: (row, col),
+                    dimensions,
+                });
+            }
+            cells[row * n_cols + col] = true;
+        }
+        Ok(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live_coordinates;
+
+    const LIBRARY: &str = r#"{
+        "patterns": {
+            "glider": [[0, 1], [1, 2], [2, 0], [2, 1], [2, 2]],
+            "blinker": [[1, 0], [1, 1], [1, 2]]
+        }
+    }"#;
+
+    #[test]
+    fn test_load_patterns() {
+        let library = Library::from_json(LIBRARY).unwrap();
+
+        let glider = library.board("glider", (4, 5)).unwrap();
+        assert_eq!(
+            live_coordinates(&glider, 5),
+            vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+        let blinker = library.board("blinker", (3, 3)).unwrap();
+        assert_eq!(live_coordinates(&blinker, 3), vec![(1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_library_errors() {
+        let library = Library::from_json(LIBRARY).unwrap();
+
+        assert!(matches!(
+            library.board("pulsar", (8, 8)),
+            Err(LibraryError::UnknownPattern(name)) if name == "pulsar"
+        ));
+        assert!(matches!(
+            library.board("glider", (3, 2)),
+            Err(LibraryError::OutOfBounds {
+                cell: (1, 2),
+                dimensions: (3, 2)
+            })
+        ));
+        assert!(matches!(
+            Library::from_json(r#"{"patterns": {"glider": [[0, 1]"#),
+            Err(LibraryError::Json(_))
+        ));
+        assert!(matches!(
+            Library::load(Path::new("/nonexistent/library.json")),
+            Err(LibraryError::Io(_))
+        ));
+    }
+}
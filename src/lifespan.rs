@@ -0,0 +1,130 @@
+//! Encrypted record of the generation at which every cell first dies, to show
+//! which parts of a board are fragile.
+
+use concrete::FheBool;
+
+/// Per-cell state machine following a board for `max_generations`
+/// generations: a cell that was alive records the first generation at which
+/// it is dead, its value staying 0 until then.
+#[derive(Clone)]
+pub struct LifespanTracker {
+    max_generations: usize,
+    /// Generation of the board when the tracker was attached.
+    start: usize,
+    was_alive: Vec<FheBool>,
+    died: Vec<FheBool>,
+    /// Bits of the generation of the first death, least significant first.
+    death_generation: Vec<Vec<FheBool>>,
+}
+
+impl LifespanTracker {
+    /// Starts tracking `cells`, at `generation` of their board.
+    pub fn new(
+        cells: &[FheBool],
+        generation: usize,
+        max_generations: usize,
+        zero: &FheBool,
+    ) -> Self {
+        assert!(
+            max_generations > 0,
+            "the tracker must run for at least a generation"
+        );
+        let n_bits = (usize::BITS - max_generations.leading_zeros()) as usize;
+        Self {
+            max_generations,
+            start: generation,
+            was_alive: cells.to_vec(),
+            died: vec![zero.clone(); cells.len()],
+            death_generation: vec![vec![zero.clone(); n_bits]; cells.len()],
+        }
+    }
+
+    /// Records the cells of the board at `generation`. Observing the same
+    /// generation again changes nothing, and generations past
+    /// `max_generations` are ignored.
+    ///
+    /// Costs about 5 gates per cell, plus one per set bit of the generation.
+    pub fn observe(&mut self, cells: &[FheBool], generation: usize) {
+        let elapsed = generation.saturating_sub(self.start);
+        if elapsed == 0 || elapsed > self.max_generations {
+            return;
+        }
+        for (k, cell) in cells.iter().enumerate() {
+            let dies_now = &self.was_alive[k] & !(cell | &self.died[k]);
+            for (bit, death_bit) in self.death_generation[k].iter_mut().enumerate() {
+                if elapsed >> bit & 1 == 1 {
+                    *death_bit = &*death_bit | &dies_now;
+                }
+            }
+            self.died[k] = &self.died[k] | dies_now;
+            self.was_alive[k] = &self.was_alive[k] | cell;
+        }
+    }
+
+    /// The generation of the first death of every cell, `max_generations` if
+    /// it was still alive at the end, 0 if it was never alive.
+    pub fn lifespans(&self) -> Vec<Vec<FheBool>> {
+        self.death_generation
+            .iter()
+            .zip(self.was_alive.iter().zip(&self.died))
+            .map(|(death_generation, (was_alive, died))| {
+                let survived = was_alive & !died.clone();
+                death_generation
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, death_bit)| {
+                        if self.max_generations >> bit & 1 == 1 {
+                            death_bit | &survived
+                        } else {
+                            death_bit.clone()
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Plaintext model of `LifespanTracker::lifespans`, `frames[g]` being the
+/// board `g` generations after the tracker was attached.
+pub fn plaintext_lifespans(frames: &[Vec<bool>], max_generations: usize) -> Vec<usize> {
+    let mut was_alive = frames[0].clone();
+    let mut lifespans = vec![None; was_alive.len()];
+    for (generation, frame) in frames.iter().enumerate().take(max_generations + 1).skip(1) {
+        for (k, &alive) in frame.iter().enumerate() {
+            if was_alive[k] && !alive && lifespans[k].is_none() {
+                lifespans[k] = Some(generation);
+            }
+            was_alive[k] |= alive;
+        }
+    }
+    lifespans
+        .into_iter()
+        .zip(was_alive)
+        .map(|(lifespan, was_alive)| match lifespan {
+            Some(generation) => generation,
+            None if was_alive => max_generations,
+            None => 0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plaintext_lifespans() {
+        let frames = [
+            vec![true, false, false, true],
+            vec![false, true, false, true],
+            vec![true, false, false, true],
+            vec![false, false, false, false],
+        ];
+
+        // the first cell dies at generation 1 and the second at 2, the third
+        // is never alive and the last survives the 2 tracked generations
+        assert_eq!(plaintext_lifespans(&frames, 2), vec![1, 2, 0, 2]);
+        assert_eq!(plaintext_lifespans(&frames, 3), vec![1, 2, 0, 3]);
+    }
+}
@@ -1,204 +1,2100 @@
 use concrete::prelude::*;
-use concrete::{generate_keys, set_server_key, ConfigBuilder, FheBool};
-use std::ops::AddAssign;
+use concrete::{generate_keys, set_server_key, ClientKey, ConfigBuilder, FheBool, ServerKey};
+#[cfg(feature = "phase-timer")]
+use homomorphic_game_of_life_2::phase_timer;
+use homomorphic_game_of_life_2::{
+    analysis, benchgen, checkpoint, decrypt_cells, decrypt_number, estimate, inference, keys,
+    library, lifespan, live_coordinates, noise, patch, pattern, patternfile, plain, quadrant,
+    reference, resample, rule, seeds, shadow, storage, stream, sum_width, trace, Board,
+    BoundaryCondition,
+};
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Clone)]
-struct Accumulator(FheBool, FheBool, FheBool);
+mod audit;
+mod completion;
+mod explain;
+mod pacing;
+mod split;
+mod stats;
 
-impl From<(FheBool, FheBool, FheBool)> for Accumulator {
-    fn from(inner: (FheBool, FheBool, FheBool)) -> Self {
-        Self(inner.0, inner.1, inner.2)
+fn print_grid(cells: &[bool], n_cols: usize) {
+    for row in cells.chunks(n_cols) {
+        println!();
+        for alive in row {
+            if *alive {
+                print!("█");
+            } else {
+                print!("░");
+            }
+        }
+    }
+    println!();
+}
+
+/// Prints a number for every cell, right aligned.
+fn print_numbers(numbers: &[usize], n_cols: usize) {
+    let width = numbers.iter().max().map_or(1, |max| max.to_string().len());
+    for row in numbers.chunks(n_cols) {
+        let row: Vec<String> = row.iter().map(|n| format!("{:>width$}", n)).collect();
+        println!("{}", row.join(" "));
     }
 }
 
-impl AddAssign<&FheBool> for Accumulator {
-    // ^ -> xor
-    // & -> and
-    fn add_assign(&mut self, rhs: &FheBool) {
-        let c1 =  &self.0 ^ rhs; 
-        let first_carry = rhs & &self.0;
+/// Compares a decrypted frame with the shadow when a comparison is due,
+/// warning about any divergence.
+fn check_shadow(shadow: Option<&shadow::Shadow>, cells: &[bool]) {
+    if let Some(shadow) = shadow.filter(|shadow| shadow.is_due()) {
+        if let Err(divergence) = shadow.compare(cells) {
+            eprintln!("{}", divergence);
+        }
+    }
+}
 
-        let second_carry = &first_carry & &self.1;
-        let c2 = &self.1 ^ first_carry;
+fn print_coords(cells: &[bool], n_cols: usize) {
+    let coords = live_coordinates(cells, n_cols);
+    println!("live cells ({}): {:?}", coords.len(), coords);
+}
+
+/// What to do once the live cells fill more than the `--full-threshold`
+/// fraction of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnFull {
+    /// Only report the generation at the end of the run.
+    Continue,
+    /// Also print a warning when it happens.
+    Warn,
+    /// End the run.
+    Stop,
+}
 
-        self.2 = &self.2 ^ second_carry;
-        self.1 = c2;
-        self.0 = c1;
+impl OnFull {
+    const NAMES: [&'static str; 3] = ["continue", "warn", "stop"];
+}
+
+impl std::str::FromStr for OnFull {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "continue" => Ok(Self::Continue),
+            "warn" => Ok(Self::Warn),
+            "stop" => Ok(Self::Stop),
+            _ => Err(format!("unknown policy '{}'", policy)),
+        }
     }
 }
 
-/// Rules are
+/// Options that only know Conway's rule.
+const CONWAY_ONLY: [&str; 2] = ["--shadow", "--hash"];
+
+/// Options that only play toroidal boards.
+const TOROIDAL_ONLY: [&str; 2] = ["--shadow", "--hash"];
+
+/// Flags decrypted every generation by `--detect-still`: whether the board
+/// equals the previous generation, and the one before.
+const STILL_FLAGS_PER_FRAME: usize = 2;
+
+/// Dimensions of the boards when `--size` is not given.
+const DEFAULT_SIZE: (usize, usize) = (16, 16);
+
+/// Name of the parameters the keys are generated with when `--params` is
+/// not given, recorded in key files.
+const DEFAULT_PARAMETERS: &str = "default";
+
+/// Every option of the command line, for suggestions and completion.
+const OPTIONS: &[&str] = &[
+    "--grid",
+    "--coords",
+    "--neighbour-counts",
+    "--population",
+    "--noisy-population",
+    "--analysis",
+    "--on-full",
+    "--full-threshold",
+    "--downsample",
+    "--upsample",
+    "--tile",
+    "--board-profile",
+    "--library",
+    "--pattern",
+    "--catalog",
+    "--pattern-file",
+    "--offset",
+    "--size",
+    "--seed",
+    "--seed-manifest",
+    "--generations",
+    "--warmup",
+    "--checksum",
+    "--estimate-population",
+    "--trace",
+    "--trace-cell",
+    "--batch",
+    "--detect-still",
+    "--stream-in",
+    "--stream-edge",
+    "--time-limit",
+    "--fps",
+    "--shadow",
+    "--hash",
+    "--entropy",
+    "--distance",
+    "--infer-rule",
+    "--lifespan",
+    "--audit",
+    "--max-leak-bits",
+    "--extract",
+    #[cfg(feature = "latex-export")]
+    "--latex",
+    #[cfg(feature = "phase-timer")]
+    "--phase-timer",
+    "--plaintext",
+    "--keys",
+    "--regen-keys",
+    "--params",
+    "--load-board",
+    "--save-board",
+    "--checkpoint",
+    "--full-checkpoint-every",
+    "--make-patch",
+    "--toggle",
+    "--stamp",
+    "--apply-patch",
+    "--export-rle",
+    "--rule",
+    "--boundary",
+    "--quadrants",
+    "--completions",
+    "--explain",
+    "--explain-json",
+];
+
+/// Command line options.
+///
+/// `--coords` prints the live-cell coordinates instead of the grid, add
+/// `--grid` to get both. `--tile <stride>` starts from a board of
+/// `2 * stride` rows and columns tiled with gliders.
+///
+/// `--generations <n>` sets how many timed generations are run and
+/// `--warmup <n>` how many are run before them, untimed.
+///
+/// `--library <path> --pattern <name>` starts from a named pattern of a JSON
+/// library, on a board of `--size` cells.
+///
+/// `--catalog <name>` starts from a pattern of `pattern::CATALOG` with
+/// `pattern::CATALOG_MARGIN` dead cells around it, and reports its known period.
+///
+/// `--pattern-file <path>` starts from a `.rle` or `.cells` pattern (see
+/// `patternfile`) placed in the middle of a board of `--size` cells, or with
+/// its top left corner at `--offset <row>,<col>`. Without `--size`, the board
+/// leaves `pattern::CATALOG_MARGIN` dead cells around the pattern.
+///
+/// `--board-profile <profile>` starts from a generated benchmark board (see
+/// `benchgen::BoardProfile`) of `--size <rows>x<cols>` cells, 16x16 by
+/// default, reproducible with `--seed <n>`.
+///
+/// `--seed-manifest <path>` records the seeds of the run in a JSON manifest
+/// (see `seeds`), or replays those of the manifest when it exists. The keys
+/// and the noise of `--noisy-population` come from the OS and are drawn
+/// anew, with a warning.
+///
+/// `--downsample <factor>` shows the grid shrunk by that factor, a block
+/// being alive when any of its cells is, and `--upsample <factor>` enlarges it.
+///
+/// `--population` counts the live cells homomorphically and prints only the
+/// decrypted count. `--noisy-population <scale>` adds noise of that scale, at
+/// most `noise::MAX_SCALE`, to the count before it is decrypted, from a pool
+/// encrypted before the run (see `noise::NoisyRelease`). The exact count of
+/// `--on-full` cannot be combined with it.
+///
+/// `--analysis` prints the entropy, the connected components and the known
+/// small patterns of every generation (see `analysis::FrameAnalysis`).
+///
+/// `--on-full <continue|warn|stop>` watches the homomorphic population count
+/// for the board filling more than `--full-threshold <fraction>` of its cells,
+/// 0.8 by default.
+///
+/// `--neighbour-counts` also prints the neighbour count heat map.
+///
+/// `--batch <n>` runs `n` generations per call to the server and only shows
+/// every `n`th one, and `--time-limit <seconds>` stops the run, even in the
+/// middle of a batch, once it has lasted that long.
+///
+/// `--fps <n>` waits between generations so that they are shown at most `n`
+/// times per second, for demonstrations, and at least once a day; the update
+/// times do not include the waits, but the elapsed time does.
+///
+/// `--detect-still` compares every new generation with the previous two
+/// homomorphically, decrypting only the results, and ends the run once the
+/// board stops changing or repeats every 2 generations, like a blinker.
+///
+/// `--stream-in <path>` reads rows drawn with `#` and `.` from the file and
+/// scrolls one of them into the board after every generation, from the top
+/// or from `--stream-edge <top|bottom>` (see `Board::scroll_in_row`).
+/// Saved boards do not record how many rows have entered, so it cannot be
+/// combined with `--checkpoint` or `--load-board`.
+///
+/// `--shadow <n>` advances a plaintext copy of the board along the encrypted
+/// one and warns every `n` generations if they diverge.
+///
+/// `--trace` decrypts the intermediate values of every cell update and
+/// prints them for the first cell that differs from the plaintext game.
+///
+/// `--trace-cell <row>,<col>` decrypts the intermediate values of the update
+/// of that cell only, every generation, and prints them as a table at the end
+/// of the run (see `Board::trace_cell`).
+///
+/// `--checksum` prints a checksum of every new generation, to compare runs.
+///
+/// `--estimate-population <k>` decrypts `k` cells picked at random in every
+/// row as soon as the row of the new generation is computed, and prints an
+/// estimate of its population with 95% bounds while the rest is computed
+/// (see `estimate`). The estimate is exact once `k` covers the rows.
+///
+/// `--hash <text>` prints the `cellular_hash` of the text after
+/// `--generations` rounds instead of running a board.
+///
+/// `--entropy <window>` estimates the topological entropy of the final board
+/// over that many more generations.
 ///
-/// a live cell will survive if it has 2 or 3 neighbours alive
-/// a dead cell will birth if it has 3 neighbours alive
-fn is_alive(cell: &FheBool, neighbours: &[&FheBool], mut accumulator: Accumulator) -> FheBool {
-    for neighbour in neighbours {
-        accumulator += *neighbour;
+/// `--distance <max>` prints the distance from every cell of the final board
+/// to the nearest live cell, computed homomorphically and capped at `max`.
+///
+/// `--infer-rule` runs one more generation and infers the rule from it,
+/// decrypting only the counts of the transitions (see `Board::rule_evidence`).
+///
+/// `--lifespan` tracks homomorphically the generation at which every cell
+/// first dies and prints the decrypted map at the end of the run.
+///
+/// `--extract <row>,<col>,<half size>` prints the neighbourhood of a cell at
+/// the end of the run, a square at most as wide as the board.
+///
+/// `--audit` lists every decryption made during the run, and
+/// `--max-leak-bits <n>` refuses, before generating the keys, to start a run
+/// whose options would decrypt more than `n` bits (see `audit`). The `--hash`
+/// mode is not audited.
+///
+/// `--plaintext` runs the game on plaintext cells, without generating keys,
+/// to preview a board quickly (see `plain::PackedBoard`). The options that
+/// need the encrypted board cannot be combined with it.
+///
+/// `--keys <path>` reads the keys from the file instead of generating them,
+/// or generates them and writes them there if it does not exist or cannot be
+/// used (see `keys::load_or_generate`). `--regen-keys` replaces them anyway,
+/// and is needed to replace a file of another version of the format.
+///
+/// `--params <default|tfhe-lib>` generates the keys with another parameter
+/// set of the boolean API (see `keys::parameters`). Key and board files
+/// record it, and are only read back with the same `--params`. The `bench`
+/// binary compares the update times of the parameter sets.
+///
+/// `--save-board <path>` writes the encrypted board at the end of the run,
+/// and `--load-board <path>` starts from such a board, at the generation it
+/// was saved at (see `Board::save`). Loading needs the keys it was encrypted
+/// with, from `--keys`, and the `--rule` and `--boundary` it was played with.
+///
+/// `--checkpoint <path>` writes the encrypted board there after every
+/// generation, replacing the file only once the new one is whole, so that an
+/// interrupted run can be resumed with `--load-board <path>`. It also needs
+/// the keys from `--keys`. With `--full-checkpoint-every <n>`, the path is a
+/// directory, or a `file://` storage URL (see `storage`), holding a chain of
+/// checkpoints (see `checkpoint`): the board is written whole every `n`
+/// generations, and in between only the cells that changed since the last
+/// checkpoint, which the client finds by decrypting whether each cell
+/// changed. `--load-board` resumes from such a directory or URL too.
+///
+/// The commands `encrypt`, `run` and `decrypt`, given as the first argument,
+/// split a run between the client and a server that only holds the server
+/// key (see `split`): `encrypt` writes the board and the server key, `run`
+/// updates the board and can be run again to carry on, and `decrypt` shows
+/// it with the keys.
+///
+/// `--make-patch <path>` edits the decrypted board with `--toggle <row>,<col>`
+/// and `--stamp <name>@<row>,<col>`, which writes a pattern of the catalog
+/// with its top left corner there, and writes the edited cells, encrypted,
+/// to a patch file (see `patch`) instead of running the game.
+/// `--apply-patch <path>` replaces the cells of such a patch before the first
+/// generation. Both need the keys from `--keys`, and the patch the generation
+/// and dimensions of the board it was made for, usually from `--load-board`.
+///
+/// `--rule <B../S..>` plays another rule than Conway's B3/S23, such as
+/// HighLife, B36/S23 (see `rule::Rule`). `--shadow` and `--hash` only know
+/// Conway's rule.
+///
+/// `--boundary <toroidal|fixed|mirror>` chooses what lies beyond the edges of
+/// the board: the opposite edge, by default, dead cells, or the reflection of
+/// the edge (see `BoundaryCondition`). `periodic` and `dead` are other names
+/// for the first two. `--shadow` and `--hash` only play toroidal boards.
+///
+/// `--quadrants` plays the four quadrants of a board with fixed boundaries
+/// as independent boards, stitched together to be shown (see
+/// `quadrant::QuadrantBoard`). The initial board must keep its live cells
+/// far enough from the edges between the quadrants for them not to meet
+/// during the run, and the rule cannot have dead cells born without live
+/// neighbours (B0).
+///
+/// `--export-rle <path>` writes the final board as a `.rle` pattern, which
+/// `--pattern-file` reads back (see `patternfile::to_rle`).
+///
+/// `--completions bash` prints a bash completion script and exits. Mistyped
+/// options and `--catalog` names get the closest valid one suggested.
+///
+/// `--explain <topic>` explains a parameter set, rule, boundary condition,
+/// board profile, kind of leak, stop condition or feature (see `explain`),
+/// or all of them with `all`, and exits. `--explain-json <topic>` prints the
+/// same as JSON.
+///
+/// With the `latex-export` feature, `--latex <path>` writes the final board
+/// as a TikZ picture.
+///
+/// With the `phase-timer` feature, `--phase-timer <n>` times the phases of
+/// the update of every `n`th cell (see `phase_timer`), and prints the mean
+/// time of each phase per cell after every generation and over the run.
+struct Options {
+    show_grid: bool,
+    show_coords: bool,
+    show_neighbour_counts: bool,
+    show_population: bool,
+    noise_scale: Option<f64>,
+    show_analysis: bool,
+    on_full: Option<OnFull>,
+    full_threshold: f64,
+    downsample: Option<usize>,
+    upsample: Option<usize>,
+    tile_stride: Option<usize>,
+    board_profile: Option<benchgen::BoardProfile>,
+    named_pattern: Option<(String, String)>,
+    catalog: Option<&'static pattern::CatalogPattern>,
+    pattern_file: Option<String>,
+    offset: Option<(usize, usize)>,
+    size: Option<(usize, usize)>,
+    seed: u64,
+    seed_manifest: Option<String>,
+    generations: usize,
+    warmup: usize,
+    extract: Option<(usize, usize, usize)>,
+    checksum: bool,
+    estimate_population: Option<usize>,
+    trace: bool,
+    trace_cell: Option<(usize, usize)>,
+    batch: Option<usize>,
+    detect_still: bool,
+    stream_in: Option<String>,
+    stream_edge: stream::Edge,
+    time_limit: Option<f64>,
+    fps: Option<f64>,
+    shadow_every: Option<usize>,
+    hash_input: Option<String>,
+    entropy_window: Option<usize>,
+    distance_field: Option<usize>,
+    infer_rule: bool,
+    lifespan: bool,
+    audit: bool,
+    max_leak_bits: Option<usize>,
+    #[cfg(feature = "latex-export")]
+    latex_path: Option<String>,
+    #[cfg(feature = "phase-timer")]
+    phase_timer: Option<usize>,
+    plaintext: bool,
+    key_path: Option<String>,
+    regen_keys: bool,
+    parameters: String,
+    load_board: Option<String>,
+    save_board: Option<String>,
+    checkpoint: Option<String>,
+    full_checkpoint_every: Option<usize>,
+    make_patch: Option<String>,
+    edits: Vec<patch::Edit>,
+    apply_patch: Option<String>,
+    export_rle: Option<String>,
+    rule: rule::Rule,
+    boundary: BoundaryCondition,
+    quadrants: bool,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let mut show_grid = None;
+        let mut show_coords = false;
+        let mut show_neighbour_counts = false;
+        let mut show_population = false;
+        let mut noise_scale: Option<f64> = None;
+        let mut show_analysis = false;
+        let mut on_full = None;
+        let mut full_threshold = 0.8;
+        let mut downsample = None;
+        let mut upsample = None;
+        let mut tile_stride = None;
+        let mut board_profile = None;
+        let mut library_path = None;
+        let mut pattern_name = None;
+        let mut catalog = None;
+        let mut pattern_file = None;
+        let mut offset = None;
+        let mut size = None;
+        let mut seed = None;
+        let mut seed_manifest = None;
+        let mut generations = 5;
+        let mut warmup = 0;
+        let mut extract = None;
+        let mut checksum = false;
+        let mut estimate_population = None;
+        let mut trace = false;
+        let mut trace_cell = None;
+        let mut batch = None;
+        let mut detect_still = false;
+        let mut stream_in = None;
+        let mut stream_edge = stream::Edge::Top;
+        let mut time_limit = None;
+        let mut fps = None;
+        let mut shadow_every = None;
+        let mut hash_input = None;
+        let mut entropy_window = None;
+        let mut distance_field = None;
+        let mut infer_rule = false;
+        let mut lifespan = false;
+        let mut audit = false;
+        let mut max_leak_bits = None;
+        #[cfg(feature = "latex-export")]
+        let mut latex_path = None;
+        #[cfg(feature = "phase-timer")]
+        let mut phase_timer = None;
+        let mut plaintext = false;
+        let mut key_path = None;
+        let mut regen_keys = false;
+        let mut parameters = DEFAULT_PARAMETERS.to_string();
+        let mut load_board = None;
+        let mut save_board = None;
+        let mut checkpoint = None;
+        let mut full_checkpoint_every = None;
+        let mut make_patch = None;
+        let mut edits = Vec::new();
+        let mut apply_patch = None;
+        let mut export_rle = None;
+        let mut rule = rule::Rule::conway();
+        let mut boundary = BoundaryCondition::Toroidal;
+        let mut quadrants = false;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--grid" => show_grid = Some(true),
+                "--coords" => show_coords = true,
+                "--neighbour-counts" => show_neighbour_counts = true,
+                "--population" => show_population = true,
+                "--noisy-population" => noise_scale = Some(parse_value(&arg, args.next())),
+                "--analysis" => show_analysis = true,
+                "--on-full" => on_full = Some(parse_value(&arg, args.next())),
+                "--full-threshold" => full_threshold = parse_value(&arg, args.next()),
+                "--downsample" => downsample = Some(parse_value(&arg, args.next())),
+                "--upsample" => upsample = Some(parse_value(&arg, args.next())),
+                "--tile" => tile_stride = Some(parse_value(&arg, args.next())),
+                "--board-profile" => board_profile = Some(parse_value(&arg, args.next())),
+                "--library" => library_path = Some(parse_value(&arg, args.next())),
+                "--pattern" => pattern_name = Some(parse_value(&arg, args.next())),
+                "--catalog" => {
+                    let name: String = parse_value(&arg, args.next());
+                    catalog = Some(pattern::catalog_pattern(&name).unwrap_or_else(|| {
+                        exit_with_usage(&completion::with_suggestion(
+                            format!("no pattern named '{}' in the catalog", name),
+                            &name,
+                            pattern::CATALOG.iter().map(|pattern| pattern.name),
+                        ))
+                    }));
+                }
+                "--pattern-file" => pattern_file = Some(parse_value(&arg, args.next())),
+                "--offset" => offset = Some(parse_cell(&arg, args.next())),
+                "--size" => size = Some(parse_size(&arg, args.next())),
+                "--seed" => seed = Some(parse_value(&arg, args.next())),
+                "--seed-manifest" => seed_manifest = Some(parse_value(&arg, args.next())),
+                "--generations" => generations = parse_value(&arg, args.next()),
+                "--warmup" => warmup = parse_value(&arg, args.next()),
+                "--checksum" => checksum = true,
+                "--estimate-population" => {
+                    estimate_population = Some(parse_value(&arg, args.next()))
+                }
+                "--trace" => trace = true,
+                "--trace-cell" => trace_cell = Some(parse_cell(&arg, args.next())),
+                "--batch" => batch = Some(parse_value(&arg, args.next())),
+                "--detect-still" => detect_still = true,
+                "--stream-in" => stream_in = Some(parse_value(&arg, args.next())),
+                "--stream-edge" => stream_edge = parse_value(&arg, args.next()),
+                "--time-limit" => time_limit = Some(parse_value(&arg, args.next())),
+                "--fps" => fps = Some(parse_value(&arg, args.next())),
+                "--shadow" => shadow_every = Some(parse_value(&arg, args.next())),
+                "--hash" => hash_input = Some(parse_value(&arg, args.next())),
+                "--entropy" => entropy_window = Some(parse_value(&arg, args.next())),
+                "--distance" => distance_field = Some(parse_value(&arg, args.next())),
+                "--infer-rule" => infer_rule = true,
+                "--lifespan" => lifespan = true,
+                "--audit" => audit = true,
+                "--max-leak-bits" => max_leak_bits = Some(parse_value(&arg, args.next())),
+                "--extract" => extract = Some(parse_extract(&arg, args.next())),
+                #[cfg(feature = "latex-export")]
+                "--latex" => latex_path = Some(parse_value(&arg, args.next())),
+                #[cfg(feature = "phase-timer")]
+                "--phase-timer" => phase_timer = Some(parse_value(&arg, args.next())),
+                "--plaintext" => plaintext = true,
+                "--keys" => key_path = Some(parse_value(&arg, args.next())),
+                "--regen-keys" => regen_keys = true,
+                "--params" => {
+                    let name: String = parse_value(&arg, args.next());
+                    if let Err(err) = keys::parameters(&name) {
+                        exit_with_usage(&completion::with_suggestion(
+                            err,
+                            &name,
+                            keys::PARAMETER_NAMES,
+                        ));
+                    }
+                    parameters = name;
+                }
+                "--load-board" => load_board = Some(parse_value(&arg, args.next())),
+                "--save-board" => save_board = Some(parse_value(&arg, args.next())),
+                "--checkpoint" => checkpoint = Some(parse_value(&arg, args.next())),
+                "--full-checkpoint-every" => {
+                    full_checkpoint_every = Some(parse_value(&arg, args.next()))
+                }
+                "--make-patch" => make_patch = Some(parse_value(&arg, args.next())),
+                "--toggle" => edits.push(patch::Edit::Toggle(parse_cell(&arg, args.next()))),
+                "--stamp" => edits.push(parse_stamp(&arg, args.next())),
+                "--apply-patch" => apply_patch = Some(parse_value(&arg, args.next())),
+                "--export-rle" => export_rle = Some(parse_value(&arg, args.next())),
+                "--rule" => {
+                    let value: String = parse_value(&arg, args.next());
+                    rule = value
+                        .parse()
+                        .unwrap_or_else(|err: String| exit_with_usage(&err));
+                }
+                "--boundary" => boundary = parse_value(&arg, args.next()),
+                "--quadrants" => quadrants = true,
+                "--completions" => {
+                    let shell: String = parse_value(&arg, args.next());
+                    if shell != "bash" {
+                        exit_with_usage("only bash completions are supported");
+                    }
+                    let catalog_names = pattern::CATALOG.iter().map(|pattern| pattern.name);
+                    let catalog = explain::catalog();
+                    let mut topics: Vec<&str> =
+                        catalog.iter().map(|entry| entry.topic.as_str()).collect();
+                    topics.push("all");
+                    print!(
+                        "{}",
+                        completion::bash_completion(
+                            "homomorphic_game_of_life_2",
+                            OPTIONS,
+                            &[
+                                ("--catalog", catalog_names.collect()),
+                                ("--on-full", OnFull::NAMES.to_vec()),
+                                ("--stream-edge", vec!["top", "bottom"]),
+                                ("--boundary", vec!["toroidal", "fixed", "mirror"]),
+                                ("--params", keys::PARAMETER_NAMES.to_vec()),
+                                ("--completions", vec!["bash"]),
+                                ("--explain", topics.clone()),
+                                ("--explain-json", topics),
+                            ],
+                        )
+                    );
+                    std::process::exit(0);
+                }
+                "--explain" | "--explain-json" => {
+                    let topic: String = parse_value(&arg, args.next());
+                    let explanation = explain::render(&topic, arg == "--explain-json")
+                        .unwrap_or_else(|err| exit_with_usage(&err));
+                    println!("{}", explanation.trim_end());
+                    std::process::exit(0);
+                }
+                other => exit_with_usage(&completion::with_suggestion(
+                    format!("unknown argument: {}", other),
+                    other,
+                    OPTIONS.iter().copied(),
+                )),
+            }
+        }
+
+        if entropy_window == Some(0) {
+            exit_with_usage("--entropy must be at least 1");
+        }
+        if fps.is_some_and(|fps: f64| !fps.is_finite() || fps <= 0.0) {
+            exit_with_usage("--fps must be positive");
+        }
+        if fps.is_some_and(|fps| fps < pacing::MIN_FRAMES_PER_SECOND) {
+            exit_with_usage("--fps must be at least 1/86400, a frame a day");
+        }
+        if distance_field == Some(0) {
+            exit_with_usage("--distance must be at least 1");
+        }
+        if generations == 0 {
+            exit_with_usage("--generations must be at least 1");
+        }
+        if shadow_every == Some(0) {
+            exit_with_usage("--shadow must be at least 1");
+        }
+        if downsample == Some(0) || upsample == Some(0) {
+            exit_with_usage("scaling factors must be at least 1");
+        }
+        if downsample.is_some() && upsample.is_some() {
+            exit_with_usage("--downsample and --upsample cannot be combined");
+        }
+        if !(0.0..=1.0).contains(&full_threshold) {
+            exit_with_usage("--full-threshold must be between 0 and 1");
+        }
+        if let Some(scale) = noise_scale {
+            if !(scale > 0.0 && scale.is_finite()) {
+                exit_with_usage("--noisy-population must be positive");
+            }
+            if scale > noise::MAX_SCALE {
+                exit_with_usage(&format!(
+                    "--noisy-population must be at most {}",
+                    noise::MAX_SCALE
+                ));
+            }
+            if !show_population {
+                exit_with_usage("--noisy-population needs --population");
+            }
+            if on_full.is_some() {
+                exit_with_usage("--noisy-population and --on-full cannot be combined");
+            }
+        }
+        if batch == Some(0) {
+            exit_with_usage("--batch must be at least 1");
+        }
+        if seed.is_some()
+            && seed_manifest
+                .as_ref()
+                .is_some_and(|path: &String| Path::new(path).exists())
+        {
+            exit_with_usage("--seed cannot be combined with an existing --seed-manifest");
+        }
+        if estimate_population == Some(0) {
+            exit_with_usage("--estimate-population must be at least 1");
+        }
+        // the rows are only seen one by one in a plain update
+        if estimate_population.is_some() && (checksum || trace || batch.is_some() || quadrants) {
+            exit_with_usage(
+                "--estimate-population cannot be combined with --checksum, --trace, --batch or \
+                 --quadrants",
+            );
+        }
+        #[cfg(feature = "phase-timer")]
+        if phase_timer == Some(0) {
+            exit_with_usage("--phase-timer must be at least 1");
+        }
+        if stream_in.is_some() && lifespan {
+            exit_with_usage("--stream-in and --lifespan cannot be combined");
+        }
+        if stream_in.is_some() && batch.is_some() {
+            exit_with_usage("--stream-in and --batch cannot be combined");
+        }
+        // a resumed run would stream the rows in again from the first one
+        if stream_in.is_some() && (checkpoint.is_some() || load_board.is_some()) {
+            exit_with_usage("--stream-in cannot be combined with --checkpoint or --load-board");
+        }
+        if regen_keys && key_path.is_none() {
+            exit_with_usage("--regen-keys needs --keys");
+        }
+        // a saved board can only be decrypted with the keys it was encrypted with
+        if load_board.is_some() && (key_path.is_none() || regen_keys) {
+            exit_with_usage(
+                "--load-board needs the saved keys, with --keys and without --regen-keys",
+            );
+        }
+        if checkpoint.is_some() && key_path.is_none() {
+            exit_with_usage("--checkpoint needs --keys, to resume with the same keys");
+        }
+        if full_checkpoint_every.is_some() && checkpoint.is_none() {
+            exit_with_usage("--full-checkpoint-every needs --checkpoint");
+        }
+        if full_checkpoint_every == Some(0) {
+            exit_with_usage("--full-checkpoint-every must be at least 1");
+        }
+        if make_patch.is_some() == edits.is_empty() {
+            exit_with_usage("--make-patch goes with at least one --toggle or --stamp");
+        }
+        // the patched cells can only be decrypted with the keys of the board
+        if (make_patch.is_some() || apply_patch.is_some()) && (key_path.is_none() || regen_keys) {
+            exit_with_usage(
+                "--make-patch and --apply-patch need the saved keys, with --keys and without \
+                 --regen-keys",
+            );
+        }
+        if trace_cell.is_some() && batch.is_some() {
+            exit_with_usage("--trace-cell and --batch cannot be combined");
+        }
+        if detect_still && batch.is_some() {
+            exit_with_usage("--detect-still and --batch cannot be combined");
+        }
+        if [checksum, trace, batch.is_some()]
+            .iter()
+            .filter(|given| **given)
+            .count()
+            > 1
+        {
+            exit_with_usage("--checksum, --trace and --batch cannot be combined");
+        }
+        let encrypted_only = [
+            ("--checksum", checksum),
+            ("--estimate-population", estimate_population.is_some()),
+            ("--trace", trace),
+            ("--trace-cell", trace_cell.is_some()),
+            ("--batch", batch.is_some()),
+            ("--shadow", shadow_every.is_some()),
+            ("--hash", hash_input.is_some()),
+            ("--extract", extract.is_some()),
+            ("--noisy-population", noise_scale.is_some()),
+            ("--audit", audit),
+            ("--max-leak-bits", max_leak_bits.is_some()),
+            ("--keys", key_path.is_some()),
+            ("--params", parameters != DEFAULT_PARAMETERS),
+            ("--load-board", load_board.is_some()),
+            ("--save-board", save_board.is_some()),
+            ("--checkpoint", checkpoint.is_some()),
+            ("--make-patch", make_patch.is_some()),
+            ("--apply-patch", apply_patch.is_some()),
+            #[cfg(feature = "latex-export")]
+            ("--latex", latex_path.is_some()),
+            #[cfg(feature = "phase-timer")]
+            ("--phase-timer", phase_timer.is_some()),
+        ];
+        if let Some((option, _)) = encrypted_only.iter().find(|(_, given)| *given && plaintext) {
+            exit_with_usage(&format!("{} cannot be combined with --plaintext", option));
+        }
+        let mut conway_only = CONWAY_ONLY
+            .into_iter()
+            .zip([shadow_every.is_some(), hash_input.is_some()]);
+        if rule != rule::Rule::conway() {
+            if let Some((option, _)) = conway_only.find(|(_, given)| *given) {
+                exit_with_usage(&format!("{} cannot be combined with --rule", option));
+            }
+        }
+        let mut toroidal_only = TOROIDAL_ONLY
+            .into_iter()
+            .zip([shadow_every.is_some(), hash_input.is_some()]);
+        if boundary != BoundaryCondition::Toroidal {
+            if let Some((option, _)) = toroidal_only.find(|(_, given)| *given) {
+                exit_with_usage(&format!(
+                    "{} cannot be combined with --boundary {}",
+                    option, boundary
+                ));
+            }
+        }
+        if quadrants {
+            if boundary != BoundaryCondition::Fixed {
+                exit_with_usage("--quadrants needs --boundary fixed");
+            }
+            // dead cells would be born in the moats, see quadrant
+            if rule.birth & 1 != 0 {
+                exit_with_usage("--quadrants cannot be combined with a --rule with B0");
+            }
+            // the moats are checked on the initial cells, and the board
+            // only changes through the updates of the quadrants
+            let whole_board_only = [
+                ("--library", library_path.is_some()),
+                ("--load-board", load_board.is_some()),
+                ("--stream-in", stream_in.is_some()),
+                ("--trace", trace),
+                ("--batch", batch.is_some()),
+                ("--checksum", checksum),
+                ("--lifespan", lifespan),
+                ("--apply-patch", apply_patch.is_some()),
+            ];
+            if let Some((option, _)) = whole_board_only.iter().find(|(_, given)| *given) {
+                exit_with_usage(&format!("{} cannot be combined with --quadrants", option));
+            }
+        }
+        let named_pattern = match (library_path, pattern_name) {
+            (Some(path), Some(name)) => Some((path, name)),
+            (None, None) => None,
+            _ => exit_with_usage("--library and --pattern go together"),
+        };
+        let sources = [
+            tile_stride.is_some(),
+            board_profile.is_some(),
+            named_pattern.is_some(),
+            catalog.is_some(),
+            pattern_file.is_some(),
+            load_board.is_some(),
+        ];
+        if sources.iter().filter(|given| **given).count() > 1 {
+            exit_with_usage(
+                "--tile, --board-profile, --pattern, --catalog, --pattern-file and --load-board \
+                 cannot be combined",
+            );
+        }
+        if offset.is_some() && pattern_file.is_none() {
+            exit_with_usage("--offset needs --pattern-file");
+        }
+
+        Self {
+            show_grid: show_grid.unwrap_or(!show_coords),
+            show_coords,
+            show_neighbour_counts,
+            show_population,
+            noise_scale,
+            show_analysis,
+            on_full,
+            full_threshold,
+            downsample,
+            upsample,
+            tile_stride,
+            board_profile,
+            named_pattern,
+            catalog,
+            pattern_file,
+            offset,
+            size,
+            seed: seed.unwrap_or(0),
+            seed_manifest,
+            generations,
+            warmup,
+            extract,
+            checksum,
+            estimate_population,
+            trace,
+            trace_cell,
+            batch,
+            detect_still,
+            stream_in,
+            stream_edge,
+            time_limit,
+            fps,
+            shadow_every,
+            hash_input,
+            entropy_window,
+            distance_field,
+            infer_rule,
+            lifespan,
+            audit,
+            max_leak_bits,
+            #[cfg(feature = "latex-export")]
+            latex_path,
+            #[cfg(feature = "phase-timer")]
+            phase_timer,
+            plaintext,
+            key_path,
+            regen_keys,
+            parameters,
+            load_board,
+            save_board,
+            checkpoint,
+            full_checkpoint_every,
+            make_patch,
+            edits,
+            apply_patch,
+            export_rle,
+            rule,
+            boundary,
+            quadrants,
+        }
     }
+}
 
-    // check if sum is equal to 2 or 3
-    let sum_is_2_or_3 = !accumulator.2 & accumulator.1;
-    let sum_is_3 = &sum_is_2_or_3 & accumulator.0;
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> T {
+    match value.map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        Some(Err(_)) => exit_with_usage(&format!("invalid value for {}", option)),
+        None => exit_with_usage(&format!("missing value for {}", option)),
+    }
+}
 
-    sum_is_3 | cell & sum_is_2_or_3
+fn parse_size(option: &str, value: Option<String>) -> (usize, usize) {
+    let value: String = parse_value(option, value);
+    match value.split_once('x').map(|(r, c)| (r.parse(), c.parse())) {
+        Some((Ok(n_rows), Ok(n_cols))) if n_rows > 0 && n_cols > 0 => (n_rows, n_cols),
+        _ => exit_with_usage(&format!("invalid value for {}", option)),
+    }
 }
 
-struct Board {
+/// Parses comma separated numbers.
+fn parse_numbers(option: &str, value: Option<String>) -> Vec<usize> {
+    let value: String = parse_value(option, value);
+    value
+        .split(',')
+        .map(|number| number.parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|_| exit_with_usage(&format!("invalid value for {}", option)))
+}
+
+fn parse_extract(option: &str, value: Option<String>) -> (usize, usize, usize) {
+    match parse_numbers(option, value)[..] {
+        [row, col, half_size] => (row, col, half_size),
+        _ => exit_with_usage(&format!("invalid value for {}", option)),
+    }
+}
+
+fn parse_cell(option: &str, value: Option<String>) -> (usize, usize) {
+    match parse_numbers(option, value)[..] {
+        [row, col] => (row, col),
+        _ => exit_with_usage(&format!("invalid value for {}", option)),
+    }
+}
+
+/// Parses `<name>@<row>,<col>`, a pattern of the catalog and where to put
+/// its top left corner.
+fn parse_stamp(option: &str, value: Option<String>) -> patch::Edit {
+    let value: String = parse_value(option, value);
+    let Some((name, at)) = value.split_once('@') else {
+        exit_with_usage(&format!("invalid value for {}", option));
+    };
+    let catalog_pattern = pattern::catalog_pattern(name).unwrap_or_else(|| {
+        exit_with_usage(&completion::with_suggestion(
+            format!("no pattern named '{}' in the catalog", name),
+            name,
+            pattern::CATALOG.iter().map(|pattern| pattern.name),
+        ))
+    });
+    let (pattern, pattern_cols) = catalog_pattern.cells();
+    patch::Edit::Stamp {
+        pattern,
+        pattern_cols,
+        at: parse_cell(option, Some(at.to_string())),
+    }
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!(
+        "usage: homomorphic_game_of_life_2 [--grid] [--coords] [--neighbour-counts] \
+         [--population [--noisy-population <scale>]] [--analysis] [--on-full <policy>] \
+         [--full-threshold <fraction>] [--downsample <factor>] [--upsample <factor>] \
+         [--tile <stride>] [--board-profile <profile>] [--library <path> --pattern <name>] \
+         [--catalog <name>] [--pattern-file <path> [--offset <row>,<col>]] \
+         [--size <rows>x<cols>] [--seed <n>] [--seed-manifest <path>] \
+         [--generations <n>] [--warmup <n>] [--extract <row>,<col>,<half size>] \
+         [--checksum] [--estimate-population <k>] [--trace] [--trace-cell <row>,<col>] \
+         [--batch <n>] [--time-limit <seconds>] [--fps <n>] \
+         [--detect-still] [--stream-in <path>] [--stream-edge <top|bottom>] \
+         [--shadow <n>] [--hash <text>] [--entropy <window>] [--distance <max>] \
+         [--infer-rule] [--lifespan] [--audit] [--max-leak-bits <n>] [--plaintext] \
+         [--keys <path> [--regen-keys]] [--params <default|tfhe-lib>] \
+         [--load-board <path>] [--save-board <path>] \
+         [--checkpoint <path> [--full-checkpoint-every <n>]] \
+         [--make-patch <path> [--toggle <row>,<col>] [--stamp <name>@<row>,<col>]] \
+         [--apply-patch <path>] [--export-rle <path>] \
+         [--rule <B../S..>] [--boundary <toroidal|fixed|mirror>] [--quadrants] \
+         [--completions bash] [--explain <topic>] [--explain-json <topic>]"
+    );
+    eprintln!(
+        "       homomorphic_game_of_life_2 encrypt --board <path> --keys <path> \
+         (--board-profile <profile> [--size <rows>x<cols>] [--seed <n>] \
+         | --pattern-file <path> [--size <rows>x<cols>]) \
+         [--rule <B../S..>] [--boundary <toroidal|fixed|mirror>] [--params <name>]"
+    );
+    eprintln!(
+        "       homomorphic_game_of_life_2 run --board <path> [--generations <n>] \
+         [--params <name>]"
+    );
+    eprintln!(
+        "       homomorphic_game_of_life_2 decrypt --board <path> --keys <path> \
+         [--params <name>]"
+    );
+    std::process::exit(1);
+}
+
+/// The most bits the client decrypts in a run with these options, see
+/// `audit::Declaration`.
+fn declared_leakage(options: &Options, dimensions: (usize, usize)) -> audit::Declaration {
+    use audit::Leak;
+    let n_cells = dimensions.0 * dimensions.1;
+    let total_generations = options.warmup + options.generations;
+    let mut declaration = audit::Declaration::default();
+
+    // the board is decrypted to be edited, and nothing else is run
+    if options.make_patch.is_some() {
+        declaration.once(Leak::Frame, n_cells);
+        return declaration;
+    }
+
+    // every frame is decrypted to be shown, and again for some options
+    declaration.every_frame(Leak::Frame, n_cells);
+    if options.show_neighbour_counts {
+        declaration.every_frame(Leak::Frame, n_cells);
+    }
+    if let Some(scale) = options.noise_scale {
+        let width = noise::width_for(n_cells as u64, noise::max_noise(scale));
+        declaration.every_frame(Leak::NoisyAggregate, width);
+    } else if options.show_population || options.on_full.is_some() {
+        declaration.every_frame(Leak::Population, sum_width(n_cells));
+    }
+    if options.checksum {
+        declaration.every_frame(Leak::Frame, n_cells);
+    }
+    if options.trace {
+        declaration.every_frame(Leak::Trace, trace::DECRYPTED_BITS * n_cells);
+        // the generation of the first mismatch runs again
+        declaration.once(Leak::Trace, trace::DECRYPTED_BITS * n_cells);
+    }
+    if options.trace_cell.is_some() {
+        declaration.every_frame(Leak::Trace, trace::DECRYPTED_BITS);
+    }
+    if options.detect_still {
+        declaration.every_frame(Leak::StillFlag, STILL_FLAGS_PER_FRAME);
+    }
+    if options.full_checkpoint_every.is_some() {
+        declaration.every_frame(Leak::Changes, n_cells);
+    }
+    if let Some(per_row) = options.estimate_population {
+        declaration.every_frame(Leak::SampledCells, per_row.min(dimensions.1) * dimensions.0);
+    }
+
+    if options.shadow_every.is_some() {
+        declaration.once(Leak::Frame, n_cells);
+    }
+    if let Some(window) = options.entropy_window {
+        declaration.once(Leak::Frame, window * n_cells);
+    }
+    if let Some(max_distance) = options.distance_field {
+        let bits = (usize::BITS - max_distance.leading_zeros()) as usize;
+        declaration.once(Leak::Distances, bits * n_cells);
+        declaration.once(Leak::Frame, n_cells);
+    }
+    if options.lifespan {
+        let bits = (usize::BITS - total_generations.leading_zeros()) as usize;
+        declaration.once(Leak::Lifespans, bits * n_cells);
+    }
+    if options.infer_rule {
+        declaration.once(Leak::RuleEvidence, 36 * sum_width(n_cells));
+        declaration.once(Leak::Frame, 2 * n_cells);
+    }
+    if let Some((_, _, half_size)) = options.extract {
+        declaration.once(Leak::Frame, (2 * half_size + 1).pow(2));
+    }
+    #[cfg(feature = "latex-export")]
+    if options.latex_path.is_some() {
+        declaration.once(Leak::Frame, n_cells);
+    }
+    declaration
+}
+
+/// Reads the rows of `--stream-in` for a board of `n_cols` columns.
+fn read_strip(path: &str, n_cols: usize) -> stream::Strip {
+    let drawing = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| exit_with_usage(&format!("could not read {}: {}", path, err)));
+    stream::Strip::parse(&drawing, n_cols).unwrap_or_else(|err| exit_with_usage(&err.to_string()))
+}
+
+/// A seed from the randomness of the operating system.
+fn os_seed() -> u64 {
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+/// The keys of `--keys`, read from the file or generated with the named
+/// parameters and written there (see `keys::load_or_generate`).
+fn load_keys(path: &str, parameters: &str, regen_keys: bool) -> (ClientKey, ServerKey) {
+    // the name was checked when parsing the options
+    let config = ConfigBuilder::all_disabled()
+        .enable_custom_bool(keys::parameters(parameters).unwrap())
+        .build();
+    let keygen_start = Instant::now();
+    let (client_key, server_key, origin) =
+        keys::load_or_generate(Path::new(path), parameters, regen_keys, || {
+            generate_keys(config)
+        })
+        .unwrap_or_else(|err| match err {
+            keys::FileError::Version(_) | keys::FileError::Unrecognised => exit_with_usage(
+                &format!("{}: {}, pass --regen-keys to replace it", path, err),
+            ),
+            _ => exit_with_usage(&format!("{}: {}", path, err)),
+        });
+    match origin {
+        keys::Origin::Loaded => {
+            println!("Keys loaded in {:.3?}", keygen_start.elapsed())
+        }
+        keys::Origin::Generated => println!(
+            "Key Generation time: {:.3?}, keys saved to {}",
+            keygen_start.elapsed(),
+            path
+        ),
+        keys::Origin::Replaced(err) => {
+            eprintln!("WARNING: {}: {}, replacing the keys", path, err);
+            println!(
+                "Key Generation time: {:.3?}, keys saved to {}",
+                keygen_start.elapsed(),
+                path
+            );
+        }
+    }
+    (client_key, server_key)
+}
+
+/// The seeds of the run: those of the `--seed-manifest` when it exists,
+/// otherwise those of `--seed`, written to the manifest.
+fn seed_manifest(options: &Options) -> seeds::SeedManifest {
+    let Some(path) = &options.seed_manifest else {
+        return seeds::SeedManifest::new(options.seed);
+    };
+    let path = Path::new(path);
+    let manifest = if path.exists() {
+        let manifest = seeds::SeedManifest::load(path)
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path.display(), err)));
+        // only the parts this run draws
+        let drawn = |part: &&str| match *part {
+            "noise" => options.noise_scale.is_some(),
+            "keys" => !options.plaintext && (options.key_path.is_none() || options.regen_keys),
+            _ => true,
+        };
+        for part in manifest.not_replayed().into_iter().filter(drawn) {
+            eprintln!(
+                "WARNING: the {} cannot be replayed, drawn anew from the OS",
+                part
+            );
+        }
+        manifest
+    } else {
+        let manifest = seeds::SeedManifest::new(options.seed);
+        manifest
+            .save(path)
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path.display(), err)));
+        manifest
+    };
+    println!("Seeds: {}", manifest);
+    manifest
+}
+
+/// A flag raised once the `--time-limit` has passed, never without one.
+fn time_limit_flag(time_limit: Option<f64>) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    if let Some(seconds) = time_limit {
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs_f64(seconds));
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+    stop
+}
+
+fn print_update_time(wall: Duration, cpu: Option<Duration>) {
+    match cpu {
+        Some(cpu) => println!(
+            "Time to update: {:.3?} ({:.3?} CPU, parallelism {:.1})",
+            wall,
+            cpu,
+            cpu.as_secs_f64() / wall.as_secs_f64()
+        ),
+        None => println!("Time to update: {:.3?}", wall),
+    }
+}
+
+/// Prints every decryption of the run, then the totals, for `--audit`.
+fn print_audit(auditor: &audit::DecryptionAuditor) {
+    for record in auditor.records() {
+        println!(
+            "generation {}: {} bits of {}",
+            record.generation, record.bits, record.leak
+        );
+    }
+    println!("Audit: {}", auditor);
+}
+
+fn print_inferred_rule(rule: &inference::InferredRule) {
+    println!("Rule inferred from one more generation: {}", rule);
+    if !rule.undecided.is_empty() {
+        let buckets: Vec<String> = rule
+            .undecided
+            .iter()
+            .map(|(alive, count)| format!("{}{}", if *alive { 'S' } else { 'B' }, count))
+            .collect();
+        println!("  not seen: {}", buckets.join(" "));
+    }
+}
+
+/// Prints what is known of the run once it is over: the periodicity of the
+/// final board, when it filled up, and the update times. Writes the final
+/// board for `--export-rle`.
+fn print_run_report(
+    options: &Options,
+    history: &[Vec<bool>],
     dimensions: (usize, usize),
-    states: Vec<FheBool>,
-    clean_accumulator: Accumulator,
+    saturated_at: Option<usize>,
+    update_times: &[Duration],
+    cpu_time: Option<Duration>,
+) {
+    match analysis::find_periodicity(history, dimensions) {
+        Some(periodicity) => println!("Final board: {}", periodicity),
+        None => println!("Final board: no periodicity found"),
+    }
+    if let Some(path) = &options.export_rle {
+        let final_board = history.last().unwrap();
+        std::fs::write(
+            path,
+            patternfile::to_rle(final_board, dimensions.1, &options.rule),
+        )
+        .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        println!("Final board written to {}", path);
+    }
+    if let Some(generation) = saturated_at {
+        println!(
+            "The board filled more than {}% of its cells at generation {}",
+            100.0 * options.full_threshold,
+            generation
+        );
+    }
+    if let Some(catalog_pattern) = options.catalog {
+        println!(
+            "The {} has a period of {}",
+            catalog_pattern.name, catalog_pattern.period
+        );
+    }
+
+    if let Some(mut stats) = stats::TimingStats::from_durations(update_times) {
+        if let Some(cpu_time) = cpu_time {
+            stats = stats.with_cpu_time(cpu_time);
+        }
+        println!("Update time over {}", stats);
+        let histogram: stats::LatencyHistogram = update_times.iter().copied().collect();
+        println!("Update time percentiles: {}", histogram);
+    }
+    if let Some(fps) = options.fps {
+        println!(
+            "Paced at {} frames per second: the elapsed time is not the throughput",
+            fps
+        );
+    }
 }
 
-impl Board {
-    pub fn new(n_cols: usize, states: Vec<FheBool>, zeros: (FheBool, FheBool, FheBool)) -> Self {
-        let n_rows = states.len() / n_cols;
+/// The `--plaintext` run: the same displays, stop conditions and reports as
+/// the encrypted run in `main`, on a `plain::PackedBoard`.
+fn run_plaintext(options: &Options, cells: Vec<bool>, dimensions: (usize, usize)) {
+    let n_cols = dimensions.1;
+    let mut board = plain::PackedBoard::new(&cells, dimensions)
+        .with_rule(options.rule)
+        .with_boundary(options.boundary);
+    let mut strip = options
+        .stream_in
+        .as_ref()
+        .map(|path| read_strip(path, n_cols));
+    let stop = time_limit_flag(options.time_limit);
 
-        Self {
-            dimensions: (n_rows, n_cols),
-            states,
-            clean_accumulator: Accumulator::from(zeros),
-        }
-    }
-
-    pub fn update(&mut self) {
-        let mut new_states = Vec::<FheBool>::with_capacity(self.states.len());
-
-        let nx = self.dimensions.0;
-        let ny = self.dimensions.1;
-        for i in 0..nx {
-            let im = if i == 0 { nx - 1 } else { i - 1 };
-            let ip = if i == nx - 1 { 0 } else { i + 1 };
-            for j in 0..ny {
-                let jm = if j == 0 { ny - 1 } else { j - 1 };
-                let jp = if j == ny - 1 { 0 } else { j + 1 };
-
-                // get the neighbours, with periodic boundary conditions
-                let n1 = &self.states[im * ny + jm];
-                let n2 = &self.states[im * ny + j];
-                let n3 = &self.states[im * ny + jp];
-                let n4 = &self.states[i * ny + jm];
-                let n5 = &self.states[i * ny + jp];
-                let n6 = &self.states[ip * ny + jm];
-                let n7 = &self.states[ip * ny + j];
-                let n8 = &self.states[ip * ny + jp];
-
-                // see if the cell is alive of dead
-                new_states.push(is_alive(
-                    &self.states[i * ny + j],
-                    &vec![n1, n2, n3, n4, n5, n6, n7, n8],
-                    self.clean_accumulator.clone(),
-                ));
+    let mut update_times = Vec::with_capacity(options.generations);
+    let mut cpu_time = Some(Duration::ZERO);
+    let mut history = Vec::with_capacity(options.warmup + options.generations + 1);
+    let mut count = 0;
+    let mut saturated_at = None;
+    let mut pacer = options.fps.map(pacing::Pacer::new);
+    loop {
+        if let Some(pacer) = &mut pacer {
+            pacer.wait();
+        }
+        print!("iter: {}", count);
+        let cells = board.cells();
+        if options.show_grid {
+            match (options.downsample, options.upsample) {
+                (Some(factor), _) => {
+                    let (reduced, dimensions) = resample::downsample(&cells, dimensions, factor);
+                    print_grid(&reduced, dimensions.1);
+                }
+                (None, Some(factor)) => {
+                    let (enlarged, dimensions) = resample::upsample(&cells, dimensions, factor);
+                    print_grid(&enlarged, dimensions.1);
+                }
+                (None, None) => print_grid(&cells, n_cols),
             }
+        } else {
+            println!();
+        }
+        if options.show_coords {
+            print_coords(&cells, n_cols);
+        }
+        if options.show_neighbour_counts {
+            let counts =
+                reference::neighbour_counts_with_boundary(&cells, dimensions, options.boundary);
+            print!("{}", reference::render_neighbour_counts(&counts, n_cols));
+        }
+        let population = board.population();
+        if options.show_population {
+            println!("population: {}", population);
+        }
+        if let Some(stride) = options.tile_stride {
+            let in_sync = pattern::tiles_identical(&cells, dimensions, (stride, stride));
+            println!("tiles in sync: {}", in_sync);
+        }
+        if options.show_analysis {
+            println!("{}", analysis::FrameAnalysis::new(&cells, dimensions));
         }
 
-        // update the board
-        self.states = new_states;
+        let full = options.on_full.is_some()
+            && population as f64 > options.full_threshold * cells.len() as f64;
+        if full && saturated_at.is_none() {
+            saturated_at = Some(count);
+            match options.on_full {
+                Some(OnFull::Warn) => {
+                    eprintln!("WARNING: the board is saturated at generation {}", count)
+                }
+                Some(OnFull::Stop) => break,
+                _ => {}
+            }
+        }
+        history.push(cells);
+
+        // increase the time step
+        let update_start = stats::Stopwatch::start();
+        board.update();
+        let (wall, cpu) = update_start.elapsed();
+        if let Some(row) = strip.as_mut().and_then(stream::Strip::next_row) {
+            // the rows were checked against the board when reading the strip
+            let scrolled =
+                stream::scroll_in_row(&board.cells(), dimensions, row, options.stream_edge)
+                    .unwrap();
+            board = plain::PackedBoard::new(&scrolled, dimensions)
+                .with_rule(options.rule)
+                .with_boundary(options.boundary);
+        }
+        print_update_time(wall, cpu);
+        if count >= options.warmup {
+            update_times.push(wall);
+            cpu_time = cpu_time.zip(cpu).map(|(total, cpu)| total + cpu);
+        }
+        count += 1;
+        if stop.load(Ordering::Relaxed) {
+            println!("Time limit reached after {} generations", count);
+            break;
+        }
+        if options.detect_still {
+            let cells = board.cells();
+            if history.last() == Some(&cells) {
+                println!("The board stopped changing at generation {}", count - 1);
+                break;
+            }
+            if history.len() >= 2 && history[history.len() - 2] == cells {
+                println!(
+                    "The board repeats every 2 generations from generation {}",
+                    count - 2
+                );
+                break;
+            }
+        }
+        if count == options.warmup + options.generations {
+            break;
+        }
+    }
+
+    let cells = board.cells();
+    history.push(cells.clone());
+    print_run_report(
+        options,
+        &history,
+        dimensions,
+        saturated_at,
+        &update_times,
+        cpu_time,
+    );
+
+    if let Some(window) = options.entropy_window {
+        let mut future = board.clone();
+        let frames: Vec<_> = (0..window)
+            .map(|_| {
+                future.update();
+                future.cells()
+            })
+            .collect();
+        println!(
+            "Topological entropy over {} generations: {:.3}",
+            window,
+            analysis::topological_entropy(&frames)
+        );
+    }
+
+    if let Some(max_distance) = options.distance_field {
+        println!("distance to the nearest live cell, up to {}:", max_distance);
+        print_numbers(
+            &reference::distance_field_with_boundary(
+                &cells,
+                dimensions,
+                max_distance,
+                options.boundary,
+            ),
+            n_cols,
+        );
+    }
+
+    if options.lifespan {
+        let tracked = options.warmup + options.generations;
+        println!(
+            "generation of the first death of every cell, {} if it survived, 0 if it never lived:",
+            tracked
+        );
+        print_numbers(&lifespan::plaintext_lifespans(&history, tracked), n_cols);
+    }
+
+    if options.infer_rule {
+        let mut after = board;
+        after.update();
+        let evidence = inference::RuleEvidence::from_frames(
+            &cells,
+            &after.cells(),
+            dimensions,
+            options.boundary,
+        );
+        print_inferred_rule(&evidence.infer());
     }
 }
 
 fn main() {
-    use std::time::Instant;
+    if let Some(command) = std::env::args()
+        .nth(1)
+        .filter(|arg| split::COMMANDS.contains(&arg.as_str()))
+    {
+        split::main(&command, std::env::args().skip(2));
+        return;
+    }
     let before = Instant::now();
-    let (n_rows, n_cols): (usize, usize) = (6, 6);
+    let options = Options::from_args();
+    let seeds = seed_manifest(&options);
+
+    // initial configuration, read after key generation for named patterns,
+    // while saved boards are already encrypted
+    let sources = (
+        &options.named_pattern,
+        options.catalog,
+        options.tile_stride,
+        &options.board_profile,
+    );
+    let loaded = options.load_board.as_ref().map(|path| {
+        let board = if Path::new(path).is_dir() || path.contains("://") {
+            let storage = storage::open(path).unwrap_or_else(|err| exit_with_usage(&err));
+            checkpoint::restore(&*storage, &options.parameters)
+                .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)))
+        } else {
+            Board::load(Path::new(path), &options.parameters)
+                .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)))
+        };
+        // the options checked against the rule and the boundary conditions
+        // are those of the command line
+        if (board.rule(), board.boundary()) != (options.rule, options.boundary) {
+            exit_with_usage(&format!(
+                "{} plays {} with {} boundaries, pass --rule {} --boundary {} to carry on",
+                path,
+                board.rule(),
+                board.boundary(),
+                board.rule(),
+                board.boundary()
+            ));
+        }
+        board
+    });
+    let (n_rows, n_cols, states) = if let Some(board) = &loaded {
+        let (n_rows, n_cols) = board.dimensions();
+        (n_rows, n_cols, None)
+    } else if let Some(path) = &options.pattern_file {
+        let file = patternfile::PatternFile::load(Path::new(path))
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        if file.rule.is_some_and(|rule| rule != options.rule) {
+            eprintln!(
+                "WARNING: {} is a {} pattern, played with {}",
+                path,
+                file.rule.unwrap(),
+                options.rule
+            );
+        }
+        let (pattern_rows, pattern_cols) = file.dimensions;
+        let (n_rows, n_cols) = options.size.unwrap_or((
+            pattern_rows + 2 * pattern::CATALOG_MARGIN,
+            pattern_cols + 2 * pattern::CATALOG_MARGIN,
+        ));
+        let states = pattern::place(&file.cells, pattern_cols, (n_rows, n_cols), options.offset)
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        (n_rows, n_cols, Some(states))
+    } else {
+        let size = options.size.unwrap_or(DEFAULT_SIZE);
+        match sources {
+            (Some(_), _, _, _) => (size.0, size.1, None),
+            (None, Some(catalog_pattern), _, _) => {
+                let (cells, pattern_cols) = catalog_pattern.cells();
+                let (states, dimensions) =
+                    pattern::centered(&cells, pattern_cols, pattern::CATALOG_MARGIN);
+                (dimensions.0, dimensions.1, Some(states))
+            }
+            (None, None, _, Some(profile)) => {
+                let states = benchgen::generate(profile, size, seeds.board.seed(os_seed));
+                (size.0, size.1, Some(states))
+            }
+            (None, None, Some(stride), None) => {
+                let dimensions = (2 * stride, 2 * stride);
+                let states = pattern::tile(&pattern::GLIDER, 3, dimensions, (stride, stride))
+                    .unwrap_or_else(|err| exit_with_usage(&err.to_string()));
+                (dimensions.0, dimensions.1, Some(states))
+            }
+            (None, None, None, None) => {
+                #[rustfmt::skip]
+            let states = vec![
+                true, false, false, false, false, false,
+                false, true, true, false, false, false,
+                true, true, false, false, false, false,
+                false, false, false, false, false, false,
+                false, false, false, false, false, false,
+                false, false, false, false, false, false,
+            ];
+                (6, 6, Some(states))
+            }
+        }
+    };
+    if let Some(states) = &states {
+        assert_eq!(states.len(), n_rows * n_cols);
+        if options.quadrants {
+            let generations = options.warmup + options.generations;
+            quadrant::check_moats(states, (n_rows, n_cols), generations, options.rule)
+                .unwrap_or_else(|err| exit_with_usage(&err.to_string()));
+        }
+    }
+    if options
+        .trace_cell
+        .is_some_and(|(row, col)| row >= n_rows || col >= n_cols)
+    {
+        exit_with_usage("--trace-cell is outside the board");
+    }
+    if let Some((row, col, half_size)) = options.extract {
+        if row >= n_rows || col >= n_cols {
+            exit_with_usage("--extract is outside the board");
+        }
+        // the square is at most as wide as the board along both sides
+        if half_size > (n_rows.min(n_cols) - 1) / 2 {
+            exit_with_usage("--extract is wider than the board");
+        }
+    }
 
+    if options.plaintext {
+        let cells = match (states, &options.named_pattern) {
+            (Some(states), _) => states,
+            (None, Some((path, name))) => library::Library::load(Path::new(path))
+                .and_then(|library| library.board(name, (n_rows, n_cols)))
+                .unwrap_or_else(|err| exit_with_usage(&err.to_string())),
+            (None, None) => unreachable!(),
+        };
+        run_plaintext(&options, cells, (n_rows, n_cols));
+        println!("Elapsed time: {:.2?}", before.elapsed());
+        return;
+    }
 
-    let config = ConfigBuilder::all_disabled().enable_default_bool().build();
+    let mut auditor = audit::DecryptionAuditor::new(
+        &declared_leakage(&options, (n_rows, n_cols)),
+        options.warmup + options.generations + 1,
+        options.max_leak_bits,
+    )
+    .unwrap_or_else(|err| exit_with_usage(&err.to_string()));
 
-    let keygen_start = Instant::now();
-    let (client_key, server_key) = generate_keys(config);
-    println!("Key Generation time: {:.3?}", keygen_start.elapsed());
+    // the name was checked when parsing the options
+    let parameters = keys::parameters(&options.parameters).unwrap();
+    let config = ConfigBuilder::all_disabled()
+        .enable_custom_bool(parameters)
+        .build();
 
-    let zeros = (
-        FheBool::encrypt(false, &client_key),
-        FheBool::encrypt(false, &client_key),
-        FheBool::encrypt(false, &client_key),
-    );
+    let (client_key, server_key) = match &options.key_path {
+        Some(path) => load_keys(path, &options.parameters, options.regen_keys),
+        None => {
+            let keygen_start = Instant::now();
+            let keys = generate_keys(config);
+            println!("Key Generation time: {:.3?}", keygen_start.elapsed());
+            keys
+        }
+    };
+
+    if let Some(text) = &options.hash_input {
+        let digest = Board::cellular_hash(
+            text.as_bytes(),
+            options.generations,
+            &client_key,
+            server_key,
+        );
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        println!("hash after {} rounds: {}", options.generations, hex);
+        println!("Elapsed time: {:.2?}", before.elapsed());
+        return;
+    }
 
-    // initial configuration
-    #[rustfmt::skip]
-    let states = vec![
-        true, false, false, false, false, false,
-        false, true, true, false, false, false,
-        true, true, false, false, false, false,
-        false, false, false, false, false, false,
-        false, false, false, false, false, false,
-        false, false, false, false, false, false,
-    ];
+    let mut board = match (loaded, states, &options.named_pattern) {
+        (Some(board), _, _) => {
+            set_server_key(server_key);
+            board
+        }
+        (None, Some(states), _) => {
+            let zeros = (
+                FheBool::encrypt(false, &client_key),
+                FheBool::encrypt(false, &client_key),
+                FheBool::encrypt(false, &client_key),
+            );
 
-    // encrypt the initial configuration
-    let states: Vec<_> = states
-        .into_iter()
-        .map(|x| FheBool::encrypt(x, &client_key))
-        .collect();
+            // encrypt the initial configuration
+            let states: Vec<_> = states
+                .into_iter()
+                .map(|x| FheBool::encrypt(x, &client_key))
+                .collect();
 
-    set_server_key(server_key);
+            set_server_key(server_key);
 
-    let mut board = Board::new(n_cols, states, zeros);
+            Board::new(n_cols, states, zeros)
+        }
+        (None, None, Some((path, name))) => Board::from_named_pattern(
+            Path::new(path),
+            name,
+            n_rows,
+            n_cols,
+            &client_key,
+            server_key,
+        )
+        .unwrap_or_else(|err| exit_with_usage(&err.to_string())),
+        (None, None, None) => unreachable!(),
+    }
+    .with_rule(options.rule)
+    .with_boundary(options.boundary);
+    if options.rule != rule::Rule::conway() {
+        println!("rule: {}", options.rule);
+    }
 
+    if let Some(path) = &options.make_patch {
+        auditor.set_generation(board.generation());
+        let frame = board.decrypt(&client_key);
+        auditor.record(audit::Leak::Frame, frame.len());
+        let mut edited = frame.clone();
+        patch::apply_edits(&mut edited, (n_rows, n_cols), &options.edits)
+            .unwrap_or_else(|err| exit_with_usage(&err.to_string()));
+        let generation = board.generation();
+        let patch = patch::Patch::new(&frame, &edited, (n_rows, n_cols), generation, &client_key);
+        patch
+            .save(Path::new(path), &options.parameters)
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        println!(
+            "Patch of {} cells for generation {} saved to {}",
+            patch.len(),
+            generation,
+            path
+        );
+        if options.audit {
+            print_audit(&auditor);
+        }
+        println!("Elapsed time: {:.2?}", before.elapsed());
+        return;
+    }
+    if let Some(path) = &options.apply_patch {
+        let replaced = patch::Patch::load(Path::new(path), &options.parameters)
+            .and_then(|patch| board.apply_patch(&patch))
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        println!(
+            "Patch {}: {} cells replaced at generation {}",
+            path,
+            replaced,
+            board.generation()
+        );
+    }
+
+    let mut shadow = options.shadow_every.map(|every| {
+        auditor.record(audit::Leak::Frame, n_rows * n_cols);
+        shadow::Shadow::new(board.decrypt(&client_key), (n_rows, n_cols), every)
+    });
+
+    let mut strip = options
+        .stream_in
+        .as_ref()
+        .map(|path| read_strip(path, n_cols));
+
+    if options.lifespan {
+        board.attach_lifespan_tracker(options.warmup + options.generations);
+    }
+    // the options were checked against the boundary conditions
+    let mut quadrants = options
+        .quadrants
+        .then(|| quadrant::QuadrantBoard::split(&board).unwrap());
+    #[cfg(feature = "phase-timer")]
+    let mut phases = options.phase_timer.map(|every| {
+        phase_timer::set_sampling(Some(every));
+        phase_timer::PhaseProfile::default()
+    });
+    let mut chain = options.full_checkpoint_every.map(|full_every| {
+        let path = options.checkpoint.as_ref().unwrap();
+        let storage = storage::open(path).unwrap_or_else(|err| exit_with_usage(&err));
+        checkpoint::CheckpointChain::create(storage, &options.parameters, full_every)
+            .unwrap_or_else(|err| exit_with_usage(&err.to_string()))
+    });
+
+    let stop = time_limit_flag(options.time_limit);
+
+    let mut update_times = Vec::with_capacity(options.generations);
+    let mut cpu_time = Some(Duration::ZERO);
+    let mut history = Vec::with_capacity(options.warmup + options.generations + 1);
     let mut count = 0;
+    let mut mismatch_reported = false;
+    // the generation before `previous`, for --detect-still
+    let mut two_back = None;
+    let mut cell_traces = Vec::new();
+    let mut saturated_at = None;
+    let mut pacer = options.fps.map(pacing::Pacer::new);
+    let mut noisy = options.noise_scale.map(|scale| {
+        // the server must not be able to guess the noise from the options
+        let seed = seeds.noise.seed(os_seed);
+        let n_frames = options.warmup + options.generations + 1;
+        let n_cells = (n_rows * n_cols) as u64;
+        let mut rng = benchgen::Rng::new(seed);
+        noise::NoisyRelease::new(scale, n_frames, n_cells, &mut rng, &client_key)
+    });
+    let zero = FheBool::encrypt(false, &client_key);
+    let mut sampling_rng = benchgen::Rng::new(seeds.sampling.seed(os_seed));
     loop {
+        if let Some(pacer) = &mut pacer {
+            pacer.wait();
+        }
         print!("iter: {}", count);
+        auditor.set_generation(board.generation());
         // show the board
-        for i in 0..n_rows {
+        let cells = board.decrypt(&client_key);
+        auditor.record(audit::Leak::Frame, cells.len());
+        if options.show_grid {
+            match (options.downsample, options.upsample) {
+                (Some(factor), _) => {
+                    let (reduced, dimensions) =
+                        resample::downsample(&cells, (n_rows, n_cols), factor);
+                    print_grid(&reduced, dimensions.1);
+                }
+                (None, Some(factor)) => {
+                    let (enlarged, dimensions) =
+                        resample::upsample(&cells, (n_rows, n_cols), factor);
+                    print_grid(&enlarged, dimensions.1);
+                }
+                (None, None) => print_grid(&cells, n_cols),
+            }
+        } else {
             println!();
-            for j in 0..n_rows {
-                if (&board.states[i * n_cols + j]).decrypt(&client_key) {
-                    print!("█");
-                } else {
-                    print!("░");
+        }
+        if options.show_coords {
+            print_coords(&cells, n_cols);
+        }
+        if options.show_neighbour_counts {
+            board.display_neighbour_counts(&client_key);
+            auditor.record(audit::Leak::Frame, cells.len());
+        }
+        if let Some(noisy) = &mut noisy {
+            let count = board.encrypted_population_count();
+            match noisy.release(&count, &zero) {
+                Ok(released) => {
+                    auditor.record(audit::Leak::NoisyAggregate, released.len());
+                    let population = noise::decrypt_signed(&released, &client_key);
+                    println!("noisy population: {}", population);
                 }
+                Err(err) => println!("noisy population: not released, {}", err),
             }
         }
-        println!();
+        let exact = options.show_population || options.on_full.is_some();
+        let population = (exact && noisy.is_none()).then(|| {
+            let count = board.encrypted_population_count();
+            auditor.record(audit::Leak::Population, count.len());
+            decrypt_number(&count, &client_key)
+        });
+        if let Some(population) = population.filter(|_| options.show_population) {
+            println!("population: {}", population);
+        }
+        if let Some(stride) = options.tile_stride {
+            let in_sync = pattern::tiles_identical(&cells, (n_rows, n_cols), (stride, stride));
+            println!("tiles in sync: {}", in_sync);
+        }
+        if options.show_analysis {
+            println!("{}", analysis::FrameAnalysis::new(&cells, (n_rows, n_cols)));
+        }
+        check_shadow(shadow.as_ref(), &cells);
+
+        let full = population.is_some_and(|population| {
+            population as f64 > options.full_threshold * cells.len() as f64
+        });
+        if full && saturated_at.is_none() {
+            saturated_at = Some(count);
+            match options.on_full {
+                Some(OnFull::Warn) => {
+                    eprintln!("WARNING: the board is saturated at generation {}", count)
+                }
+                Some(OnFull::Stop) => break,
+                _ => {}
+            }
+        }
+        history.push(cells);
+        if let Some((row, col)) = options.trace_cell {
+            cell_traces.push(board.trace_cell(row, col, &client_key));
+            auditor.record(audit::Leak::Trace, trace::DECRYPTED_BITS);
+        }
 
         // increase the time step
-        let update_start = Instant::now();
-        board.update();
-        println!("Time to update: {:.3?}", update_start.elapsed());
-        count += 1;
-        if count == 5 {
+        let previous = options.detect_still.then(|| board.snapshot());
+        let update_start = stats::Stopwatch::start();
+        let mut durations = Vec::with_capacity(1);
+        let mut cancelled = false;
+        if let Some(batch) = options.batch {
+            let remaining = options.warmup + options.generations - count;
+            let report = board.step_n(batch.min(remaining), Some(1), &|| {
+                stop.load(Ordering::Relaxed)
+            });
+            // all the frames but the last, which is shown at the next iteration
+            for frame in report.frames.iter().rev().skip(1).rev() {
+                auditor.set_generation(frame.generation);
+                let cells = decrypt_cells(&frame.states, &client_key);
+                auditor.record(audit::Leak::Frame, cells.len());
+                if let Some(shadow) = &mut shadow {
+                    shadow.advance();
+                }
+                check_shadow(shadow.as_ref(), &cells);
+                history.push(cells);
+            }
+            durations = report.durations;
+            cancelled = report.cancelled;
+        } else if options.checksum {
+            let checksum = board.step_with_checksum(&client_key);
+            auditor.record(audit::Leak::Frame, board.states().len());
+            println!("checksum: {:08x}", checksum);
+        } else if options.trace && !mismatch_reported {
+            let previous = board.snapshot();
+            let traces = board.update_traced(&client_key);
+            auditor.record(audit::Leak::Trace, trace::DECRYPTED_BITS * traces.len());
+            if let Some(mismatch) = trace::first_mismatch(&traces) {
+                eprintln!("{}", mismatch);
+                mismatch_reported = true;
+
+                // a bug gives the same result again, a noise failure rarely does
+                let current = board.snapshot();
+                board.restore(previous).unwrap();
+                let retraces = board.update_traced(&client_key);
+                auditor.record(audit::Leak::Trace, trace::DECRYPTED_BITS * retraces.len());
+                let reproduced = trace::first_mismatch(&retraces) == Some(mismatch);
+                eprintln!(
+                    "  {} when running the generation again",
+                    if reproduced {
+                        "reproduced"
+                    } else {
+                        "not reproduced"
+                    }
+                );
+                board.restore(current).unwrap();
+            }
+        } else if let Some(quadrants) = &mut quadrants {
+            quadrants.update();
+            board = quadrants.stitch();
+        } else if let Some(per_row) = options.estimate_population {
+            let mut estimator = estimate::PopulationEstimator::new(n_rows * n_cols);
+            let print_every = n_rows.div_ceil(10);
+            board.update_by_rows(&mut |row, cells| {
+                let sample = estimate::sample_cells(cells, per_row, &mut sampling_rng, &client_key);
+                estimator.observe(cells.len(), &sample);
+                if (row + 1) % print_every == 0 || row + 1 == n_rows {
+                    println!("{}", estimator.estimate().unwrap());
+                }
+            });
+            auditor.record(audit::Leak::SampledCells, per_row.min(n_cols) * n_rows);
+        } else {
+            board.update();
+        }
+        let (wall, cpu) = update_start.elapsed();
+        if options.batch.is_none() {
+            durations.push(wall);
+        }
+        if let Some(shadow) = &mut shadow {
+            if !durations.is_empty() {
+                shadow.advance();
+            }
+        }
+        if let Some(row) = strip.as_mut().and_then(stream::Strip::next_row) {
+            let encrypted_row = row
+                .iter()
+                .map(|&alive| FheBool::encrypt(alive, &client_key))
+                .collect();
+            // the rows were checked against the board when reading the strip
+            board
+                .scroll_in_row(encrypted_row, options.stream_edge)
+                .unwrap();
+            if let Some(shadow) = &mut shadow {
+                shadow.scroll_in_row(row, options.stream_edge).unwrap();
+            }
+        }
+        if let Some(chain) = &mut chain {
+            if !chain.full_due() {
+                auditor.record(audit::Leak::Changes, board.states().len());
+            }
+            let path = options.checkpoint.as_ref().unwrap();
+            chain
+                .record(&board, &client_key)
+                .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        } else if let Some(path) = &options.checkpoint {
+            board
+                .save(Path::new(path), &options.parameters)
+                .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        }
+        print_update_time(wall, cpu);
+        #[cfg(feature = "phase-timer")]
+        if let Some(phases) = &mut phases {
+            let profile = phase_timer::take_profile();
+            println!("Phases: {}", profile);
+            phases.merge(&profile);
+        }
+        let generations = durations.len();
+        let mut timed = 0;
+        for update_time in durations {
+            if count >= options.warmup {
+                update_times.push(update_time);
+                timed += 1;
+            }
+            count += 1;
+        }
+        // the CPU time of a batch is shared between its generations
+        if timed > 0 {
+            cpu_time = cpu_time
+                .zip(cpu)
+                .map(|(total, cpu)| total + cpu * timed / generations as u32);
+        }
+        if cancelled || stop.load(Ordering::Relaxed) {
+            println!("Time limit reached after {} generations", count);
+            break;
+        }
+        if let Some(previous) = &previous {
+            auditor.record(audit::Leak::StillFlag, 1);
+            if board.equals(previous).decrypt(&client_key) {
+                println!(
+                    "The board stopped changing at generation {}",
+                    previous.generation
+                );
+                break;
+            }
+        }
+        if let Some(two_back) = &two_back {
+            auditor.record(audit::Leak::StillFlag, 1);
+            if board.equals(two_back).decrypt(&client_key) {
+                println!(
+                    "The board repeats every 2 generations from generation {}",
+                    two_back.generation
+                );
+                break;
+            }
+        }
+        two_back = previous;
+        if count == options.warmup + options.generations {
             break;
         }
     }
 
-    println!("Elapsed time: {:.2?}", before.elapsed());
-}
+    auditor.set_generation(board.generation());
+    let cells = board.decrypt(&client_key);
+    auditor.record(audit::Leak::Frame, cells.len());
+    check_shadow(shadow.as_ref(), &cells);
+    history.push(cells);
+    print_run_report(
+        &options,
+        &history,
+        (n_rows, n_cols),
+        saturated_at,
+        &update_times,
+        cpu_time,
+    );
+    #[cfg(feature = "phase-timer")]
+    if let Some(phases) = &phases {
+        println!("Phases over the run: {}", phases);
+    }
+    if let Some((row, col)) = options.trace_cell {
+        println!("trace of cell ({}, {}):", row, col);
+        print!("{}", trace::render_table(&cell_traces));
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::Accumulator;
-    use concrete::prelude::*;
-    use concrete::{generate_keys, ConfigBuilder, FheBool};
-
-    fn decrypt_acc(acc: &Accumulator, keys: &mut KeyChain) -> (FheBool, FheBool, FheBool) {
-        (
-            acc.2.to_FheBool(keys),
-            acc.1.to_FheBool(keys),
-            acc.0.to_FheBool(keys),
-        )
+    if let Some(window) = options.entropy_window {
+        let entropy = board.estimate_topological_entropy(&client_key, window);
+        auditor.record(audit::Leak::Frame, window * board.states().len());
+        println!(
+            "Topological entropy over {} generations: {:.3}",
+            window, entropy
+        );
     }
 
-    #[test]
-    fn test_accumulator() {
-        let config = ConfigBuilder::all_disabled().enable_default_bool().build();
+    if let Some(max_distance) = options.distance_field {
+        let distances: Vec<usize> = board
+            .distance_field(max_distance)
+            .iter()
+            .map(|bits| {
+                auditor.record(audit::Leak::Distances, bits.len());
+                decrypt_number(bits, &client_key)
+            })
+            .collect();
+        println!("distance to the nearest live cell, up to {}:", max_distance);
+        print_numbers(&distances, board.dimensions().1);
+        auditor.record(audit::Leak::Frame, board.states().len());
+        let expected = reference::distance_field_with_boundary(
+            &board.decrypt(&client_key),
+            board.dimensions(),
+            max_distance,
+            board.boundary(),
+        );
+        if distances != expected {
+            println!("WARNING: the encrypted distances differ from the plaintext ones");
+        }
+    }
 
-        let (client_key, server_key) = generate_keys(config);
+    if let Some(lifespans) = board.lifespans() {
+        let lifespans: Vec<usize> = lifespans
+            .iter()
+            .map(|bits| {
+                auditor.record(audit::Leak::Lifespans, bits.len());
+                decrypt_number(bits, &client_key)
+            })
+            .collect();
+        let tracked = options.warmup + options.generations;
+        println!(
+            "generation of the first death of every cell, {} if it survived, 0 if it never lived:",
+            tracked
+        );
+        print_numbers(&lifespans, n_cols);
+        if lifespans != lifespan::plaintext_lifespans(&history, tracked) {
+            println!("WARNING: the encrypted lifespans differ from the plaintext ones");
+        }
+    }
 
-        let zeros = (
-            FheBool::encrypt(false, &client_key),
-            FheBool::encrypt(false, &client_key),
-            FheBool::encrypt(false, &client_key),
+    if options.infer_rule {
+        let mut after = board.clone();
+        after.update();
+        let counters = board.rule_evidence(&after);
+        auditor.record(audit::Leak::RuleEvidence, counters.n_bits());
+        let evidence = counters.decrypt(&client_key);
+        print_inferred_rule(&evidence.infer());
+        auditor.record(audit::Leak::Frame, 2 * board.states().len());
+        let expected = inference::RuleEvidence::from_frames(
+            &board.decrypt(&client_key),
+            &after.decrypt(&client_key),
+            board.dimensions(),
+            board.boundary(),
         );
+        if evidence != expected {
+            println!("WARNING: the encrypted transition counts differ from the plaintext ones");
+        }
+    }
 
-        let mut accumulator = Accumulator::from(zeros);
-        let ftrue = FheBool::encrypt(true, &mut keys);
-        let ffalse = FheBool::encrypt(false, &mut keys);
+    if let Some((row, col, half_size)) = options.extract {
+        let extracted = board.extract_pattern_at(row, col, half_size);
+        print!("around ({}, {}):", row, col);
+        auditor.record(audit::Leak::Frame, extracted.states().len());
+        print_grid(&extracted.decrypt(&client_key), extracted.dimensions().1);
+    }
 
-        let bits = decrypt_acc(&accumulator, &mut keys);
-        assert_eq!(bits, (false, false, false));
+    #[cfg(feature = "latex-export")]
+    if let Some(path) = &options.latex_path {
+        auditor.record(audit::Leak::Frame, board.states().len());
+        std::fs::write(path, board.to_latex_tikz(&client_key, 4.0))
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+    }
+    if let Some(path) = &options.save_board {
+        board
+            .save(Path::new(path), &options.parameters)
+            .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+        println!("Board saved to {}", path);
+    }
+    if options.audit {
+        print_audit(&auditor);
+    }
+    println!("Elapsed time: {:.2?}", before.elapsed());
+}
 
-        accumulator += &ftrue;
-        let bits = decrypt_acc(&accumulator, &mut keys);
-        assert_eq!(bits, (false, false, true));
+#[cfg(test)]
+mod tests {
+    use crate::OnFull;
+
+    #[test]
+    fn test_parse_on_full() {
+        assert_eq!("continue".parse(), Ok(OnFull::Continue));
+        assert_eq!("warn".parse(), Ok(OnFull::Warn));
+        assert_eq!("stop".parse(), Ok(OnFull::Stop));
+        assert!("halt".parse::<OnFull>().is_err());
     }
 }
@@ -0,0 +1,195 @@
+//! Noisy release of encrypted aggregates, such as the population, so that
+//! repeated releases do not pinpoint individual cells.
+//!
+//! The client encrypts a pool of noise samples ahead of time and hands it to
+//! the server, which adds one of them to every aggregate before it is
+//! decrypted. The samples follow a two-sided geometric distribution, the
+//! discrete counterpart of the Laplace mechanism, clamped to
+//! `max_noise(scale)` so that noisy aggregates fit in a known number of bits.
+
+use crate::add_numbers;
+use crate::benchgen::Rng;
+use concrete::prelude::*;
+use concrete::{ClientKey, FheBool};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+/// Every noise sample was used, releasing more would reuse one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PoolExhausted;
+
+impl fmt::Display for PoolExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the noise pool is exhausted")
+    }
+}
+
+impl Error for PoolExhausted {}
+
+/// Largest scale of the noise, far more than hides any cell, so that noisy
+/// aggregates fit in the 64 bits `decrypt_signed` reads.
+pub const MAX_SCALE: f64 = 1e6;
+
+/// Largest magnitude of the noise samples of `scale`: further than 20 scales
+/// from 0 a sample has a probability below 1e-8 and is clamped.
+pub fn max_noise(scale: f64) -> u64 {
+    (20.0 * scale).ceil() as u64
+}
+
+/// Bits of the noisy releases of aggregates up to `max_aggregate`, in two's
+/// complement, the noise possibly taking them below 0.
+pub fn width_for(max_aggregate: u64, max_noise: u64) -> usize {
+    let largest = max_aggregate.max(1) + max_noise;
+    1 + (u64::BITS - largest.leading_zeros()) as usize
+}
+
+/// A sample of two-sided geometric noise of `scale`, the difference of two
+/// geometric variables of ratio `exp(-1 / scale)`.
+pub fn sample_noise(scale: f64, rng: &mut Rng) -> i64 {
+    let ratio = (-1.0 / scale).exp();
+    let mut geometric = || {
+        // uniform in (0, 1]
+        let uniform = ((rng.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+        (uniform.ln() / ratio.ln()).floor() as i64
+    };
+    let bound = max_noise(scale) as i64;
+    (geometric() - geometric()).clamp(-bound, bound)
+}
+
+/// Pool of encrypted noise samples, consumed one per release.
+#[derive(Clone)]
+pub struct NoisyRelease {
+    width: usize,
+    pool: VecDeque<Vec<FheBool>>,
+}
+
+impl NoisyRelease {
+    /// Encrypts `n_samples` samples of noise of `scale`, for aggregates up to
+    /// `max_aggregate`. Runs on the client.
+    pub fn new(
+        scale: f64,
+        n_samples: usize,
+        max_aggregate: u64,
+        rng: &mut Rng,
+        client_key: &ClientKey,
+    ) -> Self {
+        assert!(
+            scale > 0.0 && scale <= MAX_SCALE,
+            "the scale of the noise must be positive and at most {}",
+            MAX_SCALE
+        );
+        let width = width_for(max_aggregate, max_noise(scale));
+        let pool = (0..n_samples)
+            .map(|_| {
+                let noise = sample_noise(scale, rng);
+                (0..width)
+                    .map(|k| FheBool::encrypt(noise >> k & 1 == 1, client_key))
+                    .collect()
+            })
+            .collect();
+        Self { width, pool }
+    }
+
+    /// Bits of the released aggregates.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of releases left.
+    pub fn remaining(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Adds the next noise sample to `aggregate`, whose bits are least
+    /// significant first and whose value is at most the maximum of the pool,
+    /// and returns the `width` bits of the sum in two's complement, see
+    /// `decrypt_signed`. Runs on the server.
+    pub fn release(
+        &mut self,
+        aggregate: &[FheBool],
+        zero: &FheBool,
+    ) -> Result<Vec<FheBool>, PoolExhausted> {
+        let noise = self.pool.pop_front().ok_or(PoolExhausted)?;
+        let mut sum = add_numbers(aggregate, &noise, zero);
+        sum.truncate(self.width);
+        Ok(sum)
+    }
+}
+
+/// Decrypts a number in two's complement, least significant bit first.
+pub fn decrypt_signed(bits: &[FheBool], client_key: &ClientKey) -> i64 {
+    let value = bits.iter().enumerate().fold(0i64, |acc, (k, bit)| {
+        acc | i64::from(bit.decrypt(client_key)) << k
+    });
+    let unused = i64::BITS as usize - bits.len();
+    value << unused >> unused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::set_up_keys;
+
+    #[test]
+    fn test_noise_distribution() {
+        let scale = 2.0;
+        let mut rng = Rng::new(7);
+        let samples: Vec<i64> = (0..20_000).map(|_| sample_noise(scale, &mut rng)).collect();
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<i64>() as f64 / n;
+        let variance = samples
+            .iter()
+            .map(|&x| (x as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        // the variance of the difference of two geometric variables
+        let ratio = (-1.0 / scale).exp();
+        let expected = 2.0 * ratio / (1.0 - ratio).powi(2);
+        assert!(mean.abs() < 0.1, "mean {}", mean);
+        assert!(
+            (variance - expected).abs() < 0.05 * expected,
+            "variance {} instead of {}",
+            variance,
+            expected
+        );
+        let zeros = samples.iter().filter(|&&x| x == 0).count() as f64 / n;
+        let expected = (1.0 - ratio) / (1.0 + ratio);
+        assert!((zeros - expected).abs() < 0.02, "P(0) = {}", zeros);
+    }
+
+    #[test]
+    fn test_width_for() {
+        assert_eq!(width_for(0, 0), 2);
+        assert_eq!(width_for(16, 0), 6);
+        assert_eq!(width_for(100, 40), 9);
+        // the largest scale on a board of billions of cells
+        assert_eq!(width_for(u32::MAX as u64, max_noise(MAX_SCALE)), 34);
+    }
+
+    #[test]
+    fn test_release() {
+        let client_key = set_up_keys();
+
+        let scale = 3.0;
+        let mut pool = NoisyRelease::new(scale, 4, 25, &mut Rng::new(11), &client_key);
+        assert_eq!(pool.width(), width_for(25, max_noise(scale)));
+        let mut rng = Rng::new(11);
+        let zero = FheBool::encrypt(false, &client_key);
+
+        for aggregate in [0usize, 1, 13, 25] {
+            let bits: Vec<_> = (0..5)
+                .map(|k| FheBool::encrypt(aggregate >> k & 1 == 1, &client_key))
+                .collect();
+            let released = pool.release(&bits, &zero).unwrap();
+            assert_eq!(released.len(), pool.width());
+            assert_eq!(
+                decrypt_signed(&released, &client_key),
+                aggregate as i64 + sample_noise(scale, &mut rng)
+            );
+        }
+        assert_eq!(pool.remaining(), 0);
+        assert!(matches!(pool.release(&[], &zero), Err(PoolExhausted)));
+    }
+}
@@ -0,0 +1,83 @@
+//! Pacing of the displayed frames, so that runs on small boards can be
+//! followed live.
+
+use std::time::{Duration, Instant};
+
+/// The slowest frame rate, a frame a day, beyond which the interval between
+/// two frames would overflow.
+pub const MIN_FRAMES_PER_SECOND: f64 = 1.0 / 86_400.0;
+
+/// Spaces frames `interval` apart, never trying to catch up: a frame that
+/// comes late is shown at once and the next one is due an interval later.
+#[derive(Debug)]
+pub struct Pacer {
+    interval: Duration,
+    next_frame: Option<Instant>,
+}
+
+impl Pacer {
+    pub fn new(frames_per_second: f64) -> Self {
+        assert!(
+            frames_per_second >= MIN_FRAMES_PER_SECOND,
+            "the frame rate must be at least a frame a day"
+        );
+        Self {
+            interval: Duration::from_secs_f64(1.0 / frames_per_second),
+            next_frame: None,
+        }
+    }
+
+    /// How long to wait, from `now`, before showing the next frame.
+    pub fn delay(&mut self, now: Instant) -> Duration {
+        let due = self.next_frame.filter(|due| *due > now).unwrap_or(now);
+        self.next_frame = Some(due + self.interval);
+        due - now
+    }
+
+    /// Waits until the next frame is due.
+    pub fn wait(&mut self) {
+        let delay = self.delay(Instant::now());
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_updates_are_paced() {
+        let mut pacer = Pacer::new(4.0);
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        assert_eq!(pacer.delay(start), Duration::ZERO);
+        // the updates take 10ms, the frames are shown every 250ms
+        assert_eq!(pacer.delay(start + ms(10)), ms(240));
+        assert_eq!(pacer.delay(start + ms(260)), ms(240));
+        assert_eq!(pacer.delay(start + ms(510)), ms(240));
+    }
+
+    #[test]
+    fn test_slow_updates_are_not_delayed() {
+        let mut pacer = Pacer::new(10.0);
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        assert_eq!(pacer.delay(start), Duration::ZERO);
+        assert_eq!(pacer.delay(start + ms(300)), Duration::ZERO);
+        assert_eq!(pacer.delay(start + ms(700)), Duration::ZERO);
+        // a faster update after slow ones is not rushed to catch up
+        assert_eq!(pacer.delay(start + ms(720)), ms(80));
+    }
+
+    #[test]
+    fn test_slowest_rate() {
+        let mut pacer = Pacer::new(MIN_FRAMES_PER_SECOND);
+        let start = Instant::now();
+        assert_eq!(pacer.delay(start), Duration::ZERO);
+        assert_eq!(pacer.delay(start), Duration::from_secs(86_400));
+    }
+}
@@ -0,0 +1,421 @@
+//! Encrypted patches: the cells the client edited on a decrypted frame,
+//! encrypted again on their own, so that editing a few cells of a big board
+//! does not mean uploading the whole board.
+//!
+//! The client applies `Edit`s to the decrypted cells, `Patch::new` encrypts
+//! those that differ from the frame, and the server replaces them with
+//! `Board::apply_patch`. A patch file holds, after the header of `keys`, a
+//! checksum of the patch and the patch itself, so that corrupted files are
+//! caught before any cell is replaced.
+
+use crate::keys::{self, FileError};
+use concrete::prelude::*;
+use concrete::{ClientKey, FheBool};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// An edit of the plaintext cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Flips the cell at `(row, col)`.
+    Toggle((usize, usize)),
+    /// Writes `pattern`, `pattern_cols` wide, dead cells included, with its
+    /// top left corner at `(row, col)`.
+    Stamp {
+        pattern: Vec<bool>,
+        pattern_cols: usize,
+        at: (usize, usize),
+    },
+}
+
+#[derive(Debug)]
+pub enum PatchError {
+    /// An edit or a patched cell falls outside the board.
+    OutOfBounds {
+        cell: (usize, usize),
+        dimensions: (usize, usize),
+    },
+    /// The patch was made for a board of other dimensions.
+    Dimensions {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// The patch was made for another generation of the board.
+    Generation {
+        expected: usize,
+        found: usize,
+    },
+    /// The patch does not match its checksum.
+    Checksum,
+    /// A stamped pattern does not fill rows of its width.
+    Pattern {
+        len: usize,
+        pattern_cols: usize,
+    },
+    File(FileError),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { cell, dimensions } => write!(
+                f,
+                "cell ({}, {}) is outside the {}x{} board",
+                cell.0, cell.1, dimensions.0, dimensions.1
+            ),
+            Self::Dimensions { expected, found } => write!(
+                f,
+                "the patch is for a {}x{} board, not a {}x{} one",
+                found.0, found.1, expected.0, expected.1
+            ),
+            Self::Generation { expected, found } => write!(
+                f,
+                "the patch is for generation {}, the board is at generation {}",
+                found, expected
+            ),
+            Self::Checksum => f.write_str("the patch does not match its checksum"),
+            Self::Pattern { len, pattern_cols } => write!(
+                f,
+                "a pattern of {} cells does not fill rows of {} cells",
+                len, pattern_cols
+            ),
+            Self::File(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for PatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::File(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FileError> for PatchError {
+    fn from(err: FileError) -> Self {
+        Self::File(err)
+    }
+}
+
+/// Applies `edits` in order to the plaintext `cells` of a board of
+/// `dimensions`. Stamped patterns must fill whole rows and fit on the board.
+pub fn apply_edits(
+    cells: &mut [bool],
+    dimensions: (usize, usize),
+    edits: &[Edit],
+) -> Result<(), PatchError> {
+    let (n_rows, n_cols) = dimensions;
+    let check = |cell: (usize, usize)| {
+        if cell.0 < n_rows && cell.1 < n_cols {
+            Ok(())
+        } else {
+            Err(PatchError::OutOfBounds { cell, dimensions })
+        }
+    };
+    for edit in edits {
+        match edit {
+            Edit::Toggle(cell) => {
+                check(*cell)?;
+                cells[cell.0 * n_cols + cell.1] ^= true;
+            }
+            Edit::Stamp {
+                pattern,
+                pattern_cols,
+                at,
+            } => {
+                if *pattern_cols == 0 || pattern.len() % pattern_cols != 0 {
+                    return Err(PatchError::Pattern {
+                        len: pattern.len(),
+                        pattern_cols: *pattern_cols,
+                    });
+                }
+                if pattern.is_empty() {
+                    continue;
+                }
+                let pattern_rows = pattern.len() / pattern_cols;
+                check(*at)?;
+                check((at.0 + pattern_rows - 1, at.1 + pattern_cols - 1))?;
+                for (k, alive) in pattern.iter().enumerate() {
+                    cells[(at.0 + k / pattern_cols) * n_cols + at.1 + k % pattern_cols] = *alive;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Freshly encrypted cells to replace in a board of `dimensions` at
+/// `generation`, by their indices in row-major order.
+pub struct Patch {
+    pub(crate) dimensions: (usize, usize),
+    pub(crate) generation: usize,
+    pub(crate) cells: Vec<(usize, FheBool)>,
+}
+
+/// What a patch file holds after its checksum.
+type Saved = ((usize, usize), usize, Vec<(usize, FheBool)>);
+
+/// FNV-1a, enough to catch corrupted files.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+impl Patch {
+    /// Encrypts the cells of `edited` that differ from `frame`, the
+    /// decrypted cells of a board of `dimensions` at `generation`. Runs on
+    /// the client.
+    pub fn new(
+        frame: &[bool],
+        edited: &[bool],
+        dimensions: (usize, usize),
+        generation: usize,
+        client_key: &ClientKey,
+    ) -> Self {
+        assert_eq!(
+            frame.len(),
+            dimensions.0 * dimensions.1,
+            "the frame is not one of the board"
+        );
+        assert_eq!(
+            edited.len(),
+            frame.len(),
+            "the edited cells are not those of the frame"
+        );
+        let cells = frame
+            .iter()
+            .zip(edited)
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(k, (_, &alive))| (k, FheBool::encrypt(alive, client_key)))
+            .collect();
+        Self {
+            dimensions,
+            generation,
+            cells,
+        }
+    }
+
+    /// Number of cells replaced by the patch.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The generation of the board the patch was made for.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Checks that the patch can be applied to a board of `dimensions` at
+    /// `generation`.
+    pub(crate) fn check(
+        &self,
+        dimensions: (usize, usize),
+        generation: usize,
+    ) -> Result<(), PatchError> {
+        if self.dimensions != dimensions {
+            return Err(PatchError::Dimensions {
+                expected: dimensions,
+                found: self.dimensions,
+            });
+        }
+        if self.generation != generation {
+            return Err(PatchError::Generation {
+                expected: generation,
+                found: self.generation,
+            });
+        }
+        let n_cols = dimensions.1;
+        match self.cells.iter().find(|(k, _)| *k >= dimensions.0 * n_cols) {
+            Some((k, _)) => Err(PatchError::OutOfBounds {
+                cell: (k / n_cols, k % n_cols),
+                dimensions,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the patch for keys of the named parameters, see `keys`.
+    pub fn save(&self, path: &Path, parameters: &str) -> Result<(), FileError> {
+        let bytes = bincode::serialize(&(self.dimensions, self.generation, &self.cells))?;
+        keys::write_file(path, parameters, &(checksum(&bytes), bytes), false)
+    }
+
+    /// Reads a patch written by `save` with the same parameters.
+    pub fn load(path: &Path, parameters: &str) -> Result<Self, PatchError> {
+        let (expected, bytes): (u64, Vec<u8>) = keys::read_file(path, parameters)?;
+        if checksum(&bytes) != expected {
+            return Err(PatchError::Checksum);
+        }
+        let (dimensions, generation, cells): Saved =
+            bincode::deserialize(&bytes).map_err(FileError::Format)?;
+        Ok(Self {
+            dimensions,
+            generation,
+            cells,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::GLIDER;
+    use crate::reference::next_generation;
+    use crate::testing::{assert_boards_eq, encrypt_board, set_up_keys};
+
+    #[test]
+    fn test_apply_edits() {
+        let mut cells = vec![false; 4 * 5];
+        let edits = [
+            Edit::Stamp {
+                pattern: GLIDER.to_vec(),
+                pattern_cols: 3,
+                at: (1, 2),
+            },
+            Edit::Toggle((1, 3)),
+            Edit::Toggle((0, 0)),
+        ];
+        apply_edits(&mut cells, (4, 5), &edits).unwrap();
+        let expected: Vec<bool> = "#.... ..... ....# ..###"
+            .split(' ')
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        assert_eq!(cells, expected);
+
+        let stamp = Edit::Stamp {
+            pattern: GLIDER.to_vec(),
+            pattern_cols: 3,
+            at: (2, 2),
+        };
+        let err = apply_edits(&mut cells, (4, 5), &[stamp]).unwrap_err();
+        assert_eq!(err.to_string(), "cell (4, 4) is outside the 4x5 board");
+        assert!(matches!(
+            apply_edits(&mut cells, (4, 5), &[Edit::Toggle((0, 5))]),
+            Err(PatchError::OutOfBounds { cell: (0, 5), .. })
+        ));
+
+        // patterns shorter than a row, or with a partial last row
+        for (len, pattern_cols) in [(2, 3), (4, 3), (1, 0)] {
+            let stamp = Edit::Stamp {
+                pattern: vec![true; len],
+                pattern_cols,
+                at: (3, 4),
+            };
+            let err = apply_edits(&mut cells, (4, 5), &[stamp]).unwrap_err();
+            assert!(matches!(err, PatchError::Pattern { .. }), "{}", err);
+        }
+        assert_eq!(
+            PatchError::Pattern {
+                len: 4,
+                pattern_cols: 3
+            }
+            .to_string(),
+            "a pattern of 4 cells does not fill rows of 3 cells"
+        );
+    }
+
+    #[test]
+    fn test_patch_round_trip() {
+        let client_key = set_up_keys();
+
+        let dimensions = (6, 6);
+        let mut cells = vec![false; 36];
+        apply_edits(
+            &mut cells,
+            dimensions,
+            &[Edit::Stamp {
+                pattern: GLIDER.to_vec(),
+                pattern_cols: 3,
+                at: (0, 0),
+            }],
+        )
+        .unwrap();
+        let mut board = encrypt_board(&cells, 6, &client_key);
+        board.update();
+
+        // the client edits three cells of the decrypted frame
+        let frame = board.decrypt(&client_key);
+        let mut edited = frame.clone();
+        let edits = [(4, 4), (4, 5), (5, 4)].map(Edit::Toggle);
+        apply_edits(&mut edited, dimensions, &edits).unwrap();
+        let patch = Patch::new(&frame, &edited, dimensions, board.generation(), &client_key);
+        assert_eq!(patch.len(), 3);
+
+        let path = std::env::temp_dir().join(format!("patch-{}.bin", std::process::id()));
+        patch.save(&path, "default").unwrap();
+        let patch = Patch::load(&path, "default").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(board.apply_patch(&patch).unwrap(), 3);
+        assert_eq!(board.generation(), 1);
+        assert_boards_eq(&edited, &board.decrypt(&client_key), dimensions, "patched");
+        let mut expected = edited;
+        for generation in 0..2 {
+            board.update();
+            expected = next_generation(&expected, dimensions);
+            assert_boards_eq(
+                &expected,
+                &board.decrypt(&client_key),
+                dimensions,
+                &format!("generation {} after the patch", generation + 2),
+            );
+        }
+    }
+
+    #[test]
+    fn test_patch_errors() {
+        let client_key = set_up_keys();
+
+        let mut board = encrypt_board(&[false; 6], 3, &client_key);
+        let frame = vec![false; 6];
+        let mut edited = frame.clone();
+        edited[4] = true;
+
+        let patch = Patch::new(&frame, &edited, (2, 3), 1, &client_key);
+        let err = board.apply_patch(&patch).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "the patch is for generation 1, the board is at generation 0"
+        );
+        let patch = Patch::new(&edited, &frame, (3, 2), 0, &client_key);
+        assert!(matches!(
+            board.apply_patch(&patch),
+            Err(PatchError::Dimensions {
+                expected: (2, 3),
+                found: (3, 2)
+            })
+        ));
+        let mut patch = Patch::new(&frame, &edited, (2, 3), 0, &client_key);
+        patch.cells[0].0 = 6;
+        assert!(matches!(
+            board.apply_patch(&patch),
+            Err(PatchError::OutOfBounds { cell: (2, 0), .. })
+        ));
+        assert!(board.decrypt(&client_key).iter().all(|alive| !alive));
+
+        let path = std::env::temp_dir().join(format!("bad-patch-{}.bin", std::process::id()));
+        Patch::new(&frame, &edited, (2, 3), 0, &client_key)
+            .save(&path, "default")
+            .unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+        let err = Patch::load(&path, "default").err().unwrap();
+        assert!(matches!(err, PatchError::Checksum));
+        let err = Patch::load(&path, "tfhe-lib").err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            err,
+            PatchError::File(FileError::Parameters { .. })
+        ));
+    }
+}
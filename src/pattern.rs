@@ -0,0 +1,331 @@
+//! Plaintext initial configurations, built on the client before encryption.
+
+use std::error::Error;
+use std::fmt;
+
+/// A glider heading towards the bottom right corner.
+#[rustfmt::skip]
+pub const GLIDER: [bool; 9] = [
+    false, true, false,
+    false, false, true,
+    true, true, true,
+];
+
+/// A well known pattern, drawn with `#` for the live cells and `.` for the
+/// dead ones.
+pub struct CatalogPattern {
+    pub name: &'static str,
+    pub drawing: &'static str,
+    /// Number of generations after which the pattern comes back, possibly
+    /// translated.
+    pub period: usize,
+}
+
+impl CatalogPattern {
+    /// The cells of the pattern and its number of columns.
+    pub fn cells(&self) -> (Vec<bool>, usize) {
+        let n_cols = self.drawing.lines().next().map_or(0, str::len);
+        let cells = self
+            .drawing
+            .lines()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        (cells, n_cols)
+    }
+}
+
+/// Dead cells left around the patterns of the catalog for `--catalog`,
+/// enough for all of them to oscillate without touching their copies across
+/// the edges.
+pub const CATALOG_MARGIN: usize = 4;
+
+/// Still lifes, oscillators and spaceships looked for in the boards.
+pub const CATALOG: [CatalogPattern; 8] = [
+    CatalogPattern {
+        name: "block",
+        drawing: "##\n##",
+        period: 1,
+    },
+    CatalogPattern {
+        name: "beehive",
+        drawing: ".##.\n#..#\n.##.",
+        period: 1,
+    },
+    CatalogPattern {
+        name: "blinker",
+        drawing: "###",
+        period: 2,
+    },
+    CatalogPattern {
+        name: "toad",
+        drawing: ".###\n###.",
+        period: 2,
+    },
+    CatalogPattern {
+        name: "beacon",
+        drawing: "##..\n##..\n..##\n..##",
+        period: 2,
+    },
+    CatalogPattern {
+        name: "pulsar",
+        drawing: "..###...###..\n\
+                  .............\n\
+                  #....#.#....#\n\
+                  #....#.#....#\n\
+                  #....#.#....#\n\
+                  ..###...###..\n\
+                  .............\n\
+                  ..###...###..\n\
+                  #....#.#....#\n\
+                  #....#.#....#\n\
+                  #....#.#....#\n\
+                  .............\n\
+                  ..###...###..",
+        period: 3,
+    },
+    CatalogPattern {
+        name: "pentadecathlon",
+        drawing: "..#....#..\n##.####.##\n..#....#..",
+        period: 15,
+    },
+    CatalogPattern {
+        name: "glider",
+        drawing: ".#.\n..#\n###",
+        period: 4,
+    },
+];
+
+/// Looks a pattern of the catalog up by name.
+pub fn catalog_pattern(name: &str) -> Option<&'static CatalogPattern> {
+    CATALOG.iter().find(|pattern| pattern.name == name)
+}
+
+/// Puts `pattern` in the middle of an empty board leaving `margin` dead
+/// cells on every side. Returns the cells and the dimensions.
+pub fn centered(
+    pattern: &[bool],
+    pattern_cols: usize,
+    margin: usize,
+) -> (Vec<bool>, (usize, usize)) {
+    let pattern_rows = pattern.len() / pattern_cols;
+    let dimensions = (pattern_rows + 2 * margin, pattern_cols + 2 * margin);
+
+    let mut cells = vec![false; dimensions.0 * dimensions.1];
+    for (k, alive) in pattern.iter().enumerate() {
+        cells[(k / pattern_cols + margin) * dimensions.1 + k % pattern_cols + margin] = *alive;
+    }
+    (cells, dimensions)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// The pattern does not fit in the space it is given.
+    DoesNotFit {
+        pattern: (usize, usize),
+        space: (usize, usize),
+    },
+    /// The number of cells is not a multiple of the number of columns.
+    Ragged { len: usize, n_cols: usize },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DoesNotFit { pattern, space } => write!(
+                f,
+                "a {}x{} pattern does not fit in {}x{} cells",
+                pattern.0, pattern.1, space.0, space.1
+            ),
+            Self::Ragged { len, n_cols } => {
+                write!(f, "{} cells cannot be split in rows of {}", len, n_cols)
+            }
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+/// Repeats `pattern` over a board of the given dimensions, one copy every
+/// `stride` rows and columns, starting from the top left corner.
+///
+/// Copies reaching the edges wrap around, consistently with the periodic
+/// boundary conditions of the board.
+pub fn tile(
+    pattern: &[bool],
+    pattern_cols: usize,
+    dimensions: (usize, usize),
+    stride: (usize, usize),
+) -> Result<Vec<bool>, PatternError> {
+    if pattern_cols == 0 || !pattern.len().is_multiple_of(pattern_cols) {
+        return Err(PatternError::Ragged {
+            len: pattern.len(),
+            n_cols: pattern_cols,
+        });
+    }
+    let pattern_rows = pattern.len() / pattern_cols;
+    if pattern_rows > stride.0 || pattern_cols > stride.1 {
+        return Err(PatternError::DoesNotFit {
+            pattern: (pattern_rows, pattern_cols),
+            space: stride,
+        });
+    }
+
+    let (n_rows, n_cols) = dimensions;
+    let mut cells = vec![false; n_rows * n_cols];
+    for origin_row in (0..n_rows).step_by(stride.0) {
+        for origin_col in (0..n_cols).step_by(stride.1) {
+            for (k, alive) in pattern.iter().enumerate() {
+                let i = (origin_row + k / pattern_cols) % n_rows;
+                let j = (origin_col + k % pattern_cols) % n_cols;
+                cells[i * n_cols + j] |= *alive;
+            }
+        }
+    }
+
+    Ok(cells)
+}
+
+/// Puts `pattern` on an empty board of the given dimensions, with its top
+/// left corner at `offset`, or in the middle of the board.
+pub fn place(
+    pattern: &[bool],
+    pattern_cols: usize,
+    dimensions: (usize, usize),
+    offset: Option<(usize, usize)>,
+) -> Result<Vec<bool>, PatternError> {
+    if pattern_cols == 0 || !pattern.len().is_multiple_of(pattern_cols) {
+        return Err(PatternError::Ragged {
+            len: pattern.len(),
+            n_cols: pattern_cols,
+        });
+    }
+    let pattern_rows = pattern.len() / pattern_cols;
+    let (n_rows, n_cols) = dimensions;
+    let (row, col) = offset.unwrap_or((
+        n_rows.saturating_sub(pattern_rows) / 2,
+        n_cols.saturating_sub(pattern_cols) / 2,
+    ));
+    let space = (n_rows.saturating_sub(row), n_cols.saturating_sub(col));
+    if pattern_rows > space.0 || pattern_cols > space.1 {
+        return Err(PatternError::DoesNotFit {
+            pattern: (pattern_rows, pattern_cols),
+            space,
+        });
+    }
+
+    let mut cells = vec![false; n_rows * n_cols];
+    for (k, alive) in pattern.iter().enumerate() {
+        cells[(row + k / pattern_cols) * n_cols + col + k % pattern_cols] = *alive;
+    }
+    Ok(cells)
+}
+
+/// Checks that every `stride` sized tile of the board holds the same cells.
+///
+/// The dimensions must be multiples of the stride.
+pub fn tiles_identical(cells: &[bool], dimensions: (usize, usize), stride: (usize, usize)) -> bool {
+    let (n_rows, n_cols) = dimensions;
+    (0..n_rows).all(|i| {
+        (0..n_cols).all(|j| cells[i * n_cols + j] == cells[(i % stride.0) * n_cols + j % stride.1])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::find_periodicity;
+    use crate::reference::next_generation;
+    use crate::testing::assert_boards_eq;
+
+    #[test]
+    fn test_tile_rejects_large_pattern() {
+        assert_eq!(
+            tile(&GLIDER, 3, (8, 8), (2, 4)),
+            Err(PatternError::DoesNotFit {
+                pattern: (3, 3),
+                space: (2, 4)
+            })
+        );
+        assert_eq!(
+            tile(&GLIDER, 2, (8, 8), (4, 4)),
+            Err(PatternError::Ragged { len: 9, n_cols: 2 })
+        );
+    }
+
+    #[test]
+    fn test_place() {
+        let centered = place(&[true, true], 2, (3, 4), None).unwrap();
+        assert_eq!(crate::live_coordinates(&centered, 4), vec![(1, 1), (1, 2)]);
+        let corner = place(&[true, true], 2, (3, 4), Some((2, 2))).unwrap();
+        assert_eq!(crate::live_coordinates(&corner, 4), vec![(2, 2), (2, 3)]);
+
+        assert_eq!(
+            place(&GLIDER, 3, (4, 4), Some((0, 2))),
+            Err(PatternError::DoesNotFit {
+                pattern: (3, 3),
+                space: (4, 2)
+            })
+        );
+        assert_eq!(
+            place(&GLIDER, 3, (2, 8), None),
+            Err(PatternError::DoesNotFit {
+                pattern: (3, 3),
+                space: (2, 6)
+            })
+        );
+    }
+
+    #[test]
+    fn test_tile_wraps_at_edges() {
+        let cells = tile(&[true, true], 2, (2, 3), (1, 2)).unwrap();
+
+        #[rustfmt::skip]
+        assert_eq!(cells, vec![
+            true, true, true,
+            true, true, true,
+        ]);
+    }
+
+    #[test]
+    fn test_tiled_gliders_advance_identically() {
+        let dimensions = (10, 10);
+        let stride = (5, 5);
+        let mut cells = tile(&GLIDER, 3, dimensions, stride).unwrap();
+
+        for _ in 0..4 {
+            cells = next_generation(&cells, dimensions);
+            assert!(tiles_identical(&cells, dimensions, stride));
+        }
+
+        // after 4 generations every glider moved one cell down and right
+        let mut shifted = vec![false; 25];
+        for (k, alive) in GLIDER.iter().enumerate() {
+            shifted[(k / 3 + 1) * 5 + k % 3 + 1] = *alive;
+        }
+        let expected = tile(&shifted, 5, dimensions, stride).unwrap();
+        assert_boards_eq(&expected, &cells, dimensions, "tiled gliders");
+    }
+
+    #[test]
+    fn test_catalog_periods() {
+        for pattern in &CATALOG {
+            let (cells, n_cols) = pattern.cells();
+            assert_eq!(cells.len() % n_cols, 0, "{} is ragged", pattern.name);
+            let (mut cells, dimensions) = centered(&cells, n_cols, 4);
+
+            let mut history = vec![cells.clone()];
+            for _ in 0..2 * pattern.period {
+                cells = next_generation(&cells, dimensions);
+                history.push(cells.clone());
+            }
+
+            let periodicity = find_periodicity(&history, dimensions).unwrap();
+            assert_eq!(periodicity.period, pattern.period, "{}", pattern.name);
+        }
+        assert_eq!(
+            catalog_pattern("glider").unwrap().cells(),
+            (GLIDER.to_vec(), 3)
+        );
+        assert!(catalog_pattern("gosper gun").is_none());
+    }
+}
@@ -0,0 +1,385 @@
+//! Patterns shared by the Life community, read from run length encoded
+//! `.rle` files or plaintext `.cells` files:
+//!
+//! ```text
+//! #N Glider            !Name: Glider
+//! x = 3, y = 3         .O.
+//! bo$2bo$3o!           ..O
+//!                      OOO
+//! ```
+
+use crate::rule::Rule;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// Longest line of the RLE files written, as is customary.
+const RLE_LINE_LEN: usize = 70;
+
+/// Most cells of a pattern, far more than can be encrypted, so that a
+/// corrupted header does not make us allocate anything it says.
+const MAX_CELLS: usize = 1 << 24;
+
+#[derive(Debug)]
+pub enum PatternFileError {
+    Io(std::io::Error),
+    /// The file is neither `.rle` nor `.cells`.
+    UnknownFormat(String),
+    Syntax {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for PatternFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the pattern: {}", err),
+            Self::UnknownFormat(path) => {
+                write!(f, "{} is neither a .rle nor a .cells pattern", path)
+            }
+            Self::Syntax { line, message } => {
+                write!(f, "invalid pattern, line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl Error for PatternFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PatternFileError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+fn syntax_error(line: usize, message: String) -> PatternFileError {
+    PatternFileError::Syntax { line, message }
+}
+
+/// The cells of a pattern, in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternFile {
+    pub cells: Vec<bool>,
+    pub dimensions: (usize, usize),
+    /// The rule of the `rule =` field of an RLE header, if any.
+    pub rule: Option<Rule>,
+}
+
+impl PatternFile {
+    /// Reads a pattern, in the format given by the extension of the file.
+    pub fn load(path: &Path) -> Result<Self, PatternFileError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("rle") => Self::from_rle(&std::fs::read_to_string(path)?),
+            Some("cells") => Self::from_cells(&std::fs::read_to_string(path)?),
+            _ => Err(PatternFileError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    /// Parses a run length encoded pattern: `#` comment lines, a header
+    /// giving the width `x`, the height `y` and possibly the rule, then runs
+    /// of dead `b` and live `o` cells, rows ending with `$` and the pattern
+    /// with `!`. Runs can span several lines.
+    pub fn from_rle(text: &str) -> Result<Self, PatternFileError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(k, line)| (k + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        let (header_line, header) = lines
+            .next()
+            .ok_or_else(|| syntax_error(1, "missing header".to_string()))?;
+
+        let (mut width, mut height, mut rule) = (None, None, None);
+        for field in header.split(',') {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| syntax_error(header_line, format!("invalid field '{}'", field)))?;
+            let value = value.trim();
+            let size = || {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| syntax_error(header_line, format!("invalid size '{}'", value)))
+            };
+            match key.trim() {
+                "x" => width = Some(size()?),
+                "y" => height = Some(size()?),
+                "rule" => {
+                    rule = Some(
+                        value
+                            .parse()
+                            .map_err(|err| syntax_error(header_line, err))?,
+                    )
+                }
+                other => {
+                    return Err(syntax_error(
+                        header_line,
+                        format!("unknown field '{}'", other),
+                    ))
+                }
+            }
+        }
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(syntax_error(header_line, "missing x or y".to_string()));
+        };
+        if width
+            .checked_mul(height)
+            .is_none_or(|n_cells| n_cells > MAX_CELLS)
+        {
+            return Err(syntax_error(
+                header_line,
+                format!("a {}x{} pattern is too large", width, height),
+            ));
+        }
+
+        let mut cells = vec![false; width * height];
+        let (mut row, mut col) = (0, 0);
+        let mut run: Option<usize> = None;
+        for (line, text) in lines {
+            for token in text.chars().filter(|c| !c.is_whitespace()) {
+                if let Some(digit) = token.to_digit(10) {
+                    run = run
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|run| run.checked_add(digit as usize));
+                    if run.is_none() {
+                        return Err(syntax_error(line, "run count too large".to_string()));
+                    }
+                    continue;
+                }
+                let count = run.take().unwrap_or(1);
+                match token {
+                    'b' | 'o' => {
+                        if count > width - col {
+                            return Err(syntax_error(
+                                line,
+                                format!("row {} is wider than x = {}", row, width),
+                            ));
+                        }
+                        if token == 'o' {
+                            if row >= height {
+                                return Err(syntax_error(
+                                    line,
+                                    format!("more rows than y = {}", height),
+                                ));
+                            }
+                            cells[row * width + col..][..count].fill(true);
+                        }
+                        col += count;
+                    }
+                    '$' => {
+                        row = row.saturating_add(count);
+                        col = 0;
+                    }
+                    '!' => {
+                        return Ok(Self {
+                            cells,
+                            dimensions: (height, width),
+                            rule,
+                        })
+                    }
+                    other => {
+                        return Err(syntax_error(line, format!("unexpected '{}'", other)));
+                    }
+                }
+            }
+        }
+        Err(syntax_error(
+            text.lines().count(),
+            "missing '!' at the end".to_string(),
+        ))
+    }
+
+    /// Parses a plaintext pattern: `!` comment lines, then rows of dead `.`
+    /// and live `O` cells. Rows shorter than the widest one end with dead
+    /// cells.
+    pub fn from_cells(text: &str) -> Result<Self, PatternFileError> {
+        let rows: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(k, line)| (k + 1, line.trim_end()))
+            .filter(|(_, line)| !line.starts_with('!'))
+            .collect();
+        let width = rows.iter().map(|(_, row)| row.len()).max().unwrap_or(0);
+
+        let mut cells = Vec::with_capacity(rows.len() * width);
+        for (line, row) in &rows {
+            for c in row.chars() {
+                match c {
+                    '.' => cells.push(false),
+                    'O' | '*' => cells.push(true),
+                    other => {
+                        return Err(syntax_error(*line, format!("unexpected '{}'", other)));
+                    }
+                }
+            }
+            cells.resize(cells.len() + width - row.len(), false);
+        }
+        Ok(Self {
+            cells,
+            dimensions: (rows.len(), width),
+            rule: None,
+        })
+    }
+}
+
+/// Writes cells as a run length encoded pattern, for `PatternFile::from_rle`
+/// and the other Life programs. Dead cells at the end of rows and empty rows
+/// at the bottom are left out.
+pub fn to_rle(cells: &[bool], n_cols: usize, rule: &Rule) -> String {
+    let n_rows = cells.len() / n_cols;
+    let mut tokens = Vec::new();
+    let mut push = |count: usize, tag: char| match count {
+        0 => {}
+        1 => tokens.push(tag.to_string()),
+        _ => tokens.push(format!("{}{}", count, tag)),
+    };
+    let mut pending_rows = 0;
+    for row in cells.chunks(n_cols) {
+        let mut runs: Vec<(bool, usize)> = Vec::new();
+        for &alive in row {
+            match runs.last_mut() {
+                Some((state, count)) if *state == alive => *count += 1,
+                _ => runs.push((alive, 1)),
+            }
+        }
+        if runs.last().is_some_and(|(alive, _)| !alive) {
+            runs.pop();
+        }
+        if !runs.is_empty() {
+            push(pending_rows, '$');
+            pending_rows = 0;
+            for (alive, count) in runs {
+                push(count, if alive { 'o' } else { 'b' });
+            }
+        }
+        pending_rows += 1;
+    }
+    tokens.push("!".to_string());
+
+    let mut rle = format!("x = {}, y = {}, rule = {}\n", n_cols, n_rows, rule);
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len + token.len() > RLE_LINE_LEN {
+            rle.push('\n');
+            line_len = 0;
+        }
+        line_len += token.len();
+        rle += &token;
+    }
+    rle.push('\n');
+    rle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::{catalog_pattern, GLIDER};
+
+    #[test]
+    fn test_rle() {
+        let glider = PatternFile::from_rle(
+            "#N Glider\n\
+             #C A comment\n\
+             x = 3, y = 3, rule = B3/S23\n\
+             bo$2bo$3o!",
+        )
+        .unwrap();
+        assert_eq!(glider.cells, GLIDER);
+        assert_eq!(glider.dimensions, (3, 3));
+        assert_eq!(glider.rule, Some(Rule::conway()));
+
+        let blinker = PatternFile::from_rle("x = 3, y = 1\n3o!").unwrap();
+        assert_eq!(blinker.cells, catalog_pattern("blinker").unwrap().cells().0);
+        assert_eq!(blinker.rule, None);
+
+        // runs spanning lines, a trailing dead run and an empty row
+        #[rustfmt::skip]
+        let lwss = [
+            false, true, false, false, true,
+            true, false, false, false, false,
+            true, false, false, false, true,
+            true, true, true, true, false,
+            false, false, false, false, false,
+        ];
+        let parsed = PatternFile::from_rle("x = 5, y = 5\nbo2bo$o4b$o3bo$\n4o$\n!").unwrap();
+        assert_eq!(parsed.cells, lwss);
+        let parsed = PatternFile::from_rle("x = 5, y = 5\nbo2bo$o4b$o3bo$4\no2$!").unwrap();
+        assert_eq!(parsed.cells, lwss);
+    }
+
+    #[test]
+    fn test_to_rle() {
+        let conway = Rule::conway();
+        assert_eq!(
+            to_rle(&GLIDER, 3, &conway),
+            "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+
+        // empty rows inside and below the pattern, and a long line
+        let mut cells = vec![false; 5 * 100];
+        cells[0] = true;
+        cells[3 * 100 + 1] = true;
+        for col in (0..100).step_by(2) {
+            cells[4 * 100 + col] = true;
+        }
+        let rle = to_rle(&cells, 100, &conway);
+        assert!(rle.starts_with("x = 100, y = 5, rule = B3/S23\no3$bo$obobo"));
+        assert!(rle.lines().all(|line| line.len() <= RLE_LINE_LEN));
+        let parsed = PatternFile::from_rle(&rle).unwrap();
+        assert_eq!(parsed.cells, cells);
+        assert_eq!(parsed.rule, Some(conway));
+
+        let empty = PatternFile::from_rle(&to_rle(&[false; 6], 3, &conway)).unwrap();
+        assert_eq!(empty.cells, [false; 6]);
+        assert_eq!(empty.dimensions, (2, 3));
+    }
+
+    #[test]
+    fn test_cells() {
+        let glider = PatternFile::from_cells("!Name: Glider\n!\n.O.\n..O\nOOO\n").unwrap();
+        assert_eq!(glider.cells, GLIDER);
+        assert_eq!(glider.dimensions, (3, 3));
+
+        // trailing dead cells are often left out
+        let glider = PatternFile::from_cells(".O\n..O\nOOO").unwrap();
+        assert_eq!(glider.cells, GLIDER);
+    }
+
+    #[test]
+    fn test_errors() {
+        let line = |result: Result<PatternFile, PatternFileError>| match result {
+            Err(PatternFileError::Syntax { line, .. }) => line,
+            other => panic!("expected a syntax error, got {:?}", other),
+        };
+        assert_eq!(line(PatternFile::from_rle("#C empty\n")), 1);
+        assert_eq!(line(PatternFile::from_rle("x = 3\nbo$2bo$3o!")), 1);
+        assert_eq!(line(PatternFile::from_rle("x = 2, y = 3\nbo$2bo$3o!")), 2);
+        assert_eq!(line(PatternFile::from_rle("x = 3, y = 2\nbo$2bo$3o!")), 2);
+        assert_eq!(line(PatternFile::from_rle("x = 3, y = 3\nbo$2bo$\n3o")), 3);
+        assert_eq!(line(PatternFile::from_rle("x = 3, y = 3\nbo$2bx$3o!")), 2);
+        assert_eq!(
+            line(PatternFile::from_rle(
+                "x = 3, y = 3\n99999999999999999999999o!"
+            )),
+            2
+        );
+        assert_eq!(line(PatternFile::from_rle("x = 100000, y = 100000\n!")), 1);
+        assert_eq!(line(PatternFile::from_cells(".O.\n..O\nO#O")), 3);
+        assert!(matches!(
+            PatternFile::load(Path::new("glider.txt")),
+            Err(PatternFileError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            PatternFile::load(Path::new("/nonexistent/glider.rle")),
+            Err(PatternFileError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+//! Sampled timing of the phases of the cell updates, to see where the time
+//! of a generation goes: gathering the neighbours, counting them, and
+//! applying the rule to the count.
+//!
+//! With the `phase-timer` feature, `set_sampling(Some(n))` times the phases
+//! of every `n`th cell updated on the thread, and `take_profile` returns what
+//! was timed since its last call, once per generation. Without the feature,
+//! `time` is the closure it runs and `start_cell` does nothing.
+
+use std::fmt;
+use std::time::Duration;
+#[cfg(feature = "phase-timer")]
+use std::{cell::RefCell, time::Instant};
+
+/// A phase of the update of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Picking the neighbours of the cell, boundary conditions included.
+    Gather,
+    /// Adding up the live neighbours.
+    Count,
+    /// The gates from the count and the cell to its next state.
+    Rule,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Gather, Phase::Count, Phase::Rule];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gather => "gathering",
+            Self::Count => "counting",
+            Self::Rule => "rule",
+        })
+    }
+}
+
+/// Phase times of the sampled cells among those updated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhaseProfile {
+    cells: usize,
+    sampled: usize,
+    totals: [Duration; 3],
+}
+
+impl PhaseProfile {
+    /// Counts an updated cell, whose phases are recorded if it is `sampled`.
+    pub fn add_cell(&mut self, sampled: bool) {
+        self.cells += 1;
+        self.sampled += usize::from(sampled);
+    }
+
+    /// Adds `duration` to the time of `phase` of the sampled cells.
+    pub fn record(&mut self, phase: Phase, duration: Duration) {
+        self.totals[phase.index()] += duration;
+    }
+
+    /// Adds the cells and times of `other`, such as another generation.
+    pub fn merge(&mut self, other: &PhaseProfile) {
+        self.cells += other.cells;
+        self.sampled += other.sampled;
+        for (total, other) in self.totals.iter_mut().zip(other.totals) {
+            *total += other;
+        }
+    }
+
+    /// Number of cells updated.
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+
+    /// Number of cells whose phases were timed.
+    pub fn sampled(&self) -> usize {
+        self.sampled
+    }
+
+    /// Mean time of `phase` per sampled cell.
+    pub fn mean(&self, phase: Phase) -> Duration {
+        match self.sampled {
+            0 => Duration::ZERO,
+            sampled => self.totals[phase.index()] / sampled as u32,
+        }
+    }
+
+    /// Time of `phase` over all the cells, extrapolated from the sampled
+    /// ones.
+    pub fn estimate(&self, phase: Phase) -> Duration {
+        self.mean(phase) * self.cells as u32
+    }
+
+    /// Fraction of the time of the sampled cells spent in `phase`.
+    pub fn share(&self, phase: Phase) -> f64 {
+        let total: Duration = self.totals.iter().sum();
+        if total.is_zero() {
+            return 0.0;
+        }
+        self.totals[phase.index()].as_secs_f64() / total.as_secs_f64()
+    }
+}
+
+impl fmt::Display for PhaseProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let phases: Vec<String> = Phase::ALL
+            .iter()
+            .map(|&phase| {
+                format!(
+                    "{} {:.3?} ({:.1}%)",
+                    phase,
+                    self.mean(phase),
+                    100.0 * self.share(phase)
+                )
+            })
+            .collect();
+        write!(
+            f,
+            "{} per cell, {} of {} cells sampled",
+            phases.join(", "),
+            self.sampled,
+            self.cells
+        )
+    }
+}
+
+#[cfg(feature = "phase-timer")]
+#[derive(Default)]
+struct Sampler {
+    /// Cells between two sampled ones, 0 when not sampling.
+    every: usize,
+    seen: usize,
+    /// Whether the cell being updated is sampled.
+    sampling: bool,
+    profile: PhaseProfile,
+}
+
+#[cfg(feature = "phase-timer")]
+thread_local! {
+    static SAMPLER: RefCell<Sampler> = RefCell::default();
+}
+
+/// Times the phases of every `every`th cell updated on this thread from now
+/// on, or none. Drops what was timed so far.
+#[cfg(feature = "phase-timer")]
+pub fn set_sampling(every: Option<usize>) {
+    assert_ne!(every, Some(0), "cannot sample every 0th cell");
+    SAMPLER.with(|sampler| {
+        *sampler.borrow_mut() = Sampler {
+            every: every.unwrap_or(0),
+            ..Sampler::default()
+        }
+    });
+}
+
+/// What was timed on this thread since the last call, or `set_sampling`.
+#[cfg(feature = "phase-timer")]
+pub fn take_profile() -> PhaseProfile {
+    SAMPLER.with(|sampler| {
+        let sampler = &mut *sampler.borrow_mut();
+        sampler.sampling = false;
+        std::mem::take(&mut sampler.profile)
+    })
+}
+
+/// Starts the update of a cell, whose phases are timed if it is sampled.
+#[cfg(feature = "phase-timer")]
+pub fn start_cell() {
+    SAMPLER.with(|sampler| {
+        let sampler = &mut *sampler.borrow_mut();
+        if sampler.every == 0 {
+            return;
+        }
+        sampler.sampling = sampler.seen.is_multiple_of(sampler.every);
+        sampler.seen += 1;
+        sampler.profile.add_cell(sampler.sampling);
+    });
+}
+
+#[cfg(not(feature = "phase-timer"))]
+#[inline(always)]
+pub fn start_cell() {}
+
+/// Runs `f`, timing it as `phase` of the cell being updated if it is
+/// sampled.
+#[cfg(feature = "phase-timer")]
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !SAMPLER.with(|sampler| sampler.borrow().sampling) {
+        return f();
+    }
+    let start = Instant::now();
+    let value = f();
+    let elapsed = start.elapsed();
+    SAMPLER.with(|sampler| sampler.borrow_mut().profile.record(phase, elapsed));
+    value
+}
+
+#[cfg(not(feature = "phase-timer"))]
+#[inline(always)]
+pub fn time<T>(_phase: Phase, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn micros(n: u64) -> Duration {
+        Duration::from_micros(n)
+    }
+
+    #[test]
+    fn test_profile() {
+        let mut profile = PhaseProfile::default();
+        for k in 0..10 {
+            profile.add_cell(k % 5 == 0);
+        }
+        for (gather, count, rule) in [(1, 30, 9), (3, 50, 7)] {
+            profile.record(Phase::Gather, micros(gather));
+            profile.record(Phase::Count, micros(count));
+            profile.record(Phase::Rule, micros(rule));
+        }
+        assert_eq!((profile.cells(), profile.sampled()), (10, 2));
+        assert_eq!(profile.mean(Phase::Count), micros(40));
+        assert_eq!(profile.estimate(Phase::Count), micros(400));
+        assert_eq!(profile.estimate(Phase::Gather), micros(20));
+        assert!((profile.share(Phase::Rule) - 0.16).abs() < 1e-9);
+        assert_eq!(
+            profile.to_string(),
+            "gathering 2.000µs (4.0%), counting 40.000µs (80.0%), rule 8.000µs (16.0%) \
+             per cell, 2 of 10 cells sampled"
+        );
+
+        // a second generation
+        let mut next = PhaseProfile::default();
+        next.add_cell(true);
+        next.add_cell(false);
+        next.record(Phase::Count, micros(40));
+        profile.merge(&next);
+        assert_eq!((profile.cells(), profile.sampled()), (12, 3));
+        assert_eq!(profile.mean(Phase::Count), micros(40));
+        assert_eq!(profile.mean(Phase::Rule), micros(16) / 3);
+
+        assert_eq!(PhaseProfile::default().mean(Phase::Rule), Duration::ZERO);
+        assert_eq!(PhaseProfile::default().share(Phase::Rule), 0.0);
+    }
+
+    #[cfg(feature = "phase-timer")]
+    #[test]
+    fn test_sampling() {
+        let update = || {
+            for _ in 0..10 {
+                start_cell();
+                let count = time(Phase::Count, || 3);
+                time(Phase::Rule, || count == 3);
+            }
+        };
+        set_sampling(Some(4));
+        update();
+        let profile = take_profile();
+        // cells 0, 4 and 8
+        assert_eq!((profile.cells(), profile.sampled()), (10, 3));
+        assert_eq!(profile.mean(Phase::Gather), Duration::ZERO);
+        assert_eq!(take_profile(), PhaseProfile::default());
+
+        set_sampling(None);
+        update();
+        assert_eq!(take_profile(), PhaseProfile::default());
+    }
+
+    #[cfg(feature = "phase-timer")]
+    #[test]
+    #[ignore = "times 6 updates of a 6x6 board"]
+    fn test_sampling_overhead() {
+        use crate::benchgen::{generate, BoardProfile};
+        use crate::testing::{encrypt_board, set_up_keys};
+
+        let client_key = set_up_keys();
+        let cells = generate(&BoardProfile::Soup { density: 0.4 }, (6, 6), 5);
+        let board = encrypt_board(&cells, 6, &client_key);
+
+        let fastest_update = |every| {
+            set_sampling(every);
+            (0..3)
+                .map(|_| {
+                    let mut board = board.clone();
+                    let start = Instant::now();
+                    board.update();
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        };
+        let unsampled = fastest_update(None);
+        let sampled = fastest_update(Some(8));
+        assert_eq!(take_profile().sampled(), (3 * 36usize).div_ceil(8));
+        assert!(
+            sampled.as_secs_f64() < 1.05 * unsampled.as_secs_f64(),
+            "{:?} with sampling, {:?} without",
+            sampled,
+            unsampled
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Fast plaintext engine for `--plaintext`, to preview the dynamics of a
+//! board without any encryption.
+//!
+//! Rows are packed 64 cells per word and the neighbours of all the cells of a
+//! word are added at once with the gates of the encrypted accumulator.
+
+use crate::rule::Rule;
+use crate::BoundaryCondition;
+
+/// A board of plaintext cells, packed by rows, playing Conway's rule on a
+/// torus unless told otherwise, like `Board`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedBoard {
+    dimensions: (usize, usize),
+    words_per_row: usize,
+    words: Vec<u64>,
+    rule: Rule,
+    boundary: BoundaryCondition,
+}
+
+impl PackedBoard {
+    pub fn new(cells: &[bool], dimensions: (usize, usize)) -> Self {
+        let (n_rows, n_cols) = dimensions;
+        assert_eq!(cells.len(), n_rows * n_cols, "wrong number of cells");
+        let words_per_row = n_cols.div_ceil(64);
+        let mut words = vec![0; n_rows * words_per_row];
+        for (k, _) in cells.iter().enumerate().filter(|(_, alive)| **alive) {
+            let (i, j) = (k / n_cols, k % n_cols);
+            words[i * words_per_row + j / 64] |= 1 << (j % 64);
+        }
+        Self {
+            dimensions,
+            words_per_row,
+            words,
+            rule: Rule::conway(),
+            boundary: BoundaryCondition::Toroidal,
+        }
+    }
+
+    /// Plays `rule` instead of Conway's.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Uses `boundary` instead of periodic boundary conditions.
+    pub fn with_boundary(mut self, boundary: BoundaryCondition) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The cells in row-major order.
+    pub fn cells(&self) -> Vec<bool> {
+        let (n_rows, n_cols) = self.dimensions;
+        (0..n_rows * n_cols)
+            .map(|k| {
+                let (i, j) = (k / n_cols, k % n_cols);
+                self.words[i * self.words_per_row + j / 64] >> (j % 64) & 1 == 1
+            })
+            .collect()
+    }
+
+    pub fn population(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    fn row(&self, i: usize) -> &[u64] {
+        &self.words[i * self.words_per_row..][..self.words_per_row]
+    }
+
+    /// The row `i + offset`, beyond the edges the opposite row, a dead row
+    /// or row `i` itself, depending on the boundary conditions.
+    fn row_at(&self, i: usize, offset: isize) -> Vec<u64> {
+        let n_rows = self.dimensions.0;
+        match i.checked_add_signed(offset).filter(|&k| k < n_rows) {
+            Some(k) => self.row(k).to_vec(),
+            None => match self.boundary {
+                BoundaryCondition::Toroidal => self
+                    .row((i as isize + offset).rem_euclid(n_rows as isize) as usize)
+                    .to_vec(),
+                BoundaryCondition::Fixed => vec![0; self.words_per_row],
+                BoundaryCondition::Mirror => self.row(i).to_vec(),
+            },
+        }
+    }
+
+    /// The row with every cell replaced by its western neighbour.
+    fn western_neighbours(&self, row: &[u64]) -> Vec<u64> {
+        let n_cols = self.dimensions.1;
+        let last_bit = (n_cols - 1) % 64;
+        let wrapped = match self.boundary {
+            BoundaryCondition::Toroidal => row[row.len() - 1] >> last_bit & 1,
+            BoundaryCondition::Fixed => 0,
+            BoundaryCondition::Mirror => row[0] & 1,
+        };
+        let mut shifted: Vec<u64> = (0..row.len())
+            .map(|w| {
+                let carry = if w == 0 { wrapped } else { row[w - 1] >> 63 };
+                row[w] << 1 | carry
+            })
+            .collect();
+        *shifted.last_mut().unwrap() &= u64::MAX >> (63 - last_bit);
+        shifted
+    }
+
+    /// The row with every cell replaced by its eastern neighbour.
+    fn eastern_neighbours(&self, row: &[u64]) -> Vec<u64> {
+        let last_bit = (self.dimensions.1 - 1) % 64;
+        let wrapped = match self.boundary {
+            BoundaryCondition::Toroidal => row[0] & 1,
+            BoundaryCondition::Fixed => 0,
+            BoundaryCondition::Mirror => row[row.len() - 1] >> last_bit & 1,
+        };
+        (0..row.len())
+            .map(|w| {
+                let carry = if w == row.len() - 1 {
+                    wrapped << last_bit
+                } else {
+                    row[w + 1] << 63
+                };
+                row[w] >> 1 | carry
+            })
+            .collect()
+    }
+
+    /// Plays one generation of the game.
+    pub fn update(&mut self) {
+        let (n_rows, n_cols) = self.dimensions;
+        let last_word = u64::MAX >> (63 - (n_cols - 1) % 64);
+        let mut next = Vec::with_capacity(self.words.len());
+        for i in 0..n_rows {
+            let above = self.row_at(i, -1);
+            let below = self.row_at(i, 1);
+            let row = self.row(i);
+            let neighbours = [
+                self.western_neighbours(&above),
+                above.clone(),
+                self.eastern_neighbours(&above),
+                self.western_neighbours(row),
+                self.eastern_neighbours(row),
+                self.western_neighbours(&below),
+                self.eastern_neighbours(&below),
+                below,
+            ];
+
+            for (w, cell) in row.iter().enumerate() {
+                // 3-bit counters, 8 neighbours wrapping to 0 as in the
+                // encrypted accumulator
+                let (mut s0, mut s1, mut s2) = (0u64, 0u64, 0u64);
+                for neighbour in &neighbours {
+                    let carry0 = s0 & neighbour[w];
+                    s0 ^= neighbour[w];
+                    let carry1 = s1 & carry0;
+                    s1 ^= carry0;
+                    s2 ^= carry1;
+                }
+                let state = if self.rule == Rule::conway() {
+                    s1 & !s2 & (s0 | cell)
+                } else {
+                    // 8 live neighbours wrap to 0, told apart by the cells
+                    // alive among all of them
+                    let all_alive = neighbours.iter().fold(u64::MAX, |all, n| all & n[w]);
+                    let count_is = |count: usize| {
+                        let bit = |s: u64, b: usize| if count >> b & 1 == 1 { s } else { !s };
+                        match count {
+                            8 => all_alive,
+                            0 => bit(s0, 0) & bit(s1, 1) & bit(s2, 2) & !all_alive,
+                            _ => bit(s0, 0) & bit(s1, 1) & bit(s2, 2),
+                        }
+                    };
+                    let any_of = |counts: u16| {
+                        (0..=8)
+                            .filter(|count| counts >> count & 1 == 1)
+                            .fold(0, |any, count| any | count_is(count))
+                    };
+                    !cell & any_of(self.rule.birth) | cell & any_of(self.rule.survival)
+                };
+                // the bits beyond the last column stay dead
+                let mask = if w == self.words_per_row - 1 {
+                    last_word
+                } else {
+                    u64::MAX
+                };
+                next.push(state & mask);
+            }
+        }
+        self.words = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::reference::{next_generation, next_generation_with};
+    use crate::testing::assert_boards_eq;
+
+    #[test]
+    fn test_matches_reference() {
+        for dimensions in [(3, 2), (5, 7), (4, 64), (6, 65), (3, 130), (9, 128)] {
+            let mut cells = generate(&BoardProfile::Soup { density: 0.4 }, dimensions, 21);
+            let mut board = PackedBoard::new(&cells, dimensions);
+            assert_eq!(board.cells(), cells);
+
+            for generation in 1..=5 {
+                cells = next_generation(&cells, dimensions);
+                board.update();
+                assert_boards_eq(
+                    &cells,
+                    &board.cells(),
+                    dimensions,
+                    &format!("generation {} of {:?}", generation, dimensions),
+                );
+                assert_eq!(
+                    board.population(),
+                    cells.iter().filter(|alive| **alive).count()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rules_and_boundaries() {
+        // B0 and S8 tell 8 live neighbours from none, and would fill the bits
+        // beyond the last column if they were not masked
+        let rules: Vec<Rule> = ["B36/S23", "B0123478/S01234678", "B3/S8"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        for dimensions in [(5, 7), (4, 65), (3, 130)] {
+            for (rule, boundary) in rules
+                .iter()
+                .flat_map(|rule| BoundaryCondition::ALL.map(|boundary| (rule, boundary)))
+            {
+                let mut cells = generate(&BoardProfile::Soup { density: 0.6 }, dimensions, 4);
+                let mut board = PackedBoard::new(&cells, dimensions)
+                    .with_rule(*rule)
+                    .with_boundary(boundary);
+                for generation in 1..=4 {
+                    cells = next_generation_with(&cells, dimensions, rule, boundary);
+                    board.update();
+                    let context = format!(
+                        "generation {} of {:?} with {} and {} boundaries",
+                        generation, dimensions, rule, boundary
+                    );
+                    assert_boards_eq(&cells, &board.cells(), dimensions, &context);
+                    assert_eq!(
+                        board.population(),
+                        cells.iter().filter(|alive| **alive).count()
+                    );
+                }
+            }
+        }
+    }
+}
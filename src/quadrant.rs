@@ -0,0 +1,399 @@
+//! Boards made of four independent quadrants, for patterns kept apart by
+//! dead moats: every quadrant evolves as its own smaller board, and the
+//! quadrants are stitched back into one board to be shown.
+//!
+//! Splitting gives the same generations as the whole board as long as no
+//! live cell reaches the edges between the quadrants. Nothing travels
+//! faster than one cell per generation, so `check_moats` only needs the
+//! initial cells, unless the rule gives birth to cells without live
+//! neighbours (B0): then the whole moat comes alive at once, and such rules
+//! are refused.
+
+use crate::rule::Rule;
+use crate::{Board, BoundaryCondition};
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum QuadrantError {
+    /// The board has fewer than 2 rows or columns.
+    TooSmall { dimensions: (usize, usize) },
+    /// Cells beyond the edges of the board are not dead, so the quadrants
+    /// would interact through them.
+    Boundary(BoundaryCondition),
+    /// The rule gives birth to dead cells without live neighbours, so the
+    /// moats do not stay dead.
+    BirthWithoutNeighbours(Rule),
+    /// A live cell is too close to the edges between the quadrants to stay
+    /// away from them for that many generations.
+    MoatTooNarrow {
+        cell: (usize, usize),
+        generations: usize,
+    },
+}
+
+impl fmt::Display for QuadrantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooSmall { dimensions } => write!(
+                f,
+                "a {}x{} board cannot be split in quadrants",
+                dimensions.0, dimensions.1
+            ),
+            Self::Boundary(boundary) => {
+                write!(f, "quadrants need fixed boundaries, not {} ones", boundary)
+            }
+            Self::BirthWithoutNeighbours(rule) => write!(
+                f,
+                "quadrants cannot play {}, whose dead cells are born without live neighbours",
+                rule
+            ),
+            Self::MoatTooNarrow { cell, generations } => write!(
+                f,
+                "the live cell at ({}, {}) can reach another quadrant within {} generations",
+                cell.0, cell.1, generations
+            ),
+        }
+    }
+}
+
+impl Error for QuadrantError {}
+
+/// Rows and columns of the top left quadrant: the others take the rest.
+pub fn split_point(dimensions: (usize, usize)) -> (usize, usize) {
+    (dimensions.0 / 2, dimensions.1 / 2)
+}
+
+/// Index of the quadrant of `cell`, in row-major order, and the cell within
+/// it.
+fn locate(cell: (usize, usize), split: (usize, usize)) -> (usize, (usize, usize)) {
+    let (row, col) = cell;
+    let (bottom, right) = (row >= split.0, col >= split.1);
+    let quadrant = usize::from(bottom) * 2 + usize::from(right);
+    let origin = (
+        if bottom { split.0 } else { 0 },
+        if right { split.1 } else { 0 },
+    );
+    (quadrant, (row - origin.0, col - origin.1))
+}
+
+/// Checks that the live cells of a board about to be split stay away from
+/// the edges between the quadrants for `generations` generations of
+/// `rule`: their rows and columns must be at least `generations` cells from
+/// the last row or column of their quadrant next to another one.
+pub fn check_moats(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    generations: usize,
+    rule: Rule,
+) -> Result<(), QuadrantError> {
+    let (n_rows, n_cols) = dimensions;
+    if n_rows < 2 || n_cols < 2 {
+        return Err(QuadrantError::TooSmall { dimensions });
+    }
+    check_rule(rule)?;
+    let split = split_point(dimensions);
+    // distance to the nearest inner edge, the rows `split.0 - 1` and
+    // `split.0`, and the same columns
+    let distance = |k: usize, split: usize| {
+        if k < split {
+            split - 1 - k
+        } else {
+            k - split
+        }
+    };
+    let too_close = cells
+        .iter()
+        .enumerate()
+        .filter(|(_, alive)| **alive)
+        .map(|(k, _)| (k / n_cols, k % n_cols))
+        .find(|&(row, col)| distance(row, split.0).min(distance(col, split.1)) < generations);
+    match too_close {
+        Some(cell) => Err(QuadrantError::MoatTooNarrow { cell, generations }),
+        None => Ok(()),
+    }
+}
+
+fn check_rule(rule: Rule) -> Result<(), QuadrantError> {
+    if rule.birth & 1 != 0 {
+        return Err(QuadrantError::BirthWithoutNeighbours(rule));
+    }
+    Ok(())
+}
+
+/// A board with fixed boundaries, played as four independent boards.
+#[derive(Clone)]
+pub struct QuadrantBoard {
+    dimensions: (usize, usize),
+    /// Top left, top right, bottom left and bottom right.
+    quadrants: Vec<Board>,
+}
+
+impl QuadrantBoard {
+    /// Splits `board`, which must have fixed boundaries and a rule without
+    /// B0. Check the initial
+    /// cells with `check_moats` first: this cannot, the cells being
+    /// encrypted.
+    pub fn split(board: &Board) -> Result<Self, QuadrantError> {
+        let dimensions = board.dimensions();
+        if dimensions.0 < 2 || dimensions.1 < 2 {
+            return Err(QuadrantError::TooSmall { dimensions });
+        }
+        if board.boundary() != BoundaryCondition::Fixed {
+            return Err(QuadrantError::Boundary(board.boundary()));
+        }
+        check_rule(board.rule())?;
+        let split = split_point(dimensions);
+        let mut states = vec![Vec::new(); 4];
+        for (k, state) in board.states().iter().enumerate() {
+            let (quadrant, _) = locate((k / dimensions.1, k % dimensions.1), split);
+            states[quadrant].push(state.clone());
+        }
+        let zeros = board.clean_accumulator.clone();
+        let quadrants = states
+            .into_iter()
+            .enumerate()
+            .map(|(quadrant, states)| {
+                let n_cols = if quadrant % 2 == 0 {
+                    split.1
+                } else {
+                    dimensions.1 - split.1
+                };
+                let mut quadrant = Board::new(
+                    n_cols,
+                    states,
+                    (zeros.0.clone(), zeros.1.clone(), zeros.2.clone()),
+                )
+                .with_rule(board.rule())
+                .with_boundary(BoundaryCondition::Fixed);
+                quadrant.generation = board.generation();
+                quadrant
+            })
+            .collect();
+        Ok(Self {
+            dimensions,
+            quadrants,
+        })
+    }
+
+    /// Dimensions of the stitched board.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    pub fn generation(&self) -> usize {
+        self.quadrants[0].generation()
+    }
+
+    /// The quadrants, top left, top right, bottom left and bottom right.
+    pub fn quadrants(&self) -> &[Board] {
+        &self.quadrants
+    }
+
+    /// Plays one generation of every quadrant.
+    pub fn update(&mut self) {
+        for quadrant in &mut self.quadrants {
+            quadrant.update();
+        }
+    }
+
+    /// The quadrants put back together into one board, to be shown.
+    pub fn stitch(&self) -> Board {
+        let (n_rows, n_cols) = self.dimensions;
+        let split = split_point(self.dimensions);
+        let states = (0..n_rows * n_cols)
+            .map(|k| {
+                let (quadrant, (row, col)) = locate((k / n_cols, k % n_cols), split);
+                let quadrant = &self.quadrants[quadrant];
+                quadrant.states()[row * quadrant.dimensions().1 + col].clone()
+            })
+            .collect();
+        let first = &self.quadrants[0];
+        let zeros = first.clean_accumulator.clone();
+        let mut board = Board::new(n_cols, states, (zeros.0, zeros.1, zeros.2))
+            .with_rule(first.rule())
+            .with_boundary(BoundaryCondition::Fixed);
+        board.generation = self.generation();
+        board
+    }
+
+    /// Writes every quadrant with `Board::save`, to the files of
+    /// `quadrant_path`, so that each can be resumed on its own.
+    pub fn save(&self, path: &Path, parameters: &str) -> Result<(), crate::keys::FileError> {
+        for (k, quadrant) in self.quadrants.iter().enumerate() {
+            quadrant.save(&quadrant_path(path, k), parameters)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the quadrants written by `save`, for a board of `dimensions`.
+    pub fn load(
+        path: &Path,
+        dimensions: (usize, usize),
+        parameters: &str,
+    ) -> Result<Self, crate::keys::FileError> {
+        let quadrants = (0..4)
+            .map(|k| Board::load(&quadrant_path(path, k), parameters))
+            .collect::<Result<Vec<_>, _>>()?;
+        let split = split_point(dimensions);
+        let expected = [
+            split,
+            (split.0, dimensions.1 - split.1),
+            (dimensions.0 - split.0, split.1),
+            (dimensions.0 - split.0, dimensions.1 - split.1),
+        ];
+        let found: Vec<_> = quadrants.iter().map(Board::dimensions).collect();
+        if found != expected {
+            return Err(crate::keys::FileError::Format(Box::new(
+                bincode::ErrorKind::Custom(format!(
+                    "quadrants of {:?} for a {}x{} board",
+                    found, dimensions.0, dimensions.1
+                )),
+            )));
+        }
+        Ok(Self {
+            dimensions,
+            quadrants,
+        })
+    }
+}
+
+/// File of the `k`th quadrant of a board saved to `path`.
+pub fn quadrant_path(path: &Path, k: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".q{}", k));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::GLIDER;
+    use crate::reference::next_generation_with;
+    use crate::testing::{assert_boards_eq, encrypt_board, set_up_keys};
+
+    #[test]
+    fn test_locate() {
+        let split = split_point((5, 7));
+        assert_eq!(split, (2, 3));
+        assert_eq!(locate((0, 0), split), (0, (0, 0)));
+        assert_eq!(locate((1, 3), split), (1, (1, 0)));
+        assert_eq!(locate((2, 2), split), (2, (0, 2)));
+        assert_eq!(locate((4, 6), split), (3, (2, 3)));
+    }
+
+    #[test]
+    fn test_check_moats() {
+        let dimensions = (10, 10);
+        let mut cells = vec![false; 100];
+        cells[2 * 10 + 1] = true;
+        // 2 rows and 2 columns from the edges next to the other quadrants
+        let conway = Rule::conway();
+        assert_eq!(check_moats(&cells, dimensions, 2, conway), Ok(()));
+        assert_eq!(
+            check_moats(&cells, dimensions, 3, conway),
+            Err(QuadrantError::MoatTooNarrow {
+                cell: (2, 1),
+                generations: 3
+            })
+        );
+        cells[2 * 10 + 1] = false;
+        cells[7 * 10 + 8] = true;
+        assert_eq!(check_moats(&cells, dimensions, 2, conway), Ok(()));
+        assert!(check_moats(&cells, dimensions, 3, conway).is_err());
+        assert!(matches!(
+            check_moats(&[true], (1, 1), 0, conway),
+            Err(QuadrantError::TooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn test_birth_without_neighbours() {
+        // under B0 the dead moat is born in the first generation, and cells
+        // on the edges between the quadrants see the other quadrants
+        let rule: Rule = "B0/S8".parse().unwrap();
+        let dimensions = (6, 6);
+        let cells = vec![false; 36];
+        let step = |cells: &[bool], dimensions| {
+            next_generation_with(cells, dimensions, &rule, BoundaryCondition::Fixed)
+        };
+        let whole = step(&step(&cells, dimensions), dimensions);
+        let top_left = step(&step(&[false; 9], (3, 3)), (3, 3));
+        // inside the whole board, in the corner of its quadrant
+        assert!(whole[2 * 6 + 2]);
+        assert!(!top_left[2 * 3 + 2]);
+        assert_eq!(
+            check_moats(&cells, dimensions, 1, rule),
+            Err(QuadrantError::BirthWithoutNeighbours(rule))
+        );
+        assert_eq!(
+            QuadrantError::BirthWithoutNeighbours(rule).to_string(),
+            "quadrants cannot play B0/S8, whose dead cells are born without live neighbours"
+        );
+    }
+
+    #[test]
+    fn test_matches_whole_board() {
+        let client_key = set_up_keys();
+
+        // a glider in every quadrant, mirrored across the edges between them
+        let dimensions = (14, 15);
+        let mut cells = vec![false; 14 * 15];
+        for (k, alive) in GLIDER.iter().enumerate() {
+            let (row, col) = (k / 3, k % 3);
+            cells[(row + 1) * 15 + col + 1] = *alive;
+            cells[(row + 1) * 15 + 13 - col] = *alive;
+            cells[(12 - row) * 15 + col + 1] = *alive;
+            cells[(12 - row) * 15 + 13 - col] = *alive;
+        }
+        let generations = 3;
+        check_moats(&cells, dimensions, generations, Rule::conway()).unwrap();
+
+        let mut whole = encrypt_board(&cells, dimensions.1, &client_key)
+            .with_boundary(BoundaryCondition::Fixed);
+        let mut quadrants = QuadrantBoard::split(&whole).unwrap();
+        let b0 = whole.clone().with_rule("B03/S23".parse().unwrap());
+        assert!(matches!(
+            QuadrantBoard::split(&b0),
+            Err(QuadrantError::BirthWithoutNeighbours(_))
+        ));
+        assert_eq!(quadrants.quadrants()[3].dimensions(), (7, 8));
+
+        for generation in 1..=generations {
+            whole.update();
+            quadrants.update();
+            let stitched = quadrants.stitch();
+            assert_eq!(stitched.generation(), generation);
+            assert_boards_eq(
+                &whole.decrypt(&client_key),
+                &stitched.decrypt(&client_key),
+                dimensions,
+                &format!("generation {}", generation),
+            );
+        }
+
+        let path = std::env::temp_dir().join(format!("quadrants-{}.bin", std::process::id()));
+        quadrants.save(&path, "default").unwrap();
+        let mut loaded = QuadrantBoard::load(&path, dimensions, "default").unwrap();
+        let err = QuadrantBoard::load(&path, (14, 16), "default")
+            .err()
+            .unwrap();
+        for k in 0..4 {
+            std::fs::remove_file(quadrant_path(&path, k)).unwrap();
+        }
+        assert!(matches!(err, crate::keys::FileError::Format(_)));
+        loaded.update();
+        quadrants.update();
+        assert_eq!(
+            loaded.stitch().decrypt(&client_key),
+            quadrants.stitch().decrypt(&client_key)
+        );
+
+        let toroidal = whole.with_boundary(BoundaryCondition::Toroidal);
+        assert!(matches!(
+            QuadrantBoard::split(&toroidal),
+            Err(QuadrantError::Boundary(BoundaryCondition::Toroidal))
+        ));
+    }
+}
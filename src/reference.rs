@@ -0,0 +1,251 @@
+//! Plaintext implementation of the game, used to check and seed encrypted runs.
+
+use crate::rule::Rule;
+use crate::BoundaryCondition;
+
+/// Number of live neighbours of every cell, with periodic boundary conditions.
+pub fn neighbour_counts(cells: &[bool], dimensions: (usize, usize)) -> Vec<u8> {
+    neighbour_counts_with_boundary(cells, dimensions, BoundaryCondition::Toroidal)
+}
+
+/// Same as `neighbour_counts` with any boundary conditions.
+pub fn neighbour_counts_with_boundary(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    boundary: BoundaryCondition,
+) -> Vec<u8> {
+    let (n_rows, n_cols) = dimensions;
+    let mut counts = Vec::with_capacity(cells.len());
+    for i in 0..n_rows {
+        for j in 0..n_cols {
+            let mut count = 0;
+            for di in [-1, 0, 1] {
+                for dj in [-1, 0, 1] {
+                    let (k, l) = (i as isize + di, j as isize + dj);
+                    let outside =
+                        !(0..n_rows as isize).contains(&k) || !(0..n_cols as isize).contains(&l);
+                    if (di, dj) == (0, 0) || (outside && boundary == BoundaryCondition::Fixed) {
+                        continue;
+                    }
+                    let (k, l) = if boundary == BoundaryCondition::Mirror {
+                        (
+                            k.clamp(0, n_rows as isize - 1) as usize,
+                            l.clamp(0, n_cols as isize - 1) as usize,
+                        )
+                    } else {
+                        (
+                            k.rem_euclid(n_rows as isize) as usize,
+                            l.rem_euclid(n_cols as isize) as usize,
+                        )
+                    };
+                    if cells[k * n_cols + l] {
+                        count += 1;
+                    }
+                }
+            }
+            counts.push(count);
+        }
+    }
+    counts
+}
+
+/// Plaintext Game of Life step with periodic boundary conditions.
+pub fn next_generation(cells: &[bool], dimensions: (usize, usize)) -> Vec<bool> {
+    next_generation_with(
+        cells,
+        dimensions,
+        &Rule::conway(),
+        BoundaryCondition::Toroidal,
+    )
+}
+
+/// Same as `next_generation` for any rule and boundary conditions.
+pub fn next_generation_with(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    rule: &Rule,
+    boundary: BoundaryCondition,
+) -> Vec<bool> {
+    neighbour_counts_with_boundary(cells, dimensions, boundary)
+        .into_iter()
+        .zip(cells)
+        .map(|(count, &alive)| rule.next_state(alive, usize::from(count)))
+        .collect()
+}
+
+/// Chebyshev distance from every cell to the nearest live cell, with periodic
+/// boundary conditions, capped at `max_distance`. Computed breadth first.
+pub fn distance_field(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    max_distance: usize,
+) -> Vec<usize> {
+    distance_field_with_boundary(cells, dimensions, max_distance, BoundaryCondition::Toroidal)
+}
+
+/// Same as `distance_field` with any boundary conditions. The distances of
+/// the other boundaries do not go across the edges: beyond them the cells of
+/// a fixed board are dead, and those of a mirrored one copies of cells that
+/// are already neighbours.
+pub fn distance_field_with_boundary(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    max_distance: usize,
+    boundary: BoundaryCondition,
+) -> Vec<usize> {
+    let (n_rows, n_cols) = dimensions;
+    let mut distances = vec![max_distance; cells.len()];
+    let mut frontier: Vec<usize> = (0..cells.len()).filter(|&k| cells[k]).collect();
+    for &k in &frontier {
+        distances[k] = 0;
+    }
+
+    for distance in 1..max_distance {
+        let mut next = Vec::new();
+        for k in frontier {
+            let (i, j) = ((k / n_cols) as isize, (k % n_cols) as isize);
+            for di in [-1, 0, 1] {
+                for dj in [-1, 0, 1] {
+                    let (row, col) = (i + di, j + dj);
+                    let outside = !(0..n_rows as isize).contains(&row)
+                        || !(0..n_cols as isize).contains(&col);
+                    if outside && boundary != BoundaryCondition::Toroidal {
+                        continue;
+                    }
+                    let l = row.rem_euclid(n_rows as isize) as usize * n_cols
+                        + col.rem_euclid(n_cols as isize) as usize;
+                    if distances[l] > distance {
+                        distances[l] = distance;
+                        next.push(l);
+                    }
+                }
+            }
+        }
+        frontier = next;
+    }
+    distances
+}
+
+/// Renders neighbour counts as a heat map: a space for no neighbours, `░▒▓`
+/// for 1 to 3 and `█` for 4 or more.
+pub fn render_neighbour_counts(counts: &[u8], n_cols: usize) -> String {
+    let mut map = String::with_capacity(counts.len() * 4);
+    for row in counts.chunks(n_cols) {
+        map.extend(row.iter().map(|count| match count {
+            0 => ' ',
+            1 => '░',
+            2 => '▒',
+            3 => '▓',
+            _ => '█',
+        }));
+        map.push('\n');
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbour_counts() {
+        // on a 3x3 torus every cell sees the 8 others
+        let counts = neighbour_counts(&[true; 9], (3, 3));
+        assert_eq!(counts, vec![8; 9]);
+        assert_eq!(counts.iter().map(|&c| c as usize).sum::<usize>(), 72);
+
+        #[rustfmt::skip]
+        let blinker = [
+            false, false, false, false,
+            false, true, true, true,
+            false, false, false, false,
+            false, false, false, false,
+        ];
+        #[rustfmt::skip]
+        assert_eq!(neighbour_counts(&blinker, (4, 4)), vec![
+            2, 2, 3, 2,
+            2, 1, 2, 1,
+            2, 2, 3, 2,
+            0, 0, 0, 0,
+        ]);
+
+        // without the wrapped neighbours, corners have 3 and edges 5
+        #[rustfmt::skip]
+        assert_eq!(
+            neighbour_counts_with_boundary(&[true; 9], (3, 3), BoundaryCondition::Fixed),
+            vec![
+                3, 5, 3,
+                5, 8, 5,
+                3, 5, 3,
+            ]
+        );
+        #[rustfmt::skip]
+        assert_eq!(
+            neighbour_counts_with_boundary(&blinker, (4, 4), BoundaryCondition::Fixed),
+            vec![
+                1, 2, 3, 2,
+                1, 1, 2, 1,
+                1, 2, 3, 2,
+                0, 0, 0, 0,
+            ]
+        );
+
+        // reflected, the edge cells are their own neighbours
+        assert_eq!(
+            neighbour_counts_with_boundary(&[true; 9], (3, 3), BoundaryCondition::Mirror),
+            vec![8; 9]
+        );
+        #[rustfmt::skip]
+        assert_eq!(
+            neighbour_counts_with_boundary(&blinker, (4, 4), BoundaryCondition::Mirror),
+            vec![
+                1, 2, 3, 3,
+                1, 1, 2, 2,
+                1, 2, 3, 3,
+                0, 0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let mut cells = vec![false; 6 * 7];
+        cells[2 * 7 + 1] = true;
+        #[rustfmt::skip]
+        assert_eq!(distance_field(&cells, (6, 7), 3), vec![
+            2, 2, 2, 2, 3, 3, 2,
+            1, 1, 1, 2, 3, 3, 2,
+            1, 0, 1, 2, 3, 3, 2,
+            1, 1, 1, 2, 3, 3, 2,
+            2, 2, 2, 2, 3, 3, 2,
+            3, 3, 3, 3, 3, 3, 3,
+        ]);
+        assert_eq!(distance_field(&[false; 4], (2, 2), 5), vec![5; 4]);
+
+        // without wrapping, the cells on the far side of the edge are farther
+        let mut cells = vec![false; 3 * 5];
+        cells[0] = true;
+        for boundary in [BoundaryCondition::Fixed, BoundaryCondition::Mirror] {
+            #[rustfmt::skip]
+            assert_eq!(distance_field_with_boundary(&cells, (3, 5), 9, boundary), vec![
+                0, 1, 2, 3, 4,
+                1, 1, 2, 3, 4,
+                2, 2, 2, 3, 4,
+            ]);
+        }
+        #[rustfmt::skip]
+        assert_eq!(distance_field(&cells, (3, 5), 9), vec![
+            0, 1, 2, 2, 1,
+            1, 1, 2, 2, 1,
+            1, 1, 2, 2, 1,
+        ]);
+    }
+
+    #[test]
+    fn test_render_neighbour_counts() {
+        assert_eq!(
+            render_neighbour_counts(&[0, 1, 2, 3, 4, 8], 3),
+            " ░▒\n▓██\n"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Rescaling of decrypted boards, to display boards that do not fit in the
+//! terminal or that are too small to read.
+
+/// Shrinks the board by `factor` in both directions: a cell of the result is
+/// alive when any cell of the corresponding block is.
+///
+/// Blocks on the bottom and right edges are truncated when the dimensions are
+/// not multiples of the factor. Returns the cells and their dimensions.
+pub fn downsample(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    factor: usize,
+) -> (Vec<bool>, (usize, usize)) {
+    let (n_rows, n_cols) = dimensions;
+    let reduced = (n_rows.div_ceil(factor), n_cols.div_ceil(factor));
+
+    let mut reduced_cells = vec![false; reduced.0 * reduced.1];
+    for (k, alive) in cells.iter().enumerate() {
+        let (i, j) = (k / n_cols / factor, k % n_cols / factor);
+        reduced_cells[i * reduced.1 + j] |= *alive;
+    }
+
+    (reduced_cells, reduced)
+}
+
+/// Grows the board by `factor` in both directions, every cell becoming a
+/// `factor` wide square block. Returns the cells and their dimensions.
+pub fn upsample(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    factor: usize,
+) -> (Vec<bool>, (usize, usize)) {
+    let (n_rows, n_cols) = dimensions;
+    let enlarged = (n_rows * factor, n_cols * factor);
+
+    let enlarged_cells = (0..enlarged.0 * enlarged.1)
+        .map(|k| cells[k / enlarged.1 / factor * n_cols + k % enlarged.1 / factor])
+        .collect();
+
+    (enlarged_cells, enlarged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::testing::assert_boards_eq;
+
+    #[test]
+    fn test_downsample_then_upsample_keeps_blocks() {
+        // a board made of 3x3 blocks comes back unchanged
+        let blocks = generate(&BoardProfile::Soup { density: 0.5 }, (4, 5), 2);
+        let (cells, dimensions) = upsample(&blocks, (4, 5), 3);
+        assert_eq!(dimensions, (12, 15));
+
+        let (reduced, reduced_dimensions) = downsample(&cells, dimensions, 3);
+        assert_eq!(reduced_dimensions, (4, 5));
+        assert_boards_eq(&blocks, &reduced, reduced_dimensions, "blocks");
+        assert_eq!(upsample(&reduced, reduced_dimensions, 3).0, cells);
+    }
+
+    #[test]
+    fn test_downsample_covers_live_cells() {
+        let dimensions = (10, 7);
+        let cells = generate(&BoardProfile::Soup { density: 0.1 }, dimensions, 5);
+
+        let (reduced, reduced_dimensions) = downsample(&cells, dimensions, 4);
+        assert_eq!(reduced_dimensions, (3, 2));
+
+        // every live cell lies in a live block, the edges being truncated
+        let (restored, _) = upsample(&reduced, reduced_dimensions, 4);
+        for (k, alive) in cells.iter().enumerate() {
+            let (i, j) = (k / dimensions.1, k % dimensions.1);
+            assert!(!alive || restored[i * 8 + j]);
+        }
+        // and every live block holds a live cell
+        for (k, alive) in reduced.iter().enumerate() {
+            let (bi, bj) = (k / 2, k % 2);
+            let any_alive = (0..dimensions.0 * dimensions.1)
+                .any(|l| cells[l] && l / dimensions.1 / 4 == bi && l % dimensions.1 / 4 == bj);
+            assert_eq!(*alive, any_alive);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Outer totalistic rules, in the usual `B3/S23` notation: the numbers of
+//! live neighbours for which a dead cell is born and a live cell survives.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Birth and survival counts as bit sets, bit `c` standing for `c` live
+/// neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub birth: u16,
+    pub survival: u16,
+}
+
+impl Rule {
+    /// Conway's game of life, B3/S23.
+    pub const fn conway() -> Self {
+        Self {
+            birth: 1 << 3,
+            survival: 1 << 2 | 1 << 3,
+        }
+    }
+
+    /// State of a cell with `count` live neighbours at the next generation.
+    pub fn next_state(&self, alive: bool, count: usize) -> bool {
+        let counts = if alive { self.survival } else { self.birth };
+        counts >> count & 1 == 1
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::conway()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let counts = |part: &str, prefix: char| {
+            let digits = part
+                .strip_prefix(prefix)
+                .or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+                .ok_or_else(|| format!("expected {} in rule '{}'", prefix, rule))?;
+            digits.chars().try_fold(0u16, |counts, digit| {
+                match digit.to_digit(10).filter(|count| *count <= 8) {
+                    Some(count) => Ok(counts | 1 << count),
+                    None => Err(format!(
+                        "invalid neighbour count '{}' in rule '{}'",
+                        digit, rule
+                    )),
+                }
+            })
+        };
+        let (birth, survival) = rule
+            .split_once('/')
+            .ok_or_else(|| format!("expected B<counts>/S<counts>, got '{}'", rule))?;
+        Ok(Self {
+            birth: counts(birth, 'B')?,
+            survival: counts(survival, 'S')?,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |counts: u16| {
+            (0..=8)
+                .filter(|count| counts >> count & 1 == 1)
+                .map(|count: u16| count.to_string())
+                .collect::<String>()
+        };
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!("B3/S23".parse(), Ok(Rule::conway()));
+        let highlife: Rule = "b36/s23".parse().unwrap();
+        assert_eq!(highlife.to_string(), "B36/S23");
+        let seeds: Rule = "B2/S".parse().unwrap();
+        assert_eq!(seeds.survival, 0);
+        assert!(seeds.next_state(false, 2));
+        assert!(!seeds.next_state(true, 2));
+
+        assert!("B3S23".parse::<Rule>().is_err());
+        assert!("B39/S23".parse::<Rule>().is_err());
+        assert!("S23/B3".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_conway() {
+        let rule = Rule::conway();
+        let alive: Vec<usize> = (0..=8)
+            .filter(|count| rule.next_state(true, *count))
+            .collect();
+        let born: Vec<usize> = (0..=8)
+            .filter(|count| rule.next_state(false, *count))
+            .collect();
+        assert_eq!(alive, [2, 3]);
+        assert_eq!(born, [3]);
+    }
+}
@@ -0,0 +1,224 @@
+//! The sources of randomness of a run, recorded in a JSON `SeedManifest` by
+//! `--seed-manifest` to replay the run:
+//!
+//! ```json
+//! {"board": {"seed": 7}, "sampling": {"seed": 7}, "noise": "os", "keys": "os"}
+//! ```
+//!
+//! The keys come from the randomness of the operating system, which concrete
+//! does not let us seed, and the noise of `--noisy-population` too, so that
+//! the server cannot guess it from the options or from a manifest: neither is
+//! ever replayed.
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// Where the random values of a part of the run come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// A `benchgen::Rng` seeded with it, replayed from the manifest.
+    Seed(u64),
+    /// The randomness of the operating system, which cannot be replayed.
+    Os,
+}
+
+impl Source {
+    /// The seed of this source, drawn by `os_seed` for the randomness of
+    /// the operating system.
+    pub fn seed(self, os_seed: impl FnOnce() -> u64) -> u64 {
+        match self {
+            Self::Seed(seed) => seed,
+            Self::Os => os_seed(),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Seed(seed) => write!(f, "seed {}", seed),
+            Self::Os => f.write_str("from the OS"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// A source that must come from the operating system has a seed.
+    Seeded(&'static str),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access the seed manifest: {}", err),
+            Self::Json(err) => write!(f, "invalid seed manifest: {}", err),
+            Self::Seeded(part) => write!(f, "the {} cannot be seeded", part),
+        }
+    }
+}
+
+impl Error for ManifestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Seeded(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ManifestError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// Every source of randomness of a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedManifest {
+    /// The random boards of `--board-profile`.
+    pub board: Source,
+    /// The cells decrypted by `--estimate-population`.
+    pub sampling: Source,
+    /// The noise of `--noisy-population`, always from the OS.
+    pub noise: Source,
+    /// The keys, always from the OS.
+    pub keys: Source,
+}
+
+impl SeedManifest {
+    /// The sources of a run with `--seed <seed>`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            board: Source::Seed(seed),
+            sampling: Source::Seed(seed),
+            noise: Source::Os,
+            keys: Source::Os,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ManifestError> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a manifest is always valid JSON")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ManifestError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ManifestError> {
+        let manifest: Self = serde_json::from_str(json)?;
+        match (manifest.noise, manifest.keys) {
+            (Source::Seed(_), _) => Err(ManifestError::Seeded("noise")),
+            (_, Source::Seed(_)) => Err(ManifestError::Seeded("keys")),
+            _ => Ok(manifest),
+        }
+    }
+
+    /// The parts of the run drawn from the randomness of the operating
+    /// system, which a replay draws anew.
+    pub fn not_replayed(&self) -> Vec<&'static str> {
+        [
+            ("board", self.board),
+            ("sampling", self.sampling),
+            ("noise", self.noise),
+            ("keys", self.keys),
+        ]
+        .into_iter()
+        .filter(|(_, source)| *source == Source::Os)
+        .map(|(part, _)| part)
+        .collect()
+    }
+}
+
+impl fmt::Display for SeedManifest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "board {}, sampling {}, noise {}, keys {}",
+            self.board, self.sampling, self.noise, self.keys
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile, Rng};
+    use crate::estimate::pick;
+    use crate::plain::PackedBoard;
+
+    #[test]
+    fn test_json() {
+        let manifest = SeedManifest::new(7);
+        let json = r#"{"board":{"seed":7},"sampling":{"seed":7},"noise":"os","keys":"os"}"#;
+        assert_eq!(manifest.to_json(), json);
+        assert_eq!(SeedManifest::from_json(json).unwrap(), manifest);
+        assert_eq!(
+            manifest.to_string(),
+            "board seed 7, sampling seed 7, noise from the OS, keys from the OS"
+        );
+        assert_eq!(manifest.not_replayed(), ["noise", "keys"]);
+
+        let seeded_noise = json.replace(r#""noise":"os""#, r#""noise":{"seed":1}"#);
+        assert_eq!(
+            SeedManifest::from_json(&seeded_noise)
+                .err()
+                .unwrap()
+                .to_string(),
+            "the noise cannot be seeded"
+        );
+        assert!(matches!(
+            SeedManifest::from_json(r#"{"board":"os"}"#),
+            Err(ManifestError::Json(_))
+        ));
+    }
+
+    /// The frames and sampled cells of a plaintext run with `seeds`.
+    fn run(seeds: &SeedManifest) -> (Vec<Vec<bool>>, Vec<Vec<usize>>) {
+        let dimensions = (6, 8);
+        let profile = BoardProfile::Soup { density: 0.4 };
+        let cells = generate(&profile, dimensions, seeds.board.seed(|| 1));
+        let mut board = PackedBoard::new(&cells, dimensions);
+        let mut sampling_rng = Rng::new(seeds.sampling.seed(|| 2));
+        let mut frames = vec![cells];
+        let mut samples = Vec::new();
+        for _ in 0..4 {
+            board.update();
+            frames.push(board.cells());
+            samples.push(pick(dimensions.1, 3, &mut sampling_rng));
+        }
+        (frames, samples)
+    }
+
+    #[test]
+    fn test_replay() {
+        let path = std::env::temp_dir().join(format!("seeds-{}.json", std::process::id()));
+        let manifest = SeedManifest {
+            sampling: Source::Seed(12),
+            ..SeedManifest::new(5)
+        };
+        manifest.save(&path).unwrap();
+        let replayed = SeedManifest::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replayed, manifest);
+        assert_eq!(run(&replayed), run(&manifest));
+        assert_ne!(run(&SeedManifest::new(6)).0, run(&manifest).0);
+    }
+}
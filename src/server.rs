@@ -0,0 +1,104 @@
+//! A run split between the client, who holds the keys, and a server, who
+//! only ever holds the server key.
+//!
+//! The client encrypts a board and writes it with the server key next to
+//! it (see `hand_over`). The server updates the board for some generations
+//! and writes it back after each of them (see `run`), so that an interrupted
+//! run carries on from the last generation written, and a later run from
+//! where the previous one stopped. The client decrypts the board it finds
+//! with `Board::load`.
+
+use crate::keys::{self, FileError};
+use crate::Board;
+use concrete::{set_server_key, ServerKey};
+use std::path::{Path, PathBuf};
+
+/// Where the server key of the board at `board_path` is written: next to it,
+/// with the extension `server-key`.
+pub fn server_key_path(board_path: &Path) -> PathBuf {
+    board_path.with_extension("server-key")
+}
+
+/// Writes the board to `path` and the server key next to it, for `run`.
+/// `parameters` names the parameters of the keys, see `keys`.
+pub fn hand_over(
+    board: &Board,
+    path: &Path,
+    parameters: &str,
+    server_key: &ServerKey,
+) -> Result<(), FileError> {
+    keys::save_server_key(&server_key_path(path), parameters, server_key)?;
+    board.save(path, parameters)
+}
+
+/// Updates the board written by `hand_over` for `generations` generations
+/// with its server key, writing it back after each of them, and calls
+/// `on_generation` with the board each time. Returns the last board.
+pub fn run(
+    path: &Path,
+    parameters: &str,
+    generations: usize,
+    mut on_generation: impl FnMut(&Board),
+) -> Result<Board, FileError> {
+    set_server_key(keys::load_server_key(&server_key_path(path), parameters)?);
+    let mut board = Board::load(path, parameters)?;
+    for _ in 0..generations {
+        board.update();
+        board.save(path, parameters)?;
+        on_generation(&board);
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchgen::{generate, BoardProfile};
+    use crate::testing::{assert_boards_eq, default_keys, encrypt_board};
+
+    #[test]
+    fn test_run_in_two_steps() {
+        let (client_key, server_key) = default_keys();
+        let dimensions = (3, 4);
+        let cells = generate(&BoardProfile::Soup { density: 0.5 }, dimensions, 3);
+        let path = std::env::temp_dir().join(format!("server-{}.bin", std::process::id()));
+        let board = encrypt_board(&cells, dimensions.1, &client_key);
+        hand_over(&board, &path, "default", &server_key).unwrap();
+
+        // the second run carries on from the board written by the first one
+        let mut generations = Vec::new();
+        run(&path, "default", 2, |board| {
+            generations.push(board.generation())
+        })
+        .unwrap();
+        run(&path, "default", 2, |board| {
+            generations.push(board.generation())
+        })
+        .unwrap();
+        assert_eq!(generations, [1, 2, 3, 4]);
+        let resumed = Board::load(&path, "default").unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(server_key_path(&path)).unwrap();
+
+        // the server key of this thread is the one handed over
+        let mut straight = board;
+        for _ in 0..4 {
+            straight.update();
+        }
+        assert_eq!(resumed.generation(), 4);
+        assert_boards_eq(
+            &straight.decrypt(&client_key),
+            &resumed.decrypt(&client_key),
+            dimensions,
+            "resumed",
+        );
+    }
+
+    #[test]
+    fn test_server_key_path() {
+        assert_eq!(
+            server_key_path(Path::new("runs/board.bin")),
+            Path::new("runs/board.server-key")
+        );
+    }
+}
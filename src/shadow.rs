@@ -0,0 +1,113 @@
+//! Plaintext shadow of an encrypted run, to catch wrong results (such as
+//! noise failures) in long runs without validating every generation.
+//!
+//! The shadow holds the initial board in the clear, so it lives on the
+//! client, next to the client key.
+
+use crate::reference::next_generation;
+use crate::stream::{scroll_in_row, Edge, RowLengthMismatch};
+use std::fmt;
+
+/// The plaintext board advanced alongside the encrypted one.
+pub struct Shadow {
+    cells: Vec<bool>,
+    dimensions: (usize, usize),
+    /// Compare every `every` generations.
+    every: usize,
+    generation: usize,
+}
+
+/// The decrypted board does not match the shadow.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub generation: usize,
+    pub mismatches: usize,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "WARNING: the encrypted board diverged from its plaintext shadow at generation {}, \
+             {} cells differ",
+            self.generation, self.mismatches
+        )
+    }
+}
+
+impl Shadow {
+    pub fn new(cells: Vec<bool>, dimensions: (usize, usize), every: usize) -> Self {
+        assert!(every > 0, "the comparison interval must be at least 1");
+        Self {
+            cells,
+            dimensions,
+            every,
+            generation: 0,
+        }
+    }
+
+    /// Advances the shadow by one generation, with the reference game.
+    pub fn advance(&mut self) {
+        self.cells = next_generation(&self.cells, self.dimensions);
+        self.generation += 1;
+    }
+
+    /// Scrolls the shadow as `Board::scroll_in_row` scrolls the board.
+    pub fn scroll_in_row(&mut self, row: &[bool], edge: Edge) -> Result<(), RowLengthMismatch> {
+        self.cells = scroll_in_row(&self.cells, self.dimensions, row, edge)?;
+        Ok(())
+    }
+
+    /// Whether the current generation should be compared.
+    pub fn is_due(&self) -> bool {
+        self.generation.is_multiple_of(self.every)
+    }
+
+    /// Compares the decrypted board with the shadow of the same generation.
+    pub fn compare(&self, decrypted: &[bool]) -> Result<(), Divergence> {
+        let mismatches = self
+            .cells
+            .iter()
+            .zip(decrypted)
+            .filter(|(expected, actual)| expected != actual)
+            .count();
+        if mismatches == 0 {
+            Ok(())
+        } else {
+            Err(Divergence {
+                generation: self.generation,
+                mismatches,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::{tile, GLIDER};
+
+    #[test]
+    fn test_shadow_follows_reference() {
+        let dimensions = (6, 6);
+        let mut cells = tile(&GLIDER, 3, dimensions, (6, 6)).unwrap();
+        let mut shadow = Shadow::new(cells.clone(), dimensions, 3);
+
+        for generation in 1..=6 {
+            shadow.advance();
+            cells = next_generation(&cells, dimensions);
+            assert_eq!(shadow.is_due(), generation % 3 == 0);
+            assert_eq!(shadow.compare(&cells), Ok(()));
+        }
+
+        cells[0] = !cells[0];
+        cells[7] = !cells[7];
+        assert_eq!(
+            shadow.compare(&cells),
+            Err(Divergence {
+                generation: 6,
+                mismatches: 2
+            })
+        );
+    }
+}
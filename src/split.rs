@@ -0,0 +1,202 @@
+//! The `encrypt`, `run` and `decrypt` commands, splitting a run between the
+//! client and a server that never holds the client key (see `server`).
+//!
+//! `encrypt` writes the encrypted board to `--board <path>` and the server
+//! key next to it, `run` updates that board for `--generations <n>` with the
+//! server key alone, and can be run again to carry on, and `decrypt` shows
+//! the board with the keys of `--keys`.
+
+use crate::{exit_with_usage, load_keys, os_seed, parse_size, parse_value, print_coords};
+use crate::{print_grid, DEFAULT_PARAMETERS, DEFAULT_SIZE};
+use concrete::prelude::*;
+use concrete::FheBool;
+use homomorphic_game_of_life_2::benchgen::{self, BoardProfile};
+use homomorphic_game_of_life_2::{
+    keys, pattern, patternfile, rule, server, Board, BoundaryCondition,
+};
+use std::path::Path;
+use std::time::Instant;
+
+/// The commands, given as the first argument.
+pub const COMMANDS: [&str; 3] = ["encrypt", "run", "decrypt"];
+
+/// The options of the commands, and the commands taking each of them.
+const OPTIONS: [(&str, &[&str]); 10] = [
+    ("--board", &COMMANDS),
+    ("--params", &COMMANDS),
+    ("--keys", &["encrypt", "decrypt"]),
+    ("--board-profile", &["encrypt"]),
+    ("--pattern-file", &["encrypt"]),
+    ("--size", &["encrypt"]),
+    ("--seed", &["encrypt"]),
+    ("--rule", &["encrypt"]),
+    ("--boundary", &["encrypt"]),
+    ("--generations", &["run"]),
+];
+
+/// The options of one of the commands.
+struct Options {
+    board: String,
+    parameters: String,
+    key_path: Option<String>,
+    board_profile: Option<BoardProfile>,
+    pattern_file: Option<String>,
+    size: Option<(usize, usize)>,
+    seed: Option<u64>,
+    rule: rule::Rule,
+    boundary: BoundaryCondition,
+    generations: usize,
+}
+
+impl Options {
+    fn parse(command: &str, mut args: impl Iterator<Item = String>) -> Self {
+        let mut board = None;
+        let mut parameters = DEFAULT_PARAMETERS.to_string();
+        let mut key_path = None;
+        let mut board_profile = None;
+        let mut pattern_file = None;
+        let mut size = None;
+        let mut seed = None;
+        let mut rule = rule::Rule::conway();
+        let mut boundary = BoundaryCondition::Toroidal;
+        let mut generations = 1;
+        while let Some(arg) = args.next() {
+            match OPTIONS.iter().find(|(option, _)| *option == arg) {
+                Some((_, commands)) if !commands.contains(&command) => {
+                    exit_with_usage(&format!("{} does not take {}", command, arg))
+                }
+                Some(_) => (),
+                None => exit_with_usage(&format!("unknown option for {}: {}", command, arg)),
+            }
+            match arg.as_str() {
+                "--board" => board = Some(parse_value(&arg, args.next())),
+                "--params" => {
+                    parameters = parse_value(&arg, args.next());
+                    if let Err(err) = keys::parameters(&parameters) {
+                        exit_with_usage(&err);
+                    }
+                }
+                "--keys" => key_path = Some(parse_value(&arg, args.next())),
+                "--board-profile" => board_profile = Some(parse_value(&arg, args.next())),
+                "--pattern-file" => pattern_file = Some(parse_value(&arg, args.next())),
+                "--size" => size = Some(parse_size(&arg, args.next())),
+                "--seed" => seed = Some(parse_value(&arg, args.next())),
+                "--rule" => {
+                    let value: String = parse_value(&arg, args.next());
+                    rule = value
+                        .parse()
+                        .unwrap_or_else(|err: String| exit_with_usage(&err));
+                }
+                "--boundary" => boundary = parse_value(&arg, args.next()),
+                "--generations" => generations = parse_value(&arg, args.next()),
+                _ => unreachable!(),
+            }
+        }
+        let Some(board) = board else {
+            exit_with_usage(&format!("{} needs --board", command));
+        };
+        if command != "run" && key_path.is_none() {
+            exit_with_usage(&format!("{} needs --keys", command));
+        }
+        if board_profile.is_some() == pattern_file.is_some() && command == "encrypt" {
+            exit_with_usage("encrypt needs one of --board-profile and --pattern-file");
+        }
+        if seed.is_some() && board_profile.is_none() {
+            exit_with_usage("--seed needs --board-profile");
+        }
+        Self {
+            board,
+            parameters,
+            key_path,
+            board_profile,
+            pattern_file,
+            size,
+            seed,
+            rule,
+            boundary,
+            generations,
+        }
+    }
+}
+
+/// Runs the command, one of `COMMANDS`, with the arguments that follow it.
+pub fn main(command: &str, args: impl Iterator<Item = String>) {
+    let options = Options::parse(command, args);
+    match command {
+        "encrypt" => encrypt(&options),
+        "run" => run(&options),
+        "decrypt" => decrypt(&options),
+        _ => unreachable!(),
+    }
+}
+
+/// The cells of the board to encrypt, and its dimensions.
+fn initial_cells(options: &Options) -> (Vec<bool>, (usize, usize)) {
+    if let Some(profile) = &options.board_profile {
+        let size = options.size.unwrap_or(DEFAULT_SIZE);
+        let seed = options.seed.unwrap_or_else(os_seed);
+        return (benchgen::generate(profile, size, seed), size);
+    }
+    let path = options.pattern_file.as_ref().unwrap();
+    let file = patternfile::PatternFile::load(Path::new(path))
+        .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+    let (pattern_rows, pattern_cols) = file.dimensions;
+    let size = options.size.unwrap_or((
+        pattern_rows + 2 * pattern::CATALOG_MARGIN,
+        pattern_cols + 2 * pattern::CATALOG_MARGIN,
+    ));
+    let cells = pattern::place(&file.cells, pattern_cols, size, None)
+        .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", path, err)));
+    (cells, size)
+}
+
+fn encrypt(options: &Options) {
+    let (cells, (_, n_cols)) = initial_cells(options);
+    let key_path = options.key_path.as_ref().unwrap();
+    let (client_key, server_key) = load_keys(key_path, &options.parameters, false);
+    let encrypt = |alive| FheBool::encrypt(alive, &client_key);
+    let zeros = (encrypt(false), encrypt(false), encrypt(false));
+    let board = Board::new(n_cols, cells.into_iter().map(encrypt).collect(), zeros)
+        .with_rule(options.rule)
+        .with_boundary(options.boundary);
+    let path = Path::new(&options.board);
+    server::hand_over(&board, path, &options.parameters, &server_key)
+        .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", options.board, err)));
+    println!(
+        "Board written to {}, server key to {}",
+        options.board,
+        server::server_key_path(path).display()
+    );
+}
+
+fn run(options: &Options) {
+    let mut update_start = Instant::now();
+    let board = server::run(
+        Path::new(&options.board),
+        &options.parameters,
+        options.generations,
+        |board| {
+            println!(
+                "Generation {} written, Time to update: {:.3?}",
+                board.generation(),
+                update_start.elapsed()
+            );
+            update_start = Instant::now();
+        },
+    )
+    .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", options.board, err)));
+    println!("Board at generation {}", board.generation());
+}
+
+fn decrypt(options: &Options) {
+    let key_path = options.key_path.as_ref().unwrap();
+    let (client_key, _) = keys::load(Path::new(key_path), &options.parameters)
+        .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", key_path, err)));
+    let board = Board::load(Path::new(&options.board), &options.parameters)
+        .unwrap_or_else(|err| exit_with_usage(&format!("{}: {}", options.board, err)));
+    let cells = board.decrypt(&client_key);
+    let (_, n_cols) = board.dimensions();
+    println!("Generation {}", board.generation());
+    print_grid(&cells, n_cols);
+    print_coords(&cells, n_cols);
+}
@@ -0,0 +1,355 @@
+//! Summary statistics over the per-generation update times, and the CPU
+//! time of the process to compare them with.
+//!
+//! The mean and variance hide stragglers, so the times are also counted in a
+//! histogram of log-scaled buckets, from which the percentiles are read.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Coefficient of variation above which the timings are reported as noisy.
+const NOISY_CV: f64 = 0.1;
+
+/// Mean and spread of a series of update times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingStats {
+    pub samples: usize,
+    /// Mean time, in seconds.
+    pub mean: f64,
+    /// Variance of the times, in seconds squared.
+    pub variance: f64,
+    /// Mean CPU time of the process, all threads together, in seconds.
+    pub cpu_mean: Option<f64>,
+}
+
+impl TimingStats {
+    /// Returns `None` if there are no timings.
+    pub fn from_durations(durations: &[Duration]) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+
+        let samples = durations.len();
+        let mean = durations.iter().map(Duration::as_secs_f64).sum::<f64>() / samples as f64;
+        let variance = durations
+            .iter()
+            .map(|d| (d.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / samples as f64;
+
+        Some(Self {
+            samples,
+            mean,
+            variance,
+            cpu_mean: None,
+        })
+    }
+
+    /// Adds the CPU time used over all the samples.
+    pub fn with_cpu_time(self, total: Duration) -> Self {
+        Self {
+            cpu_mean: Some(total.as_secs_f64() / self.samples as f64),
+            ..self
+        }
+    }
+
+    /// CPU time over wall-clock time: how many cores were busy on average.
+    pub fn parallelism(&self) -> Option<f64> {
+        self.cpu_mean
+            .filter(|_| self.mean > 0.0)
+            .map(|cpu_mean| cpu_mean / self.mean)
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// Standard deviation relative to the mean.
+    pub fn coefficient_of_variation(&self) -> f64 {
+        if self.mean == 0.0 {
+            0.0
+        } else {
+            self.std_dev() / self.mean
+        }
+    }
+
+    /// High variance usually means the timings were disturbed by other
+    /// processes or by thermal throttling rather than by the computation.
+    pub fn is_noisy(&self) -> bool {
+        self.coefficient_of_variation() > NOISY_CV
+    }
+}
+
+impl fmt::Display for TimingStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} generations: mean {:.3?}, std dev {:.3?}, variance {:.3e} s², cv {:.1}%",
+            self.samples,
+            Duration::from_secs_f64(self.mean),
+            Duration::from_secs_f64(self.std_dev()),
+            self.variance,
+            100.0 * self.coefficient_of_variation(),
+        )?;
+        if let (Some(cpu_mean), Some(parallelism)) = (self.cpu_mean, self.parallelism()) {
+            write!(
+                f,
+                ", CPU time {:.3?} (parallelism {:.1})",
+                Duration::from_secs_f64(cpu_mean),
+                parallelism
+            )?;
+        }
+        if self.is_noisy() {
+            write!(f, " (high variance, timings are unreliable)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Upper bound of the first bucket of `LatencyHistogram`, the others doubling
+/// up to `FIRST_BUCKET << (N_BUCKETS - 2)`, about 65 s, and the last one
+/// holding everything slower.
+const FIRST_BUCKET: Duration = Duration::from_millis(1);
+const N_BUCKETS: usize = 18;
+
+/// Counts of durations in buckets of doubling widths.
+///
+/// Percentiles are only known to the bucket: they are reported as the upper
+/// bound of their bucket, or the slowest time if it is lower.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    counts: [usize; N_BUCKETS],
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    /// Index of the bucket holding `duration`.
+    fn bucket(duration: Duration) -> usize {
+        let mut bucket = 0;
+        while bucket < N_BUCKETS - 1 && duration >= FIRST_BUCKET * (1 << bucket) {
+            bucket += 1;
+        }
+        bucket
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        self.counts[Self::bucket(duration)] += 1;
+        self.max = self.max.max(duration);
+    }
+
+    pub fn count(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The time under which a fraction `q` of the durations fall, rounded up
+    /// to the bucket. `None` without durations.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let rank = ((q * self.count() as f64).ceil() as usize).max(1);
+        let mut seen = 0;
+        let bucket = self.counts.iter().position(|count| {
+            seen += count;
+            seen >= rank
+        })?;
+        if bucket == N_BUCKETS - 1 {
+            return Some(self.max);
+        }
+        Some(self.max.min(FIRST_BUCKET * (1 << bucket)))
+    }
+
+    /// The counts of the buckets from the fastest to the slowest duration,
+    /// drawn as bars of 8 heights.
+    pub fn sparkline(&self) -> String {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let (Some(first), Some(last)) = (
+            self.counts.iter().position(|&count| count > 0),
+            self.counts.iter().rposition(|&count| count > 0),
+        ) else {
+            return String::new();
+        };
+        let highest = *self.counts.iter().max().unwrap();
+        self.counts[first..=last]
+            .iter()
+            .map(|&count| match count {
+                0 => ' ',
+                _ => BARS[(count * BARS.len() - 1) / highest],
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<Duration> for LatencyHistogram {
+    fn from_iter<I: IntoIterator<Item = Duration>>(durations: I) -> Self {
+        let mut histogram = Self::default();
+        for duration in durations {
+            histogram.record(duration);
+        }
+        histogram
+    }
+}
+
+impl fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (Some(p50), Some(p95), Some(p99)) =
+            (self.quantile(0.5), self.quantile(0.95), self.quantile(0.99))
+        else {
+            return write!(f, "no samples");
+        };
+        write!(
+            f,
+            "p50 <= {:.3?}, p95 <= {:.3?}, p99 <= {:.3?} [{}]",
+            p50,
+            p95,
+            p99,
+            self.sparkline()
+        )
+    }
+}
+
+/// User and system CPU time used so far by all the threads of the process,
+/// or `None` where it cannot be measured.
+#[cfg(unix)]
+pub fn process_cpu_time() -> Option<Duration> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage only writes to the struct it is given.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: the call succeeded, so the struct is initialized.
+    let usage = unsafe { usage.assume_init() };
+
+    let time = |t: libc::timeval| Duration::new(t.tv_sec as u64, 1000 * t.tv_usec as u32);
+    Some(time(usage.ru_utime) + time(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+pub fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Measures the wall-clock and CPU time of a piece of work.
+pub struct Stopwatch {
+    wall: std::time::Instant,
+    cpu: Option<Duration>,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self {
+            wall: std::time::Instant::now(),
+            cpu: process_cpu_time(),
+        }
+    }
+
+    /// Wall-clock time since the start, and CPU time when it is available.
+    pub fn elapsed(&self) -> (Duration, Option<Duration>) {
+        let cpu = self
+            .cpu
+            .zip(process_cpu_time())
+            .map(|(start, now)| now.saturating_sub(start));
+        (self.wall.elapsed(), cpu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_stats() {
+        assert_eq!(TimingStats::from_durations(&[]), None);
+
+        let stats = TimingStats::from_durations(&[
+            Duration::from_millis(900),
+            Duration::from_millis(1100),
+            Duration::from_millis(1000),
+            Duration::from_millis(1000),
+        ])
+        .unwrap();
+
+        assert_eq!(stats.samples, 4);
+        assert!((stats.mean - 1.0).abs() < 1e-9);
+        assert!((stats.variance - 0.005).abs() < 1e-9);
+        assert!((stats.coefficient_of_variation() - 0.005f64.sqrt()).abs() < 1e-9);
+        assert!(!stats.is_noisy());
+    }
+
+    #[test]
+    fn test_timing_stats_flags_noise() {
+        let stats =
+            TimingStats::from_durations(&[Duration::from_millis(500), Duration::from_millis(1500)])
+                .unwrap();
+
+        assert!(stats.is_noisy());
+        assert!(stats
+            .to_string()
+            .ends_with("(high variance, timings are unreliable)"));
+    }
+
+    #[test]
+    fn test_cpu_time() {
+        let stats =
+            TimingStats::from_durations(&[Duration::from_secs(2), Duration::from_secs(2)]).unwrap();
+        assert_eq!(stats.parallelism(), None);
+        assert!(!stats.to_string().contains("CPU"));
+
+        let stats = stats.with_cpu_time(Duration::from_secs(12));
+        assert_eq!(stats.cpu_mean, Some(6.0));
+        assert_eq!(stats.parallelism(), Some(3.0));
+        assert!(stats
+            .to_string()
+            .ends_with(", CPU time 6.000s (parallelism 3.0)"));
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let bucket = |ms: u64| LatencyHistogram::bucket(Duration::from_millis(ms));
+        assert_eq!(LatencyHistogram::bucket(Duration::from_micros(999)), 0);
+        assert_eq!(bucket(1), 1);
+        assert_eq!(bucket(2), 2);
+        assert_eq!(bucket(3), 2);
+        assert_eq!(bucket(4), 3);
+        assert_eq!(bucket(65_535), 16);
+        assert_eq!(bucket(65_536), 17);
+        assert_eq!(bucket(3_600_000), 17);
+    }
+
+    #[test]
+    fn test_histogram_quantiles() {
+        assert_eq!(LatencyHistogram::default().quantile(0.5), None);
+        assert_eq!(LatencyHistogram::default().to_string(), "no samples");
+
+        // 98 generations around 100 ms and two stragglers
+        let mut durations = vec![Duration::from_millis(100); 98];
+        durations.push(Duration::from_secs(3));
+        durations.push(Duration::from_secs(100));
+        let histogram: LatencyHistogram = durations.into_iter().collect();
+
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_millis(128)));
+        assert_eq!(histogram.quantile(0.95), Some(Duration::from_millis(128)));
+        assert_eq!(histogram.quantile(0.99), Some(Duration::from_millis(4096)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_secs(100)));
+        assert_eq!(histogram.sparkline(), "█    ▁    ▁");
+
+        let fast: LatencyHistogram = [Duration::from_micros(300)].into_iter().collect();
+        assert_eq!(fast.quantile(0.99), Some(Duration::from_micros(300)));
+    }
+
+    #[test]
+    fn test_stopwatch() {
+        let stopwatch = Stopwatch::start();
+        let mut x = 0u64;
+        for k in 0..2_000_000u64 {
+            x = x.wrapping_add(k * k);
+        }
+        std::hint::black_box(x);
+        let (wall, cpu) = stopwatch.elapsed();
+
+        assert!(wall > Duration::ZERO);
+        if cfg!(unix) {
+            assert!(cpu.is_some());
+        } else {
+            assert_eq!(cpu, None);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Where the files of a run are kept, behind the `Storage` trait so that
+//! they do not have to be on the local disk: a directory (`LocalDir`), or an
+//! object store, of which `Memory` is an in-process stand-in.
+//!
+//! Object stores cannot rename, so a store only promises that every `put` is
+//! atomic on its own: readers see the previous object or the new one whole,
+//! never part of it. Writes of several objects are published by a last small
+//! object listing them, like the index of a chain of checkpoints (see
+//! `checkpoint`), and objects are only deleted once no such listing names
+//! them.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A flat namespace of objects holding bytes.
+pub trait Storage {
+    /// Writes the bytes of `body` as the object `key`, replacing the one
+    /// there. Readers see the previous object until the new one is whole.
+    fn put(&self, key: &str, body: &mut dyn Read) -> io::Result<()>;
+
+    /// Reads the object `key`, or fails with `ErrorKind::NotFound`.
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + '_>>;
+
+    /// The keys of the objects, sorted.
+    fn list(&self) -> io::Result<Vec<String>>;
+
+    /// Deletes the object `key`, if there is one.
+    fn delete(&self, key: &str) -> io::Result<()>;
+
+    /// Where the object `key` is, for error messages.
+    fn location(&self, key: &str) -> String;
+}
+
+/// Opens the storage at `url`: `file://<directory>`, or a directory path,
+/// created if needed.
+pub fn open(url: &str) -> Result<Box<dyn Storage>, String> {
+    let path = match url.split_once("://") {
+        None => url,
+        Some(("file", path)) => path,
+        Some((scheme, _)) => return Err(format!("unsupported storage '{}://'", scheme)),
+    };
+    match LocalDir::create(Path::new(path)) {
+        Ok(storage) => Ok(Box::new(storage)),
+        Err(err) => Err(format!("{}: {}", path, err)),
+    }
+}
+
+/// Objects as files of a directory.
+#[derive(Debug, Clone)]
+pub struct LocalDir {
+    root: PathBuf,
+}
+
+impl LocalDir {
+    /// The objects of `root`, created if needed.
+    pub fn create(root: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(root)?;
+        Ok(Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        assert!(
+            !key.is_empty() && !key.contains(['/', '\\']) && !key.ends_with(".partial"),
+            "invalid key '{}'",
+            key
+        );
+        self.root.join(key)
+    }
+}
+
+impl Storage for LocalDir {
+    /// Writes the file next to its path and renames it, as `keys::write_file`
+    /// does.
+    fn put(&self, key: &str, body: &mut dyn Read) -> io::Result<()> {
+        let path = self.path(key);
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = Path::new(&partial);
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        let mut file = BufWriter::new(options.open(partial)?);
+        io::copy(body, &mut file)?;
+        file.flush()?;
+        file.into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        std::fs::rename(partial, path)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(BufReader::new(File::open(self.path(key))?)))
+    }
+
+    fn list(&self) -> io::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            // files being written, and whatever is not ours
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if entry.file_type()?.is_file() && !name.ends_with(".partial") {
+                keys.push(name);
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn location(&self, key: &str) -> String {
+        self.path(key).display().to_string()
+    }
+}
+
+/// Objects in memory, with the guarantees of an object store and no more,
+/// to test what is written to one.
+#[derive(Debug, Default)]
+pub struct Memory {
+    objects: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl Storage for Memory {
+    fn put(&self, key: &str, body: &mut dyn Read) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        body.read_to_end(&mut bytes)?;
+        self.objects.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + '_>> {
+        match self.objects.lock().unwrap().get(key) {
+            Some(bytes) => Ok(Box::new(io::Cursor::new(bytes.clone()))),
+            None => Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no object '{}'", key),
+            )),
+        }
+    }
+
+    fn list(&self) -> io::Result<Vec<String>> {
+        Ok(self.objects.lock().unwrap().keys().cloned().collect())
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn location(&self, key: &str) -> String {
+        format!("memory:{}", key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Interrupted;
+
+    fn read(storage: &dyn Storage, key: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        storage.get(key).unwrap().read_to_end(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn check_storage(storage: &dyn Storage) {
+        assert_eq!(storage.list().unwrap(), Vec::<String>::new());
+        storage.put("b", &mut &b"first"[..]).unwrap();
+        storage.put("a", &mut &b""[..]).unwrap();
+        storage.put("b", &mut &b"second"[..]).unwrap();
+        assert_eq!(read(storage, "b"), b"second");
+        assert_eq!(read(storage, "a"), b"");
+        assert_eq!(storage.list().unwrap(), ["a", "b"]);
+
+        storage.delete("b").unwrap();
+        storage.delete("missing").unwrap();
+        assert_eq!(storage.list().unwrap(), ["a"]);
+        assert_eq!(storage.get("b").err().unwrap().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_local_dir() {
+        let root = std::env::temp_dir().join(format!("storage-{}", std::process::id()));
+        let storage = LocalDir::create(&root).unwrap();
+        check_storage(&storage);
+        // a write that was interrupted
+        std::fs::write(root.join("c.partial"), b"part").unwrap();
+        assert_eq!(storage.list().unwrap(), ["a"]);
+        assert_eq!(storage.location("a"), root.join("a").display().to_string());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_memory() {
+        let storage = Memory::default();
+        check_storage(&storage);
+        assert_eq!(storage.location("a"), "memory:a");
+
+        let interrupted = Interrupted::new(storage, 1);
+        interrupted.put("c", &mut &b"c"[..]).unwrap();
+        assert!(interrupted.put("d", &mut &b"d"[..]).is_err());
+        assert!(interrupted.delete("c").is_err());
+        assert_eq!(interrupted.list().unwrap(), ["a", "c"]);
+    }
+
+    #[test]
+    fn test_open() {
+        let root = std::env::temp_dir().join(format!("storage-url-{}", std::process::id()));
+        let url = format!("file://{}", root.display());
+        open(&url).unwrap().put("a", &mut &b"a"[..]).unwrap();
+        assert_eq!(read(&*open(root.to_str().unwrap()).unwrap(), "a"), b"a");
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            open("s3://bucket/run").err().unwrap(),
+            "unsupported storage 's3://'"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Rows streamed into a board from one edge, one per generation, the board
+//! scrolling away from that edge.
+//!
+//! The edges keep the boundary conditions of the board, scrolling or not:
+//! on a toroidal board the row that enters is still the neighbour of the row
+//! on the opposite edge, while beyond the edges of a fixed or mirrored board
+//! it sees dead cells or itself.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// The edge rows enter the board from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// The board scrolls down and the bottom row drops off.
+    Top,
+    /// The board scrolls up and the top row drops off.
+    Bottom,
+}
+
+impl FromStr for Edge {
+    type Err = String;
+
+    fn from_str(edge: &str) -> Result<Self, Self::Err> {
+        match edge {
+            "top" => Ok(Self::Top),
+            "bottom" => Ok(Self::Bottom),
+            _ => Err(format!("unknown edge: {}", edge)),
+        }
+    }
+}
+
+/// A row does not have as many cells as the board has columns.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RowLengthMismatch {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for RowLengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a row of {} cells cannot enter a board of {} columns",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl Error for RowLengthMismatch {}
+
+/// The cells that drop off when scrolling, and the index at which the row
+/// that enters is then inserted.
+pub fn scroll_ranges(dimensions: (usize, usize), edge: Edge) -> (Range<usize>, usize) {
+    let (n_rows, n_cols) = dimensions;
+    let last_row = (n_rows - 1) * n_cols;
+    match edge {
+        Edge::Top => (last_row..last_row + n_cols, 0),
+        Edge::Bottom => (0..n_cols, last_row),
+    }
+}
+
+/// Scrolls plaintext cells, the plaintext model of `Board::scroll_in_row`.
+pub fn scroll_in_row(
+    cells: &[bool],
+    dimensions: (usize, usize),
+    row: &[bool],
+    edge: Edge,
+) -> Result<Vec<bool>, RowLengthMismatch> {
+    if row.len() != dimensions.1 {
+        return Err(RowLengthMismatch {
+            expected: dimensions.1,
+            actual: row.len(),
+        });
+    }
+    let (dropped, entered_at) = scroll_ranges(dimensions, edge);
+    let mut scrolled = cells.to_vec();
+    scrolled.drain(dropped);
+    scrolled.splice(entered_at..entered_at, row.iter().copied());
+    Ok(scrolled)
+}
+
+/// Rows to stream into a board, read from a drawing with one row per line,
+/// `#` for live cells and `.` for dead ones.
+pub struct Strip {
+    rows: Vec<Vec<bool>>,
+    cursor: usize,
+}
+
+impl Strip {
+    /// Parses the drawing, whose rows must be `n_cols` wide. Blank lines are
+    /// skipped.
+    pub fn parse(drawing: &str, n_cols: usize) -> Result<Self, RowLengthMismatch> {
+        let rows = drawing
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                if line.chars().count() == n_cols {
+                    Ok(line.chars().map(|c| c == '#').collect())
+                } else {
+                    Err(RowLengthMismatch {
+                        expected: n_cols,
+                        actual: line.chars().count(),
+                    })
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rows, cursor: 0 })
+    }
+
+    /// The next row to stream in, `None` once they have all entered.
+    pub fn next_row(&mut self) -> Option<&[bool]> {
+        let row = self.rows.get(self.cursor)?;
+        self.cursor += 1;
+        Some(row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroll_in_row() {
+        let cells = [true, false, false, true, true, true];
+
+        let top = scroll_in_row(&cells, (3, 2), &[false, false], Edge::Top).unwrap();
+        assert_eq!(top, vec![false, false, true, false, false, true]);
+
+        let bottom = scroll_in_row(&cells, (3, 2), &[true, false], Edge::Bottom).unwrap();
+        assert_eq!(bottom, vec![false, true, true, true, true, false]);
+
+        assert_eq!(
+            scroll_in_row(&cells, (3, 2), &[true], Edge::Top),
+            Err(RowLengthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_strip() {
+        let mut strip = Strip::parse("#..\n\n.#.\n", 3).unwrap();
+        assert_eq!(strip.next_row(), Some(&[true, false, false][..]));
+        assert_eq!(strip.next_row(), Some(&[false, true, false][..]));
+        assert_eq!(strip.next_row(), None);
+
+        assert_eq!(
+            Strip::parse("#..\n.#", 3).err(),
+            Some(RowLengthMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_edge() {
+        assert_eq!("top".parse(), Ok(Edge::Top));
+        assert_eq!("bottom".parse(), Ok(Edge::Bottom));
+        assert!("left".parse::<Edge>().is_err());
+    }
+}
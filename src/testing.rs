@@ -0,0 +1,231 @@
+//! Helpers for the tests: keys, encrypted boards, and board comparison with
+//! readable failure output.
+
+use crate::storage::Storage;
+use crate::Board;
+use concrete::prelude::*;
+use concrete::{generate_keys, set_server_key, ClientKey, ConfigBuilder, FheBool, ServerKey};
+use std::cell::Cell;
+use std::fmt::Write;
+use std::io::{self, Read};
+
+/// Maximum number of mismatching cells listed in a failure report.
+const MAX_LISTED_MISMATCHES: usize = 8;
+
+/// Generates keys with the default boolean parameters.
+pub fn default_keys() -> (ClientKey, ServerKey) {
+    generate_keys(ConfigBuilder::all_disabled().enable_default_bool().build())
+}
+
+/// Generates keys with the default boolean parameters and sets the server key
+/// of this thread, to update boards encrypted with the client key returned.
+pub fn set_up_keys() -> ClientKey {
+    let (client_key, server_key) = default_keys();
+    set_server_key(server_key);
+    client_key
+}
+
+/// Encrypts `cells`, in rows of `n_cols`, into a board.
+pub fn encrypt_board(cells: &[bool], n_cols: usize, client_key: &ClientKey) -> Board {
+    let encrypt = |alive| FheBool::encrypt(alive, client_key);
+    let zeros = (encrypt(false), encrypt(false), encrypt(false));
+    Board::new(
+        n_cols,
+        cells.iter().map(|&alive| encrypt(alive)).collect(),
+        zeros,
+    )
+}
+
+/// Builds the failure report for two boards, or `None` when they are equal.
+///
+/// Both frames are rendered side by side; in the `actual` frame a `+` marks a
+/// cell that should be dead and a `-` a cell that should be alive.
+fn diff_report(
+    expected: &[bool],
+    actual: &[bool],
+    dimensions: (usize, usize),
+    context: &str,
+) -> Option<String> {
+    let (n_rows, n_cols) = dimensions;
+    assert_eq!(
+        expected.len(),
+        n_rows * n_cols,
+        "expected board has wrong size"
+    );
+    assert_eq!(actual.len(), n_rows * n_cols, "actual board has wrong size");
+
+    let mismatches: Vec<usize> = (0..expected.len())
+        .filter(|&k| expected[k] != actual[k])
+        .collect();
+    if mismatches.is_empty() {
+        return None;
+    }
+
+    let mut report = String::new();
+    writeln!(
+        report,
+        "boards differ ({}): {} of {} cells mismatch",
+        context,
+        mismatches.len(),
+        expected.len()
+    )
+    .unwrap();
+    for &k in mismatches.iter().take(MAX_LISTED_MISMATCHES) {
+        writeln!(
+            report,
+            "  ({}, {}): expected {}, got {}",
+            k / n_cols,
+            k % n_cols,
+            if expected[k] { "alive" } else { "dead" },
+            if actual[k] { "alive" } else { "dead" },
+        )
+        .unwrap();
+    }
+    if mismatches.len() > MAX_LISTED_MISMATCHES {
+        writeln!(
+            report,
+            "  ... and {} more",
+            mismatches.len() - MAX_LISTED_MISMATCHES
+        )
+        .unwrap();
+    }
+
+    writeln!(report, "{:<width$}   actual", "expected", width = n_cols).unwrap();
+    for i in 0..n_rows {
+        for j in 0..n_cols {
+            report.push(if expected[i * n_cols + j] {
+                '█'
+            } else {
+                '░'
+            });
+        }
+        report.push_str("   ");
+        for j in 0..n_cols {
+            report.push(match (expected[i * n_cols + j], actual[i * n_cols + j]) {
+                (false, true) => '+',
+                (true, false) => '-',
+                (_, true) => '█',
+                (_, false) => '░',
+            });
+        }
+        report.push('\n');
+    }
+
+    Some(report)
+}
+
+/// Asserts that two decrypted boards are equal, printing both frames and the
+/// first mismatching cells otherwise.
+///
+/// `context` should say which generation, pattern, ... is being compared.
+pub fn assert_boards_eq(
+    expected: &[bool],
+    actual: &[bool],
+    dimensions: (usize, usize),
+    context: &str,
+) {
+    if let Some(report) = diff_report(expected, actual, dimensions, context) {
+        panic!("{}", report);
+    }
+}
+
+/// A storage failing every write after the first `writes`, as if the run was
+/// interrupted there.
+pub struct Interrupted<S> {
+    pub storage: S,
+    pub writes: Cell<usize>,
+}
+
+impl<S> Interrupted<S> {
+    pub fn new(storage: S, writes: usize) -> Self {
+        Self {
+            storage,
+            writes: Cell::new(writes),
+        }
+    }
+
+    fn write(&self) -> io::Result<()> {
+        match self.writes.get() {
+            0 => Err(io::Error::other("interrupted")),
+            writes => {
+                self.writes.set(writes - 1);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<S: Storage> Storage for Interrupted<S> {
+    fn put(&self, key: &str, body: &mut dyn Read) -> io::Result<()> {
+        self.write()?;
+        self.storage.put(key, body)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + '_>> {
+        self.storage.get(key)
+    }
+
+    fn list(&self) -> io::Result<Vec<String>> {
+        self.storage.list()
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        self.write()?;
+        self.storage.delete(key)
+    }
+
+    fn location(&self, key: &str) -> String {
+        self.storage.location(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_report_equal_boards() {
+        let board = vec![true, false, false, true];
+        assert!(diff_report(&board, &board, (2, 2), "equal").is_none());
+    }
+
+    #[test]
+    fn test_diff_report_highlights_mismatches() {
+        let expected = vec![true, false, false, true];
+        let actual = vec![false, true, false, true];
+
+        let report = diff_report(&expected, &actual, (2, 2), "generation 3").unwrap();
+
+        assert!(report.starts_with("boards differ (generation 3): 2 of 4 cells mismatch\n"));
+        assert!(report.contains("  (0, 0): expected alive, got dead\n"));
+        assert!(report.contains("  (0, 1): expected dead, got alive\n"));
+        assert!(report.contains("█░   -+\n░█   ░█\n"));
+    }
+
+    #[test]
+    fn test_diff_report_truncates_large_frames() {
+        let expected = vec![false; 64 * 64];
+        let mut actual = expected.clone();
+        for k in (0..actual.len()).step_by(64) {
+            actual[k] = true;
+        }
+
+        let report = diff_report(&expected, &actual, (64, 64), "64x64").unwrap();
+
+        assert!(report.contains("64 of 4096 cells mismatch"));
+        assert_eq!(
+            report.matches(": expected dead, got alive").count(),
+            MAX_LISTED_MISMATCHES
+        );
+        assert!(report.contains("  (7, 0): expected dead, got alive\n"));
+        assert!(!report.contains("(8, 0)"));
+        assert!(report.contains("  ... and 56 more\n"));
+        assert_eq!(
+            report
+                .lines()
+                .filter(|l| l.chars().count() == 64 + 3 + 64)
+                .count(),
+            64
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Decrypted intermediate values of a cell update, to diagnose results that
+//! differ from the plaintext game.
+
+use crate::rule::Rule;
+use std::fmt;
+
+/// Number of bits decrypted for every cell of a traced update: the cell,
+/// its neighbours, the sum and the new state.
+pub const DECRYPTED_BITS: usize = 13;
+
+/// Everything that went into the new state of one cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellTrace {
+    pub row: usize,
+    pub col: usize,
+    /// Generation the cell was updated from.
+    pub generation: usize,
+    pub alive: bool,
+    /// Neighbours from the top left to the bottom right, row by row.
+    pub neighbours: [bool; 8],
+    /// Decrypted accumulator bits, least significant first.
+    pub sum_bits: [bool; 3],
+    pub next: bool,
+    /// Rule the board plays, which the new state is checked against.
+    pub rule: Rule,
+}
+
+impl CellTrace {
+    /// Value of the accumulator, 8 neighbours wrapping to 0.
+    pub fn sum(&self) -> u8 {
+        self.sum_bits
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (bit, set)| acc | (u8::from(*set) << bit))
+    }
+
+    /// Number of live neighbours, as the plaintext game counts them.
+    pub fn expected_sum(&self) -> u8 {
+        self.neighbours.iter().filter(|alive| **alive).count() as u8 % 8
+    }
+
+    /// State the plaintext game gives the cell.
+    pub fn expected_next(&self) -> bool {
+        let count = self.neighbours.iter().filter(|alive| **alive).count();
+        self.rule.next_state(self.alive, count)
+    }
+}
+
+impl fmt::Display for CellTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mark = |alive: bool| if alive { '#' } else { '.' };
+        let n = self.neighbours.map(mark);
+
+        writeln!(
+            f,
+            "cell ({}, {}) from generation {}: expected {}, got {}",
+            self.row,
+            self.col,
+            self.generation,
+            if self.expected_next() {
+                "alive"
+            } else {
+                "dead"
+            },
+            if self.next { "alive" } else { "dead" },
+        )?;
+        writeln!(f, "  neighbourhood  {}{}{}", n[0], n[1], n[2])?;
+        writeln!(f, "                 {}{}{}", n[3], mark(self.alive), n[4])?;
+        writeln!(f, "                 {}{}{}", n[5], n[6], n[7])?;
+        writeln!(
+            f,
+            "  sum {} (bits {}{}{}), expected {}",
+            self.sum(),
+            u8::from(self.sum_bits[2]),
+            u8::from(self.sum_bits[1]),
+            u8::from(self.sum_bits[0]),
+            self.expected_sum(),
+        )?;
+        write!(f, "  rule {}", self.rule)
+    }
+}
+
+/// The first cell, in row-major order, whose new state is not the one of the
+/// plaintext game.
+pub fn first_mismatch(traces: &[CellTrace]) -> Option<&CellTrace> {
+    traces
+        .iter()
+        .find(|trace| trace.next != trace.expected_next())
+}
+
+/// One line per trace of the same cell, for `--trace-cell`: the generation,
+/// the neighbourhood row by row with the cell in the middle, the sum and the
+/// new state, flagged when it is not the one of the plaintext game.
+pub fn render_table(traces: &[CellTrace]) -> String {
+    let mark = |alive: bool| if alive { '#' } else { '.' };
+    let mut table = String::from("generation  neighbourhood  sum  next\n");
+    for trace in traces {
+        let n = trace.neighbours.map(mark);
+        table += &format!(
+            "{:>10}  {}{}{} {}{}{} {}{}{}    {:>3}  {}{}\n",
+            trace.generation,
+            n[0],
+            n[1],
+            n[2],
+            n[3],
+            mark(trace.alive),
+            n[4],
+            n[5],
+            n[6],
+            n[7],
+            trace.sum(),
+            mark(trace.next),
+            if trace.next == trace.expected_next() {
+                ""
+            } else {
+                "  MISMATCH"
+            }
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(neighbours: [bool; 8], sum: u8, next: bool) -> CellTrace {
+        CellTrace {
+            row: 1,
+            col: 2,
+            generation: 4,
+            alive: false,
+            neighbours,
+            sum_bits: [sum & 1 == 1, sum & 2 == 2, sum & 4 == 4],
+            next,
+            rule: Rule::conway(),
+        }
+    }
+
+    #[test]
+    fn test_first_mismatch() {
+        let birth = [true, true, true, false, false, false, false, false];
+        let traces = [
+            trace([false; 8], 0, false),
+            trace(birth, 3, true),
+            // the adder lost a neighbour
+            trace(birth, 2, false),
+        ];
+
+        let mismatch = first_mismatch(&traces).unwrap();
+        assert_eq!(mismatch, &traces[2]);
+        assert_eq!(mismatch.sum(), 2);
+        assert_eq!(mismatch.expected_sum(), 3);
+        assert_eq!(first_mismatch(&traces[..2]), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let birth = [true, true, true, false, false, false, false, false];
+
+        assert_eq!(
+            trace(birth, 2, false).to_string(),
+            "cell (1, 2) from generation 4: expected alive, got dead\n\
+             \x20 neighbourhood  ###\n\
+             \x20                ...\n\
+             \x20                ...\n\
+             \x20 sum 2 (bits 010), expected 3\n\
+             \x20 rule B3/S23"
+        );
+    }
+
+    #[test]
+    fn test_render_table() {
+        let birth = [true, true, true, false, false, false, false, false];
+
+        assert_eq!(
+            render_table(&[trace(birth, 3, true), trace(birth, 2, false)]),
+            "generation  neighbourhood  sum  next\n\
+             \x20        4  ### ... ...      3  #\n\
+             \x20        4  ### ... ...      2  .  MISMATCH\n"
+        );
+    }
+}
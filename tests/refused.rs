@@ -0,0 +1,37 @@
+//! Options refused by the binary before it generates any key.
+
+use std::process::Command;
+
+/// The error printed by a run with `args`, which must fail.
+fn refusal(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_homomorphic_game_of_life_2"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    stderr.lines().next().unwrap().to_string()
+}
+
+#[test]
+fn test_stream_in_cannot_resume() {
+    let refused = "--stream-in cannot be combined with --checkpoint or --load-board";
+    let stream_in = ["--stream-in", "rows.txt", "--keys", "keys.bin"];
+    assert_eq!(
+        refusal(&[&stream_in[..], &["--checkpoint", "board.bin"]].concat()),
+        refused
+    );
+    assert_eq!(
+        refusal(&[&stream_in[..], &["--load-board", "board.bin"]].concat()),
+        refused
+    );
+}
+
+#[test]
+fn test_extract_wider_than_board() {
+    let board = ["--board-profile", "soup:0.4", "--size", "3x40"];
+    assert_eq!(
+        refusal(&[&board[..], &["--extract", "1,20,5"]].concat()),
+        "--extract is wider than the board"
+    );
+}
@@ -0,0 +1,71 @@
+//! Replays a run from the seed manifest it wrote, through the binary.
+
+use homomorphic_game_of_life_2::seeds::SeedManifest;
+use std::path::Path;
+use std::process::Command;
+
+/// The lines of the output that change from one run to the next.
+const TIMINGS: [&str; 5] = [
+    "Key Generation time",
+    "Keys loaded",
+    "Time to update",
+    "Update time",
+    "Elapsed time",
+];
+
+/// The output of a run with `args`, without its timings.
+fn run(args: &[&str]) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_homomorphic_game_of_life_2"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| !TIMINGS.iter().any(|timing| line.starts_with(timing)))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_replay() {
+    let directory = std::env::temp_dir().join(format!("replay-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = |name: &str| directory.join(name).to_str().unwrap().to_string();
+    let (manifest, keys) = (path("seeds.json"), path("keys"));
+
+    // a random board, and cells sampled at random to estimate its population
+    let args = [
+        "--board-profile",
+        "soup:0.4",
+        "--size",
+        "3x4",
+        "--generations",
+        "1",
+        "--grid",
+        "--estimate-population",
+        "2",
+        "--keys",
+        &keys,
+        "--seed-manifest",
+        &manifest,
+    ];
+    let recorded = run(&[&args[..], &["--seed", "5"]].concat());
+    assert_eq!(
+        SeedManifest::load(Path::new(&manifest)).unwrap(),
+        SeedManifest::new(5)
+    );
+    let replayed = run(&args);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(recorded[0], format!("Seeds: {}", SeedManifest::new(5)));
+    assert!(recorded
+        .iter()
+        .any(|line| line.starts_with("estimated population")));
+    assert_eq!(replayed, recorded);
+}